@@ -0,0 +1,97 @@
+//! Signed license bundles for airgapped installs, delivered out of band:
+//!
+//! ```json
+//! {
+//!   "format": "alure-license-bundle",
+//!   "version": 1,
+//!   "key_fingerprint": "<hex SHA-256 of the raw ed25519 public key>",
+//!   "payload": "<base64 of the payload JSON>",
+//!   "signature": "<base64 ed25519 signature over the decoded payload>"
+//! }
+//! ```
+//!
+//! The payload is `{"receipt", "device_id", "activation_id"?, "project_id"?}`.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ed25519_dalek::{Signature, Verifier};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ReceiptError;
+use crate::receipt::ReceiptVerifier;
+use crate::storage::ReceiptRecord;
+
+pub const BUNDLE_FORMAT: &str = "alure-license-bundle";
+pub const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseBundle {
+    pub format: String,
+    pub version: u32,
+    pub key_fingerprint: String,
+    pub payload: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundlePayload {
+    pub receipt: String,
+    pub device_id: String,
+    #[serde(default)]
+    pub activation_id: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+impl LicenseBundle {
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, ReceiptError> {
+        let bundle: Self = serde_json::from_slice(bytes)
+            .map_err(|err| ReceiptError(format!("invalid_bundle: {err}")))?;
+        if bundle.format != BUNDLE_FORMAT || bundle.version != BUNDLE_VERSION {
+            return Err(ReceiptError(format!(
+                "invalid_bundle: unsupported format {} v{}",
+                bundle.format, bundle.version
+            )));
+        }
+        Ok(bundle)
+    }
+
+    /// Checks the fingerprint and signature against `verifier`'s keys, then
+    /// the contained receipt's own signature, and returns the record to
+    /// store. Errors: `bundle_key_mismatch`, `bundle_signature_mismatch`,
+    /// `invalid_bundle: ...` and the receipt's `invalid_signature`.
+    pub fn open(&self, verifier: &ReceiptVerifier) -> Result<ReceiptRecord, ReceiptError> {
+        let key = verifier
+            .key_by_fingerprint(&self.key_fingerprint)?
+            .ok_or_else(|| ReceiptError("bundle_key_mismatch".to_string()))?;
+        let payload = STANDARD
+            .decode(self.payload.trim())
+            .map_err(|err| ReceiptError(format!("invalid_bundle: payload: {err}")))?;
+        let signature = STANDARD
+            .decode(self.signature.trim())
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| ReceiptError("bundle_signature_mismatch".to_string()))?;
+        key.verify(&payload, &signature)
+            .map_err(|_| ReceiptError("bundle_signature_mismatch".to_string()))?;
+        let payload: BundlePayload = serde_json::from_slice(&payload)
+            .map_err(|err| ReceiptError(format!("invalid_bundle: payload: {err}")))?;
+        if !verifier.verify_signature(&payload.receipt)? {
+            return Err(ReceiptError("invalid_signature".to_string()));
+        }
+        let project_id = payload.project_id.or_else(|| {
+            verifier
+                .parse(&payload.receipt)
+                .ok()?
+                .get("project_id")?
+                .as_str()
+                .map(str::to_string)
+        });
+        Ok(ReceiptRecord {
+            receipt: payload.receipt,
+            device_id: payload.device_id,
+            activation_id: payload.activation_id,
+            project_id,
+            extra: Default::default(),
+        })
+    }
+}
@@ -1,9 +1,54 @@
+use chrono::{DateTime, Duration, Utc};
+use futures_util::stream::{self, Stream, StreamExt, TryStreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
 
-use crate::errors::{AlureError, ReceiptError, StorageError};
-use crate::receipt::{ReceiptValidationResult, ReceiptVerifier};
-use crate::storage::{FileStorage, ReceiptRecord};
+use crate::bundle::LicenseBundle;
+use crate::clock::{Clock, SystemClock};
+use crate::codec::{Base64UrlCodec, TokenCodec};
+use crate::device::{derive_device_id, DeviceIdSources};
+use crate::endpoints::Endpoints;
+use crate::errors::{exit_code, AlureError, LicenseErrorCode, ReceiptError, StorageError};
+use crate::http::{build_http_client, HttpOptions};
+use crate::key_source::KeySource;
+use crate::metrics::{ErrorKind, MetricsSink, Operation};
+use crate::receipt::{
+    ClaimDecryptor, DeviceHasher, PreHashedDeviceHasher, ReceiptValidationResult, ReceiptVerifier,
+    Sha256DeviceHasher,
+};
+use crate::refresh::{BackgroundTasks, DEFAULT_STATUS_RECHECK};
+use crate::storage::{
+    CachedKeys, EventRecord, FileStorage, GraceOverride, OnlineVerification, PendingActivation,
+    ReceiptRecord, ReceiptStorage, ResolvedKey,
+};
+use crate::transport::{
+    send_reqwest, BodySink, CappedBuffer, RateLimiter, Throttled, Transport, TransportRequest,
+    TransportResponse, MAX_ERROR_BODY,
+};
+
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const MAX_REDIRECTS: usize = 10;
+const JSON_MEDIA_TYPE: &str = "application/json";
+/// Upper bound for an `activate_with_diagnostics` attachment.
+pub const MAX_DIAGNOSTICS_BYTES: usize = 1024 * 1024;
+const ENV_BASE_URL: &str = "ALURE_BASE_URL";
+const ENV_PUBLIC_KEY: &str = "ALURE_PUBLIC_KEY";
+const ENV_TIMEOUT: &str = "ALURE_TIMEOUT";
+const ENV_HOME: &str = "ALURE_HOME";
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "www-authenticate",
+    "proxy-authenticate",
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivateResponse {
@@ -12,14 +57,385 @@ pub struct ActivateResponse {
     pub expires_at: Option<String>,
     pub grace_period_days: i64,
     pub server_time: String,
+    /// Set when the stored activation was returned without calling the
+    /// server (`server_time` is then empty).
+    #[serde(default)]
+    pub from_cache: bool,
+    /// The receipt's decoded payload; `None` when it could not be parsed.
+    #[serde(default)]
+    pub claims: Option<serde_json::Value>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// The receipt's `features`, or its module keys when it has none.
+    #[serde(default)]
+    pub features: Option<Vec<String>>,
+    /// The receipt's `tier`, falling back to its `plan`.
+    #[serde(default)]
+    pub tier: Option<String>,
+    /// `Idempotency-Key` sent with the activation; a retrying transport
+    /// resends the same request, so the server sees one key per call.
+    /// `None` for cached activations.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+}
+
+impl ActivateResponse {
+    /// `expires_at` parsed as RFC3339; `None` when absent or malformed.
+    pub fn expires_at_datetime(&self) -> Option<DateTime<Utc>> {
+        let expires_at = self.expires_at.as_deref()?;
+        DateTime::parse_from_rfc3339(expires_at)
+            .ok()
+            .map(|value| value.with_timezone(&Utc))
+    }
+
+    /// Time left until expiry (negative once expired); `None` for perpetual
+    /// or unparseable expiries.
+    pub fn time_remaining(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.expires_at_datetime()
+            .map(|expires_at| expires_at - now)
+    }
+}
+
+/// Result of `AlureClient::activate_or_queue`.
+#[derive(Debug, Clone)]
+pub enum ActivationOutcome {
+    Activated(ActivateResponse),
+    /// The server was unreachable; the activation is queued and nothing
+    /// was stored as a receipt.
+    Pending(PendingActivation),
+}
+
+/// How the stored receipt's device binding compares to this device, see
+/// `AlureClient::device_binding`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceBinding {
+    pub matches: bool,
+    /// Device id the receipt was stored for.
+    pub stored_device_id: String,
+    /// Device id derived for this machine now.
+    pub current_device_id: String,
+    /// `current_device_id` run through the configured hasher.
+    pub current_hash: String,
+    /// The receipt's `device_id_hash`; `None` when it has none.
+    pub receipt_hash: Option<String>,
+}
+
+/// Opt-in blob (crash logs, environment info) uploaded after activation by
+/// `activate_with_diagnostics`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Diagnostics {
+    /// An `application/octet-stream` attachment.
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            content_type: "application/octet-stream".to_string(),
+            data: data.into(),
+        }
+    }
+
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    Active,
+    Expired,
+    Revoked,
+    NotFound,
+    Grace,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyResult {
+    pub valid: bool,
+    pub status: VerifyStatus,
+    pub expires_at: Option<String>,
+    pub reason: Option<String>,
+    pub revoked: bool,
+    pub new_receipt: Option<String>,
+    pub server_time: Option<String>,
+    /// Grace period the server wants applied instead of the receipt's
+    /// claim; persisted by `verify_online` for later offline checks.
+    #[serde(default)]
+    pub grace_period_days: Option<i64>,
+    pub raw: serde_json::Value,
+    /// Response headers selected via `AlureClient::with_captured_headers`.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl VerifyResult {
+    /// Builds the typed result from a `/licenses/verify` response. When the
+    /// server omits `status`, it is inferred from `valid` and `revoked`.
+    pub fn from_value(raw: serde_json::Value) -> Self {
+        let valid = raw.get("valid").and_then(|value| value.as_bool()).unwrap_or(false);
+        let revoked = raw
+            .get("revoked")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        let status = match raw.get("status") {
+            Some(value) => {
+                serde_json::from_value(value.clone()).unwrap_or(VerifyStatus::Unknown)
+            }
+            None if revoked => VerifyStatus::Revoked,
+            None if valid => VerifyStatus::Active,
+            None => VerifyStatus::Unknown,
+        };
+        let text = |key: &str| {
+            raw.get(key)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        };
+        Self {
+            valid,
+            status,
+            expires_at: text("expires_at"),
+            reason: text("reason"),
+            revoked: revoked || status == VerifyStatus::Revoked,
+            new_receipt: text("new_receipt"),
+            server_time: text("server_time"),
+            grace_period_days: raw
+                .get("grace_period_days")
+                .and_then(|value| value.as_i64())
+                .filter(|days| *days >= 0),
+            raw,
+            headers: HashMap::new(),
+        }
+    }
+
+    /// Valid and not running on the grace period.
+    pub fn is_active(&self) -> bool {
+        self.allows_access() && !self.is_in_grace()
+    }
+
+    /// Valid only thanks to the grace period.
+    pub fn is_in_grace(&self) -> bool {
+        self.allows_access()
+            && (self.status == VerifyStatus::Grace
+                || self.reason.as_deref() == Some("grace_period"))
+    }
+
+    /// Whether the app should unlock: active or in grace, never revoked.
+    pub fn allows_access(&self) -> bool {
+        self.valid && !self.revoked
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseCheck {
+    pub valid: bool,
+    pub reason: Option<String>,
+    pub seats_available: Option<i64>,
+    pub expires_at: Option<String>,
+}
+
+/// Response of `license_info`, e.g. for an account screen showing
+/// "3 of 5 seats used, Pro plan".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseInfo {
+    #[serde(default)]
+    pub license_id: Option<String>,
+    #[serde(default)]
+    pub plan: Option<String>,
+    #[serde(default)]
+    pub seats_total: Option<u32>,
+    #[serde(default)]
+    pub seats_used: Option<u32>,
+    #[serde(default)]
+    pub owner_email: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    /// Server-side state such as `active`, `expired` or `revoked`.
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl LicenseInfo {
+    /// Seats still free; `None` when the server did not report both counts.
+    pub fn seats_available(&self) -> Option<u32> {
+        Some(self.seats_total?.saturating_sub(self.seats_used?))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseInfo {
+    pub release_id: String,
+    pub version: String,
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub published_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateAsset {
+    pub asset_id: String,
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub size_bytes: Option<u64>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub download_url: Option<String>,
+}
+
+/// A release newer than the running version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub latest_version: String,
+    #[serde(default)]
+    pub release_notes: Option<String>,
+    #[serde(default)]
+    pub release: Option<ReleaseInfo>,
+    /// `None` when the release has no downloadable asset.
+    #[serde(default)]
+    pub asset: Option<UpdateAsset>,
+    #[serde(default)]
+    pub server_time: Option<String>,
+}
+
+/// Result of `check_update`. An unknown project or channel is not a
+/// variant but `AlureError::UpdateNotFound`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateCheck {
+    /// Nothing published, or the latest release is `current_version`.
+    UpToDate {
+        current_version: Option<String>,
+        server_time: Option<String>,
+    },
+    Available(Box<UpdateInfo>),
+}
+
+impl UpdateCheck {
+    pub fn update(&self) -> Option<&UpdateInfo> {
+        match self {
+            UpdateCheck::Available(info) => Some(info),
+            UpdateCheck::UpToDate { .. } => None,
+        }
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        matches!(self, UpdateCheck::UpToDate { .. })
+    }
+}
+
+/// A downloaded asset and the hex SHA-256 of what was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadResult {
+    pub asset_id: String,
+    pub path: PathBuf,
+    pub bytes_written: u64,
+    /// A file already existed at `path` and was overwritten.
+    pub replaced_existing: bool,
+    pub sha256: String,
+}
+
+/// Response of `/updates/download-token`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadToken {
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    /// Ready-to-use URL for the asset, used instead of
+    /// `/updates/download/{asset_id}` when present.
+    #[serde(default)]
+    pub download_url: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Detached asset signature, see `with_asset_signatures`.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivationInfo {
+    pub activation_id: String,
+    #[serde(default)]
+    pub device_id_hash: Option<String>,
+    #[serde(default)]
+    pub app_version: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub last_seen_at: Option<String>,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivationsPage {
+    pub items: Vec<ActivationInfo>,
+    #[serde(default)]
+    pub next_page: Option<u32>,
+}
+
+/// Called with the old and new base URL after a permanent redirect moved
+/// it, see `AlureClient::with_base_url_relocation`.
+#[derive(Clone)]
+struct RelocationHook(Arc<OnRelocated>);
+
+type OnRelocated = dyn Fn(&str, &str) + Send + Sync;
+
+impl std::fmt::Debug for RelocationHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RelocationHook")
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct AlureClient {
     base_url: String,
+    /// Where a permanent redirect moved `base_url`; shared across clones so
+    /// a relocation applies to all of them.
+    relocated_base_url: Arc<std::sync::RwLock<Option<String>>>,
+    relocation: Option<RelocationHook>,
     storage: FileStorage,
+    receipts: Arc<dyn ReceiptStorage>,
     verifier: ReceiptVerifier,
     timeout_seconds: u64,
+    http: reqwest::Client,
+    http_options: HttpOptions,
+    transport: Option<Arc<dyn Transport>>,
+    envelope: Option<String>,
+    verify_asset_signatures: bool,
+    verify_response_signatures: bool,
+    verify_update_signatures: bool,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    captured_headers: Vec<String>,
+    accept: String,
+    content_type: String,
+    device_id_sources: DeviceIdSources,
+    device_id_namespace: uuid::Uuid,
+    clock: Arc<dyn Clock>,
+    /// Server-minus-local offset in seconds, shared across clones.
+    clock_drift: Arc<AtomicI64>,
+    sync_server_time: bool,
+    device_id_is_hash: bool,
+    rollback_tolerance: Option<Duration>,
+    endpoints: Endpoints,
+    download_limiter: Option<Arc<RateLimiter>>,
+    /// Entries kept in `events.jsonl`; `None` disables the event log.
+    event_log_limit: Option<usize>,
+    /// Longest sleep of `expiry_watch` / `status_stream` between checks.
+    pub(crate) status_recheck: std::time::Duration,
+    /// Bumped by `refresh_status` to wake every `status_stream`.
+    pub(crate) status_refresh: Arc<tokio::sync::watch::Sender<u64>>,
+    /// Aborts `spawn_refresh_task` / `expiry_watch` tasks with the last clone.
+    pub(crate) background_tasks: Arc<BackgroundTasks>,
 }
 
 impl AlureClient {
@@ -28,71 +444,980 @@ impl AlureClient {
         storage_dir: Option<PathBuf>,
         public_key_pem: Option<String>,
         timeout_seconds: Option<u64>,
+    ) -> Result<Self, AlureError> {
+        let verifier = ReceiptVerifier::new(public_key_pem);
+        Self::with_verifier(base_url, storage_dir, verifier, timeout_seconds)
+    }
+
+    /// Builds a client that requires a valid public key, failing immediately
+    /// with `public_key_required` / `invalid_public_key` otherwise.
+    pub fn new_strict(
+        base_url: Option<String>,
+        storage_dir: Option<PathBuf>,
+        public_key_pem: Option<String>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Self, AlureError> {
+        let verifier = ReceiptVerifier::try_new(public_key_pem)?;
+        Self::with_verifier(base_url, storage_dir, verifier, timeout_seconds)
+    }
+
+    /// Builds a client from the environment, with `new`'s defaults for unset
+    /// or empty variables:
+    ///
+    /// - `ALURE_BASE_URL`: API base URL.
+    /// - `ALURE_PUBLIC_KEY`: receipt public key PEM; a literal `\n` counts
+    ///   as a newline, for single-line values.
+    /// - `ALURE_TIMEOUT`: request timeout in whole seconds, at least 1.
+    /// - `ALURE_HOME`: storage directory.
+    ///
+    /// A malformed value fails with `AlureError::Config` naming the variable.
+    pub fn from_env() -> Result<Self, AlureError> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let timeout_seconds = match var(ENV_TIMEOUT) {
+            Some(value) => match value.parse::<u64>() {
+                Ok(seconds) if seconds > 0 => Some(seconds),
+                _ => {
+                    return Err(AlureError::Config(format!(
+                        "{ENV_TIMEOUT} must be a positive number of seconds, got {value:?}"
+                    )))
+                }
+            },
+            None => None,
+        };
+        let verifier = match var(ENV_PUBLIC_KEY) {
+            Some(pem) => ReceiptVerifier::try_new(Some(pem.replace("\\n", "\n")))
+                .map_err(|err| AlureError::Config(format!("{ENV_PUBLIC_KEY}: {}", err.0)))?,
+            None => ReceiptVerifier::new(None),
+        };
+        Self::with_verifier(
+            var(ENV_BASE_URL),
+            var(ENV_HOME).map(PathBuf::from),
+            verifier,
+            timeout_seconds,
+        )
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The base URL calls go to: `base_url`, or where a permanent redirect
+    /// moved it (see `with_base_url_relocation`).
+    pub fn current_base_url(&self) -> String {
+        self.relocated_base_url
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+            .unwrap_or_else(|| self.base_url.clone())
+    }
+
+    /// Where the client keeps its caches and, unless `with_receipt_storage`
+    /// moved it, `receipt.json`.
+    pub fn storage_dir(&self) -> PathBuf {
+        self.storage.base_dir().to_path_buf()
+    }
+
+    /// Where `download_asset` saves assets by default; created if missing.
+    pub fn downloads_dir(&self) -> Result<PathBuf, AlureError> {
+        Ok(self.storage.downloads_dir()?)
+    }
+
+    pub fn timeout_seconds(&self) -> u64 {
+        self.timeout_seconds
+    }
+
+    fn with_verifier(
+        base_url: Option<String>,
+        storage_dir: Option<PathBuf>,
+        verifier: ReceiptVerifier,
+        timeout_seconds: Option<u64>,
     ) -> Result<Self, AlureError> {
         let base_url = base_url.unwrap_or_else(|| "http://localhost:3000/api/v1".to_string());
         let storage = FileStorage::new(storage_dir).map_err(AlureError::Storage)?;
-        let verifier = ReceiptVerifier::new(public_key_pem);
+        let timeout_seconds = timeout_seconds.unwrap_or(10);
+        let clock_drift = storage.load_clock_drift().ok().flatten().unwrap_or(0);
         Ok(Self {
             base_url: base_url.trim_end_matches('/').to_string(),
+            relocated_base_url: Arc::new(std::sync::RwLock::new(None)),
+            relocation: None,
+            receipts: Arc::new(storage.clone()),
             storage,
             verifier,
-            timeout_seconds: timeout_seconds.unwrap_or(10),
+            timeout_seconds,
+            http: build_http_client(&HttpOptions::default(), timeout_seconds)?,
+            http_options: HttpOptions::default(),
+            transport: None,
+            envelope: None,
+            verify_asset_signatures: false,
+            verify_response_signatures: false,
+            verify_update_signatures: false,
+            metrics: None,
+            captured_headers: Vec::new(),
+            accept: JSON_MEDIA_TYPE.to_string(),
+            content_type: JSON_MEDIA_TYPE.to_string(),
+            device_id_sources: DeviceIdSources::default(),
+            device_id_namespace: uuid::Uuid::NAMESPACE_DNS,
+            clock: Arc::new(SystemClock),
+            clock_drift: Arc::new(AtomicI64::new(clock_drift)),
+            sync_server_time: false,
+            device_id_is_hash: false,
+            rollback_tolerance: None,
+            endpoints: Endpoints::default(),
+            download_limiter: None,
+            event_log_limit: None,
+            status_recheck: DEFAULT_STATUS_RECHECK,
+            status_refresh: Arc::new(tokio::sync::watch::channel(0).0),
+            background_tasks: Arc::default(),
+        })
+    }
+
+    /// Rebuilds the shared HTTP client with custom address family / DNS /
+    /// TLS root settings. A certificate that fails to parse is reported as
+    /// `AlureError::Transport("invalid_certificate: ...")`.
+    pub fn with_http_options(mut self, options: HttpOptions) -> Result<Self, AlureError> {
+        self.http = build_http_client(&options, self.timeout_seconds)?;
+        self.http_options = options;
+        Ok(self)
+    }
+
+    /// Caps asset downloads at `bytes_per_second` in total: concurrent
+    /// downloads, including those of clones, share the budget. `None`
+    /// (the default) removes the cap.
+    pub fn with_download_rate_limit(mut self, bytes_per_second: Option<u64>) -> Self {
+        self.download_limiter = bytes_per_second.map(|rate| Arc::new(RateLimiter::new(rate)));
+        self
+    }
+
+    /// Records every activate, verify and deactivate in `events.jsonl`
+    /// under the storage dir (see `FileStorage::read_events`), keeping the
+    /// last `max_entries`. Off by default.
+    pub fn with_event_log(mut self, max_entries: usize) -> Self {
+        self.event_log_limit = Some(max_entries);
+        self
+    }
+
+    /// Redirects are always followed, except from https to plain http; with
+    /// this set, a 301/308 on a call under the base URL also moves the base
+    /// URL (for this client and its clones) so later calls go straight to
+    /// the new location, and `on_relocated(old, new)` is told.
+    /// `current_base_url` reports it; `base_url` keeps the configured one.
+    /// The new URL is not persisted.
+    pub fn with_base_url_relocation(
+        mut self,
+        on_relocated: impl Fn(&str, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.relocation = Some(RelocationHook(Arc::new(on_relocated)));
+        self
+    }
+
+    /// Routes for servers that do not use the default paths. `GrpcTransport`
+    /// still expects the default `activate` and `verify` routes.
+    pub fn with_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Sends every API call and download through `transport` instead of the
+    /// built-in `reqwest` client; `with_http_options` and the client timeout
+    /// then no longer apply.
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Reads activation fields from under `key` (e.g. `{"payload": {...}}`).
+    /// Without it, `data` and `result` envelopes are detected automatically.
+    pub fn with_envelope(mut self, key: impl Into<String>) -> Self {
+        self.envelope = Some(key.into());
+        self
+    }
+
+    /// Requires downloaded assets to carry a detached ed25519 signature by
+    /// the receipt key, taken from the token response `signature` field or
+    /// fetched from `/updates/download/{asset_id}.sig`. An asset that fails
+    /// the check is discarded; a file already at the target path is kept.
+    pub fn with_asset_signatures(mut self, required: bool) -> Self {
+        self.verify_asset_signatures = required;
+        self
+    }
+
+    /// Requires `verify_online` responses to carry a `signature` field: a
+    /// base64 ed25519 signature by the receipt key over the response's
+    /// canonical JSON (the body without `signature`, keys sorted, no
+    /// whitespace). Unsigned or tampered responses fail with
+    /// `AlureError::ResponseSignature`.
+    pub fn with_signed_verify_responses(mut self, required: bool) -> Self {
+        self.verify_response_signatures = required;
+        self
+    }
+
+    /// Requires `check_update` responses to be signed the same way as
+    /// `with_signed_verify_responses`, so a compromised CDN or proxy cannot
+    /// announce a forged release. The signature covers the asset's
+    /// `asset_id` and `sha256`; an announced asset without a `sha256` is
+    /// rejected as `unbound_asset`.
+    pub fn with_signed_update_manifests(mut self, required: bool) -> Self {
+        self.verify_update_signatures = required;
+        self
+    }
+
+    /// Reports every HTTP call to `sink`, e.g. a shared `AtomicMetrics`.
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(sink);
+        self
+    }
+
+    /// Overrides the time source used for offline expiry checks.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(clock);
+        self.verifier = self.verifier.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// How long `expiry_watch` and `status_stream` may sleep before checking
+    /// the stored receipt again when no expiry or grace boundary is nearer,
+    /// so receipt updates and clock changes are noticed. Defaults to a
+    /// minute.
+    pub fn with_status_recheck_interval(mut self, interval: std::time::Duration) -> Self {
+        self.status_recheck = interval;
+        self
+    }
+
+    /// The clock's time corrected by the drift measured in `sync_time`.
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.clock.now() + Duration::seconds(self.clock_drift.load(Ordering::Relaxed))
+    }
+
+    /// Measures how far the local clock is from the server's `Date` header
+    /// (via a HEAD on the base URL), persists the offset and applies it to
+    /// every later offline check. Returns server minus local time.
+    pub async fn sync_time(&self) -> Result<Duration, AlureError> {
+        let request = TransportRequest {
+            method: reqwest::Method::HEAD,
+            url: self.current_base_url(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            timeout: None,
+        };
+        let before = self.clock.now();
+        let resp = self.dispatch("/", request, None).await?;
+        let after = self.clock.now();
+        let server_time = resp
+            .headers
+            .get(reqwest::header::DATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .ok_or_else(|| {
+                AlureError::EmptyResponse(format!(
+                    "{} (missing Date header)",
+                    self.current_base_url()
+                ))
+            })?;
+        self.apply_clock_drift(server_time.with_timezone(&Utc), before, after)
+    }
+
+    /// Persists and applies server minus local time, taking the local time
+    /// halfway between sending the request and receiving the response.
+    fn apply_clock_drift(
+        &self,
+        server_time: DateTime<Utc>,
+        sent_at: DateTime<Utc>,
+        received_at: DateTime<Utc>,
+    ) -> Result<Duration, AlureError> {
+        let drift = server_time - (sent_at + (received_at - sent_at) / 2);
+        self.storage.save_clock_drift(drift.num_seconds())?;
+        self.clock_drift
+            .store(drift.num_seconds(), Ordering::Relaxed);
+        Ok(drift)
+    }
+
+    /// How far the clock may fall behind the latest time the client has seen
+    /// (persisted as `max_seen_time`) before offline checks fail with
+    /// `clock_rollback`, e.g. 24 hours. Off (`None`) by default, in which
+    /// case no time is recorded either.
+    pub fn with_clock_rollback_tolerance(mut self, tolerance: Option<Duration>) -> Self {
+        self.rollback_tolerance = tolerance;
+        self
+    }
+
+    /// Records `self.now()` as the latest time seen, if it is, while the
+    /// rollback check is on. Called after every server contact and passing
+    /// offline check; nothing is written unless the time moved forward.
+    fn record_seen_time(&self) {
+        if self.rollback_tolerance.is_none() {
+            return;
+        }
+        let now = self.now();
+        let outcome = match self.storage.load_max_seen_time() {
+            Ok(Some(seen)) if seen >= now => Ok(()),
+            Ok(_) => self.storage.save_max_seen_time(now),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = outcome {
+            tracing::warn!(error = %err, "could not persist the last seen time");
+        }
+    }
+
+    /// `validate_offline` at `self.now()`, failing with `clock_rollback`
+    /// when the clock is implausibly behind the last time seen.
+    fn validate_at_now(
+        &self,
+        receipt: &str,
+        device_id: &str,
+        verify_signature: bool,
+    ) -> ReceiptValidationResult {
+        let now = self.now();
+        if let Some(tolerance) = self.rollback_tolerance {
+            match self.storage.load_max_seen_time() {
+                Ok(Some(seen)) if now < seen - tolerance => {
+                    tracing::warn!(%now, %seen, "local clock is behind the last seen time");
+                    return ReceiptValidationResult::invalid("clock_rollback");
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!(error = %err, "could not read the last seen time"),
+            }
+        }
+        let result = self.verifier.validate_with_grace_at(
+            receipt,
+            device_id,
+            now,
+            verify_signature,
+            self.grace_override_for(receipt),
+        );
+        if result.valid {
+            self.record_seen_time();
+        }
+        result
+    }
+
+    /// The grace period persisted by `verify_online` for `receipt`, if any.
+    fn grace_override_for(&self, receipt: &str) -> Option<i64> {
+        match self.storage.load_grace_override() {
+            Ok(grace) => grace
+                .filter(|grace| grace.receipt_fingerprint == ReceiptVerifier::fingerprint(receipt))
+                .map(|grace| grace.grace_period_days),
+            Err(err) => {
+                tracing::warn!(error = %err, "could not read the server grace period");
+                None
+            }
+        }
+    }
+
+    /// Also corrects the clock drift from the `server_time` of every
+    /// successful `verify_online`, like a free `sync_time`. Off by default.
+    pub fn with_server_time_sync(mut self, enabled: bool) -> Self {
+        self.sync_server_time = enabled;
+        self
+    }
+
+    /// Replaces the receipt key with the first of `sources` that resolves.
+    /// `Url` sources are best-effort: a failed fetch is logged and the key
+    /// cached from that URL's last successful fetch is used, if any, before
+    /// moving on. A bad `Pem`, `File` or `Embedded` key fails the call, as
+    /// does running out of sources.
+    pub async fn with_key_sources(mut self, sources: &[KeySource]) -> Result<Self, AlureError> {
+        for source in sources {
+            let pem = match source {
+                KeySource::Url(url) => match self.fetch_key(url).await {
+                    Ok(pem) => pem,
+                    Err(err) => {
+                        tracing::warn!(url = %url, error = %err, "public key fetch failed");
+                        match self.storage.load_resolved_key().ok().flatten() {
+                            Some(cached) if &cached.url == url => cached.pem,
+                            _ => continue,
+                        }
+                    }
+                },
+                KeySource::Pem(pem) => pem.clone(),
+                KeySource::File(path) => std::fs::read_to_string(path).map_err(|err| {
+                    ReceiptError(format!("public_key_unreadable: {}: {err}", path.display()))
+                })?,
+                KeySource::Embedded(pem) => pem.to_string(),
+            };
+            self.verifier = self.verifier.with_pem_key(&pem)?;
+            return Ok(self);
+        }
+        Err(ReceiptError("public_key_required".to_string()).into())
+    }
+
+    /// Downloads and parses the PEM at `url`, caching it on success.
+    async fn fetch_key(&self, url: &str) -> Result<String, AlureError> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let mut request = TransportRequest {
+            method: reqwest::Method::GET,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            timeout: None,
+        };
+        insert_header(&mut request.headers, REQUEST_ID_HEADER, &request_id)?;
+        let resp = self.send(url, request, request_id, None).await?;
+        let pem = String::from_utf8(resp.body)
+            .map_err(|_| ReceiptError("invalid_public_key".to_string()))?;
+        ReceiptVerifier::validate_public_key(&pem)?;
+        let cached = ResolvedKey {
+            url: url.to_string(),
+            pem: pem.clone(),
+            fetched_at: self.now(),
+        };
+        if let Err(err) = self.storage.save_resolved_key(&cached) {
+            tracing::warn!(error = %err, "could not cache public key");
+        }
+        Ok(pem)
+    }
+
+    /// Replaces the receipt keys with the server's JWKS (`GET /licenses/keys`,
+    /// Ed25519 `OKP` keys). The keys are cached in storage with their `ETag`:
+    /// while `Cache-Control: max-age` holds no request is made, afterwards a
+    /// conditional request revalidates them and a 304 reuses the cache.
+    pub async fn fetch_verifying_keys(mut self) -> Result<Self, AlureError> {
+        let path = self.endpoints.keys.clone();
+        let cached = self.storage.load_cached_keys().ok().flatten();
+        let now = self.now();
+        if let Some(cached) = cached.as_ref().filter(|cached| {
+            cached
+                .max_age_seconds
+                .is_some_and(|max_age| now - cached.fetched_at <= Duration::seconds(max_age as i64))
+        }) {
+            self.verifier = self
+                .verifier
+                .with_raw_keys(&decode_jwk_keys(&cached.keys)?)?;
+            return Ok(self);
+        }
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let mut request = TransportRequest {
+            method: reqwest::Method::GET,
+            url: format!("{}{path}", self.current_base_url()),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            timeout: None,
+        };
+        insert_header(&mut request.headers, "Accept", &self.accept)?;
+        insert_header(&mut request.headers, REQUEST_ID_HEADER, &request_id)?;
+        if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_deref()) {
+            insert_header(&mut request.headers, "If-None-Match", etag)?;
+        }
+        let resp = self.dispatch(&path, request, None).await?;
+        let max_age_seconds = resp
+            .headers
+            .get(reqwest::header::CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(cache_max_age);
+        let etag = resp
+            .headers
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let entry = match (resp.status, cached) {
+            (304, Some(cached)) => CachedKeys {
+                etag: etag.or(cached.etag),
+                fetched_at: now,
+                max_age_seconds,
+                keys: cached.keys,
+            },
+            (status, _) if (200..300).contains(&status) => {
+                let jwks: serde_json::Value = serde_json::from_slice(&resp.body)?;
+                let keys: Vec<String> = jwks
+                    .get("keys")
+                    .and_then(|keys| keys.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter(|jwk| jwk["kty"] == "OKP" && jwk["crv"] == "Ed25519")
+                    .filter_map(|jwk| jwk.get("x")?.as_str().map(str::to_string))
+                    .collect();
+                if keys.is_empty() {
+                    return Err(AlureError::EmptyResponse(format!(
+                        "{path} (no Ed25519 keys)"
+                    )));
+                }
+                CachedKeys {
+                    keys,
+                    etag,
+                    fetched_at: now,
+                    max_age_seconds,
+                }
+            }
+            (status, _) => {
+                return Err(AlureError::from_response(
+                    status,
+                    error_message(&resp.body),
+                    Some(request_id),
+                ))
+            }
+        };
+        self.verifier = self
+            .verifier
+            .with_raw_keys(&decode_jwk_keys(&entry.keys)?)?;
+        self.storage.save_cached_keys(&entry)?;
+        Ok(self)
+    }
+
+    /// Response headers (e.g. `X-License-Warning`) to surface on typed
+    /// results. Credential-bearing headers are never captured.
+    pub fn with_captured_headers<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.captured_headers = names
+            .into_iter()
+            .map(|name| name.into().to_ascii_lowercase())
+            .filter(|name| !SENSITIVE_HEADERS.contains(&name.as_str()))
+            .collect();
+        self
+    }
+
+    /// `Accept` sent on API calls, e.g. `application/vnd.alure.v1+json`.
+    /// An `Accept` header passed to `call` overrides it for that request.
+    pub fn with_accept(mut self, media_type: impl Into<String>) -> Self {
+        self.accept = media_type.into();
+        self
+    }
+
+    /// `Content-Type` of JSON request bodies, e.g. with a charset. Bodies are
+    /// always serialized as JSON. A `Content-Type` header passed to `call`
+    /// overrides it for that request.
+    pub fn with_content_type(mut self, media_type: impl Into<String>) -> Self {
+        self.content_type = media_type.into();
+        self
+    }
+
+    /// Matches receipts whose server hashes device ids differently.
+    pub fn with_device_hasher(mut self, hasher: impl DeviceHasher + 'static) -> Self {
+        self.verifier = self.verifier.with_device_hasher(hasher);
+        self
+    }
+
+    /// Treats every device id passed to the client as the caller's own hex
+    /// SHA-256 hash: it is sent with `"device_id_is_hash": true` so the server
+    /// stores it as-is, and offline checks compare it to `device_id_hash`
+    /// directly (`PreHashedDeviceHasher`). `default_device_id` returns the
+    /// hash of the derived id, so the raw id never leaves the machine.
+    /// Replaces any hasher set with `with_device_hasher`.
+    pub fn with_device_id_is_hash(mut self, enabled: bool) -> Self {
+        self.device_id_is_hash = enabled;
+        self.verifier = match enabled {
+            true => self.verifier.with_device_hasher(PreHashedDeviceHasher),
+            false => self.verifier.with_device_hasher(Sha256DeviceHasher),
+        };
+        self
+    }
+
+    /// Decrypts confidential receipt claims; see
+    /// `ReceiptVerifier::with_claim_decryptor`.
+    pub fn with_claim_decryptor(mut self, decryptor: impl ClaimDecryptor + 'static) -> Self {
+        self.verifier = self.verifier.with_claim_decryptor(decryptor);
+        self
+    }
+
+    /// Offline checks reject receipts issued more than `max_age` ago with
+    /// reason `stale`; see `ReceiptVerifier::with_max_receipt_age`.
+    pub fn with_max_receipt_age(mut self, max_age: Option<Duration>) -> Self {
+        self.verifier = self.verifier.with_max_receipt_age(max_age);
+        self
+    }
+
+    /// Reads `current_plan` from `claim` instead of `plan`.
+    pub fn with_plan_claim(mut self, claim: impl Into<String>) -> Self {
+        self.verifier = self.verifier.with_plan_claim(claim);
+        self
+    }
+
+    /// Persists receipts in `receipts` instead of the file storage; downloads
+    /// and the install id stay under the storage dir.
+    pub fn with_receipt_storage(mut self, receipts: impl ReceiptStorage + 'static) -> Self {
+        self.receipts = Arc::new(receipts);
+        self
+    }
+
+    /// Selects which machine identifiers feed `default_device_id`.
+    pub fn with_device_id_sources(mut self, sources: DeviceIdSources) -> Self {
+        self.device_id_sources = sources;
+        self
+    }
+
+    /// Derives `default_device_id` under `namespace` instead of
+    /// `Uuid::NAMESPACE_DNS`, so products sharing a machine (and a backend)
+    /// get distinct ids. Changing it changes the id of existing installs.
+    pub fn with_device_id_namespace(mut self, namespace: uuid::Uuid) -> Self {
+        self.device_id_namespace = namespace;
+        self
+    }
+
+    /// Validates a PEM public key before it is used, e.g. at app startup.
+    pub fn validate_public_key(pem: &str) -> Result<(), AlureError> {
+        Ok(ReceiptVerifier::validate_public_key(pem)?)
+    }
+
+    pub fn default_device_id(&self) -> Result<String, AlureError> {
+        let device_id = derive_device_id(
+            self.device_id_sources,
+            &self.device_id_namespace,
+            &self.storage,
+        )?;
+        match self.device_id_is_hash {
+            true => Ok(Sha256DeviceHasher.hash(&device_id)),
+            false => Ok(device_id),
+        }
+    }
+
+    /// Whether the stored receipt is bound to this device, ignoring expiry
+    /// and signatures. Fails with `missing_receipt` when nothing is stored.
+    pub fn device_matches_stored_receipt(&self) -> Result<bool, AlureError> {
+        Ok(self.device_binding()?.matches)
+    }
+
+    /// Like `device_matches_stored_receipt`, with both hashes and device
+    /// ids for diagnosing a `device_mismatch`.
+    pub fn device_binding(&self) -> Result<DeviceBinding, AlureError> {
+        let stored = self.receipts.load_receipt()?.ok_or_else(missing_receipt)?;
+        let claims = self.verifier.parse(&stored.receipt)?;
+        let receipt_hash = claims
+            .get("device_id_hash")
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+        let current_device_id = self.default_device_id()?;
+        let current_hash = self.verifier.hash_device_id(&current_device_id);
+        Ok(DeviceBinding {
+            matches: receipt_hash.as_deref() == Some(current_hash.as_str()),
+            stored_device_id: stored.device_id,
+            current_device_id,
+            current_hash,
+            receipt_hash,
         })
     }
 
-    pub fn default_device_id(&self) -> Result<String, AlureError> {
-        let host = hostname::get()
-            .map_err(|err| StorageError(format!("hostname_failed: {err}")))?
-            .to_string_lossy()
-            .to_string();
-        let mac = mac_address::get_mac_address()
-            .map_err(|err| StorageError(format!("mac_address_failed: {err}")))?
-            .map(|addr| addr.to_string())
-            .unwrap_or_else(|| "unknown".to_string());
-        let user = whoami::username();
-        let raw = format!("{host}-{mac}-{user}");
-        Ok(uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_DNS, raw.as_bytes()).to_string())
+    /// Sends `json_body` to `path` under the base URL and decodes the JSON
+    /// response, e.g. for a route the SDK has no method for. `headers` are
+    /// added last, so an `Accept` or `Content-Type` among them replaces the
+    /// configured one (see `with_accept`) for this request only.
+    pub async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        json_body: Option<serde_json::Value>,
+        headers: Vec<(String, String)>,
+    ) -> Result<T, AlureError> {
+        self.request(method, path, json_body, None, Some(headers))
+            .await
+    }
+
+    fn mark_hashed_device_id(&self, payload: &mut serde_json::Value) {
+        if self.device_id_is_hash {
+            payload["device_id_is_hash"] = serde_json::Value::Bool(true);
+        }
+    }
+
+    async fn request<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        json_body: Option<serde_json::Value>,
+        query: Option<Vec<(String, String)>>,
+        headers: Option<Vec<(String, String)>>,
+    ) -> Result<T, AlureError> {
+        let (payload, _) = self
+            .request_with_headers(method, path, json_body, query, headers)
+            .await?;
+        Ok(payload)
+    }
+
+    /// Like `request`, also returning the configured captured response
+    /// headers (see `with_captured_headers`).
+    async fn request_with_headers<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        json_body: Option<serde_json::Value>,
+        query: Option<Vec<(String, String)>>,
+        headers: Option<Vec<(String, String)>>,
+    ) -> Result<(T, HashMap<String, String>), AlureError> {
+        let mut url = reqwest::Url::parse(&format!("{}{}", self.current_base_url(), path))
+            .map_err(|err| AlureError::Transport(format!("invalid_url: {err}")))?;
+        if let Some(params) = query.filter(|params| !params.is_empty()) {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        let headers = headers.unwrap_or_default();
+        let request_id = headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(REQUEST_ID_HEADER))
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut request = TransportRequest {
+            method,
+            url: url.into(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            timeout: None,
+        };
+        insert_header(&mut request.headers, "Accept", &self.accept)?;
+        insert_header(&mut request.headers, REQUEST_ID_HEADER, &request_id)?;
+        if let Some(body) = json_body {
+            insert_header(&mut request.headers, "Content-Type", &self.content_type)?;
+            request.body = serde_json::to_vec(&body)?;
+        }
+        // Per-call headers go last so they win over the defaults above.
+        for (key, value) in headers {
+            if !key.eq_ignore_ascii_case(REQUEST_ID_HEADER) {
+                insert_header(&mut request.headers, &key, &value)?;
+            }
+        }
+        let resp = self.send(path, request, request_id, None).await?;
+        let captured = self.capture_headers(&resp.headers);
+        if resp.status == reqwest::StatusCode::NO_CONTENT.as_u16() {
+            return Ok((empty_response(path)?, captured));
+        }
+        let payload = serde_json::from_slice::<T>(&resp.body)?;
+        Ok((payload, captured))
+    }
+
+    /// Sends through the configured transport, following redirects, and
+    /// reports to the metrics sink; any other status is returned as-is.
+    async fn dispatch(
+        &self,
+        path: &str,
+        request: TransportRequest,
+        mut sink: BodySink<'_>,
+    ) -> Result<TransportResponse, AlureError> {
+        let started = std::time::Instant::now();
+        let mut request = request;
+        let mut body = hyper::body::Bytes::from(std::mem::take(&mut request.body));
+        let mut hops = 0;
+        let sent = loop {
+            let reborrowed = sink
+                .as_mut()
+                .map(|sink| &mut **sink as &mut (dyn tokio::io::AsyncWrite + Unpin + Send));
+            let resp = match self.send_once(&request, body.clone(), reborrowed).await {
+                Ok(resp) => resp,
+                Err(err) => break Err(err),
+            };
+            match self.follow_redirect(&mut request, &mut body, &resp) {
+                Ok(true) => {}
+                Ok(false) => break Ok(resp),
+                Err(err) => break Err(err),
+            }
+            hops += 1;
+            if hops > MAX_REDIRECTS {
+                break Err(AlureError::Transport(format!(
+                    "too_many_redirects: more than {MAX_REDIRECTS}"
+                )));
+            }
+        };
+        match sent {
+            Ok(resp) => {
+                if let Some(metrics) = &self.metrics {
+                    let operation = match path {
+                        path if path == self.endpoints.activate => Operation::Activate,
+                        path if path == self.endpoints.verify => Operation::Verify,
+                        _ => Operation::Other,
+                    };
+                    metrics.on_request(operation, path, resp.status, started.elapsed());
+                }
+                Ok(resp)
+            }
+            Err(err) => {
+                if let Some(metrics) = &self.metrics {
+                    metrics.on_error(ErrorKind::Network);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Sends `request` with `body` once. A custom transport gets its own
+    /// copy; the built-in client shares `body` with the caller.
+    async fn send_once(
+        &self,
+        request: &TransportRequest,
+        body: hyper::body::Bytes,
+        sink: BodySink<'_>,
+    ) -> Result<TransportResponse, AlureError> {
+        match &self.transport {
+            Some(transport) => {
+                let request = TransportRequest {
+                    method: request.method.clone(),
+                    url: request.url.clone(),
+                    headers: request.headers.clone(),
+                    body: body.to_vec(),
+                    timeout: request.timeout,
+                };
+                match (transport.send(request).await, sink) {
+                    (Ok(mut resp), Some(sink)) if (200..300).contains(&resp.status) => {
+                        sink.write_all(&resp.body).await?;
+                        sink.flush().await?;
+                        resp.body.clear();
+                        Ok(resp)
+                    }
+                    (sent, _) => sent,
+                }
+            }
+            None => {
+                let read_timeout = self.http_options.read_timeout;
+                send_reqwest(&self.http, request, body, read_timeout, sink).await
+            }
+        }
+    }
+
+    /// Turns `request` into the one to send next when `resp` redirects and
+    /// returns whether it did. 307/308 keep the method and body;
+    /// 301/302/303 turn anything but GET/HEAD into a bare GET. Credentials
+    /// are dropped when the host changes, and a redirect from https to
+    /// anything else is refused with `insecure_redirect`. A permanent
+    /// redirect of a call under the base URL also relocates the base URL
+    /// when `with_base_url_relocation` is set.
+    fn follow_redirect(
+        &self,
+        request: &mut TransportRequest,
+        body: &mut hyper::body::Bytes,
+        resp: &TransportResponse,
+    ) -> Result<bool, AlureError> {
+        if !matches!(resp.status, 301 | 302 | 303 | 307 | 308) {
+            return Ok(false);
+        }
+        let Ok(from) = reqwest::Url::parse(&request.url) else {
+            return Ok(false);
+        };
+        let Some(to) = resp
+            .headers
+            .get(reqwest::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| from.join(location).ok())
+        else {
+            return Ok(false);
+        };
+        if from.scheme() == "https" && to.scheme() != "https" {
+            return Err(AlureError::Transport(format!(
+                "insecure_redirect: {} -> {}",
+                from.origin().ascii_serialization(),
+                to.origin().ascii_serialization()
+            )));
+        }
+        request.url = to.to_string();
+        if !matches!(resp.status, 307 | 308)
+            && !matches!(request.method, reqwest::Method::GET | reqwest::Method::HEAD)
+        {
+            request.method = reqwest::Method::GET;
+            *body = hyper::body::Bytes::new();
+            request.headers.remove(reqwest::header::CONTENT_TYPE);
+        }
+        if from.host_str() != to.host_str()
+            || from.port_or_known_default() != to.port_or_known_default()
+        {
+            for name in SENSITIVE_HEADERS {
+                request.headers.remove(*name);
+            }
+        }
+        if matches!(resp.status, 301 | 308) {
+            self.relocate(&from, &to);
+        }
+        Ok(true)
+    }
+
+    /// Moves the base URL when `to` keeps the part of `from` below it, e.g.
+    /// `old/api/v1/licenses/activate` -> `new/v2/licenses/activate` moves
+    /// `old/api/v1` to `new/v2`.
+    fn relocate(&self, from: &reqwest::Url, to: &reqwest::Url) {
+        let Some(hook) = &self.relocation else {
+            return;
+        };
+        let strip_query = |url: &reqwest::Url| {
+            let mut url = url.clone();
+            url.set_query(None);
+            url.set_fragment(None);
+            url.to_string()
+        };
+        let (from, to) = (strip_query(from), strip_query(to));
+        let old = self.current_base_url();
+        let Some(route) = from
+            .strip_prefix(&old)
+            .filter(|route| route.starts_with('/'))
+        else {
+            return;
+        };
+        let Some(new) = to
+            .strip_suffix(route)
+            .filter(|new| !new.is_empty() && *new != old)
+        else {
+            return;
+        };
+        let new = new.trim_end_matches('/').to_string();
+        *self
+            .relocated_base_url
+            .write()
+            .unwrap_or_else(|err| err.into_inner()) = Some(new.clone());
+        tracing::info!(from = %old, to = %new, "base URL relocated");
+        (hook.0)(&old, &new);
     }
 
-    async fn request<T: for<'de> Deserialize<'de>>(
+    /// Like `dispatch`, mapping non-success statuses to errors tagged with
+    /// the request id.
+    async fn send(
         &self,
-        method: reqwest::Method,
         path: &str,
-        json_body: Option<serde_json::Value>,
-        query: Option<Vec<(String, String)>>,
-        headers: Option<Vec<(String, String)>>,
-    ) -> Result<T, AlureError> {
-        let url = format!("{}{}", self.base_url, path);
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(self.timeout_seconds))
-            .build()?;
-        let mut req = client.request(method, &url).header("Accept", "application/json");
-        if let Some(body) = json_body {
-            req = req.json(&body);
-        }
-        if let Some(params) = query {
-            req = req.query(&params);
-        }
-        if let Some(items) = headers {
-            for (key, value) in items {
-                req = req.header(&key, &value);
+        request: TransportRequest,
+        request_id: String,
+        sink: BodySink<'_>,
+    ) -> Result<TransportResponse, AlureError> {
+        let resp = self.dispatch(path, request, sink).await?;
+        if !(200..300).contains(&resp.status) {
+            let request_id = response_request_id(&resp.headers).unwrap_or(request_id);
+            let message = error_message(&resp.body);
+            let err = AlureError::from_response(resp.status, message, Some(request_id));
+            if let Some(metrics) = &self.metrics {
+                metrics.on_error(match &err {
+                    AlureError::License { .. } => ErrorKind::License,
+                    _ if resp.status >= 500 => ErrorKind::Server,
+                    _ => ErrorKind::Client,
+                });
             }
+            return Err(err);
         }
-        let resp = req.send().await?;
-        let status = resp.status();
-        if !status.is_success() {
-            let message = resp.text().await.unwrap_or_default();
-            return Err(AlureError::Http {
-                status: status.as_u16(),
-                message,
-            });
-        }
-        if status == reqwest::StatusCode::NO_CONTENT {
-            let empty = serde_json::json!({});
-            return Ok(serde_json::from_value(empty)?);
+        Ok(resp)
+    }
+
+    fn capture_headers(&self, headers: &HeaderMap) -> HashMap<String, String> {
+        self.captured_headers
+            .iter()
+            .filter_map(|name| {
+                headers
+                    .get(name.as_str())
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| (name.clone(), value.to_string()))
+            })
+            .collect()
+    }
+
+    /// Appends to the event log when enabled; `outcome` is the success
+    /// result (`ok` or a status) or the error. Failing to write only warns.
+    fn log_event(&self, action: &str, request_id: &str, outcome: Result<String, &AlureError>) {
+        let Some(max_entries) = self.event_log_limit else {
+            return;
+        };
+        // A failed call carries the id the server echoed, if any.
+        let (success, result, request_id) = match outcome {
+            Ok(result) => (true, result, request_id),
+            Err(err) => (
+                false,
+                event_failure(err),
+                err.request_id().unwrap_or(request_id),
+            ),
+        };
+        let event = EventRecord {
+            at: self.now(),
+            action: action.to_string(),
+            success,
+            result,
+            request_id: Some(request_id.to_string()),
+        };
+        if let Err(err) = self.storage.append_event(&event, max_entries) {
+            tracing::warn!(error = %err, "could not append to the event log");
         }
-        let payload = resp.json::<T>().await?;
-        Ok(payload)
     }
 
     pub async fn activate(
@@ -106,29 +1431,226 @@ impl AlureClient {
             Some(value) => value,
             None => self.default_device_id()?,
         };
+        let (response, record) = self
+            .request_activation(license_key, device_id, app_version, device_meta, None)
+            .await?;
+        self.receipts.save_receipt(&record)?;
+        tracing::debug!(fingerprint = %record.fingerprint(), "activated and stored receipt");
+        Ok(response)
+    }
+
+    /// Like `activate`, then uploads `diagnostics` to the `diagnostics`
+    /// endpoint tagged with the new `activation_id`. Without that endpoint
+    /// configured, or with attachments over `MAX_DIAGNOSTICS_BYTES`, a
+    /// `Config` error is returned before anything is sent. A failed upload is
+    /// returned as the error, but the activation stays stored.
+    pub async fn activate_with_diagnostics(
+        &self,
+        license_key: &str,
+        device_id: Option<String>,
+        app_version: Option<String>,
+        device_meta: Option<serde_json::Value>,
+        diagnostics: Diagnostics,
+    ) -> Result<ActivateResponse, AlureError> {
+        let Some(path) = self.endpoints.diagnostics.as_deref() else {
+            return Err(AlureError::Config(
+                "diagnostics_endpoint_not_configured".to_string(),
+            ));
+        };
+        if diagnostics.data.len() > MAX_DIAGNOSTICS_BYTES {
+            return Err(AlureError::Config(format!(
+                "diagnostics_too_large: more than {MAX_DIAGNOSTICS_BYTES} bytes"
+            )));
+        }
+        let response = self
+            .activate(license_key, device_id, app_version, device_meta)
+            .await?;
+        self.upload_diagnostics(path, &response.activation_id, diagnostics)
+            .await?;
+        Ok(response)
+    }
+
+    /// Like `activate`, but when the server cannot be reached (network
+    /// failure or 5xx) the activation is queued in storage, license key
+    /// included (see `PendingActivation`), and `Pending` is returned.
+    /// Queuing the same key and device again replaces the earlier entry.
+    pub async fn activate_or_queue(
+        &self,
+        license_key: &str,
+        device_id: Option<String>,
+        app_version: Option<String>,
+        device_meta: Option<serde_json::Value>,
+    ) -> Result<ActivationOutcome, AlureError> {
+        let device_id = match device_id {
+            Some(value) => value,
+            None => self.default_device_id()?,
+        };
+        let pending = PendingActivation {
+            license_key: license_key.to_string(),
+            device_id,
+            app_version,
+            device_meta,
+            idempotency_key: uuid::Uuid::new_v4().to_string(),
+            queued_at: self.now(),
+        };
+        match self.complete_pending(&pending).await {
+            Err(err) if is_unreachable(&err) => {
+                tracing::info!(error = %err, "server unreachable, activation queued");
+                let mut queue = self.storage.load_pending_activations()?;
+                queue.retain(|queued| {
+                    (queued.license_key.as_str(), queued.device_id.as_str())
+                        != (pending.license_key.as_str(), pending.device_id.as_str())
+                });
+                queue.push(pending.clone());
+                self.storage.save_pending_activations(&queue)?;
+                Ok(ActivationOutcome::Pending(pending))
+            }
+            result => result.map(ActivationOutcome::Activated),
+        }
+    }
+
+    /// Activations queued by `activate_or_queue`, oldest first.
+    pub fn pending_activations(&self) -> Result<Vec<PendingActivation>, AlureError> {
+        Ok(self.storage.load_pending_activations()?)
+    }
+
+    /// Retries the queued activations in order, one result each. Completed
+    /// ones and ones the server rejects leave the queue; once the server
+    /// proves unreachable again the rest stay queued and are not attempted.
+    pub async fn flush_pending_activations(
+        &self,
+    ) -> Result<Vec<Result<ActivateResponse, AlureError>>, AlureError> {
+        let mut queue = self.storage.load_pending_activations()?;
+        let mut results = Vec::new();
+        while let Some(pending) = queue.first().cloned() {
+            match self.complete_pending(&pending).await {
+                Err(err) if is_unreachable(&err) => {
+                    results.push(Err(err));
+                    break;
+                }
+                result => {
+                    queue.remove(0);
+                    self.storage.save_pending_activations(&queue)?;
+                    results.push(result);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    async fn complete_pending(
+        &self,
+        pending: &PendingActivation,
+    ) -> Result<ActivateResponse, AlureError> {
+        let (response, record) = self
+            .request_activation(
+                &pending.license_key,
+                pending.device_id.clone(),
+                pending.app_version.clone(),
+                pending.device_meta.clone(),
+                Some(pending.idempotency_key.clone()),
+            )
+            .await?;
+        self.receipts.save_receipt(&record)?;
+        tracing::debug!(fingerprint = %record.fingerprint(), "activated and stored receipt");
+        Ok(response)
+    }
+
+    async fn upload_diagnostics(
+        &self,
+        path: &str,
+        activation_id: &str,
+        diagnostics: Diagnostics,
+    ) -> Result<(), AlureError> {
+        let mut url = reqwest::Url::parse(&format!("{}{}", self.current_base_url(), path))
+            .map_err(|err| AlureError::Transport(format!("invalid_url: {err}")))?;
+        url.query_pairs_mut()
+            .append_pair("activation_id", activation_id);
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let mut request = TransportRequest {
+            method: reqwest::Method::POST,
+            url: url.into(),
+            headers: HeaderMap::new(),
+            body: diagnostics.data,
+            timeout: None,
+        };
+        insert_header(&mut request.headers, REQUEST_ID_HEADER, &request_id)?;
+        insert_header(
+            &mut request.headers,
+            "Content-Type",
+            &diagnostics.content_type,
+        )?;
+        self.send(path, request, request_id, None).await?;
+        Ok(())
+    }
+
+    async fn request_activation(
+        &self,
+        license_key: &str,
+        device_id: String,
+        app_version: Option<String>,
+        device_meta: Option<serde_json::Value>,
+        idempotency_key: Option<String>,
+    ) -> Result<(ActivateResponse, ReceiptRecord), AlureError> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let result = self
+            .send_activation(
+                license_key,
+                device_id,
+                app_version,
+                device_meta,
+                idempotency_key,
+                &request_id,
+            )
+            .await;
+        self.log_event(
+            "activate",
+            &request_id,
+            result.as_ref().map(|_| "ok".to_string()),
+        );
+        result
+    }
+
+    async fn send_activation(
+        &self,
+        license_key: &str,
+        device_id: String,
+        app_version: Option<String>,
+        device_meta: Option<serde_json::Value>,
+        idempotency_key: Option<String>,
+        request_id: &str,
+    ) -> Result<(ActivateResponse, ReceiptRecord), AlureError> {
         let mut payload = serde_json::json!({
             "license_key": license_key,
             "device_id": device_id,
         });
+        self.mark_hashed_device_id(&mut payload);
         if let Some(app_version) = app_version {
             payload["app_version"] = serde_json::Value::String(app_version);
         }
         if let Some(meta) = device_meta {
             payload["device_meta"] = meta;
         }
+        let idempotency_key = idempotency_key.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
         let data: serde_json::Value = self
             .request(
                 reqwest::Method::POST,
-                "/licenses/activate",
+                &self.endpoints.activate,
                 Some(payload),
                 None,
-                None,
+                Some(vec![
+                    (IDEMPOTENCY_KEY_HEADER.to_string(), idempotency_key.clone()),
+                    (REQUEST_ID_HEADER.to_string(), request_id.to_string()),
+                ]),
             )
             .await?;
+        self.record_seen_time();
+        let data = self.unwrap_envelope(&data);
         let receipt = data
             .get("receipt")
             .and_then(|value| value.as_str())
-            .unwrap_or_default()
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| ReceiptError("missing_receipt_in_response".to_string()))?
             .to_string();
         let activation_id = data
             .get("activation_id")
@@ -148,52 +1670,560 @@ impl AlureClient {
             .and_then(|value| value.as_str())
             .unwrap_or_default()
             .to_string();
+        let claims = self.verifier.parse(&receipt).ok();
         let record = ReceiptRecord {
             receipt: receipt.clone(),
             device_id: device_id.clone(),
             activation_id: Some(activation_id.clone()),
-            project_id: self.extract_project_id(&receipt).ok().flatten(),
+            project_id: claims.as_ref().and_then(project_id_claim),
+            extra: HashMap::new(),
+        };
+        let response = ActivateResponse {
+            receipt,
+            activation_id,
+            expires_at,
+            grace_period_days,
+            server_time,
+            from_cache: false,
+            project_id: claims.as_ref().and_then(project_id_claim),
+            features: claims.as_ref().and_then(features_claim),
+            tier: claims.as_ref().and_then(tier_claim),
+            claims,
+            idempotency_key: Some(idempotency_key),
+        };
+        Ok((response, record))
+    }
+
+    fn unwrap_envelope<'a>(&self, data: &'a serde_json::Value) -> &'a serde_json::Value {
+        if let Some(key) = &self.envelope {
+            return data.get(key).unwrap_or(data);
+        }
+        if data.get("receipt").is_some() {
+            return data;
+        }
+        ["data", "result"]
+            .iter()
+            .filter_map(|key| data.get(*key))
+            .find(|inner| inner.get("receipt").is_some())
+            .unwrap_or(data)
+    }
+
+    /// Checks a license key without activating it; nothing is persisted.
+    pub async fn check_license(&self, license_key: &str) -> Result<LicenseCheck, AlureError> {
+        let payload = serde_json::json!({ "license_key": license_key });
+        self.request(
+            reqwest::Method::POST,
+            &self.endpoints.check,
+            Some(payload),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Reads a license's plan, seats and owner. With `license_key` the key
+    /// authenticates the call; with `None` the stored receipt does, so the
+    /// key need not be kept after activation.
+    pub async fn license_info(&self, license_key: Option<&str>) -> Result<LicenseInfo, AlureError> {
+        let payload = match license_key {
+            Some(license_key) => serde_json::json!({ "license_key": license_key }),
+            None => {
+                let stored = self.receipts.load_receipt()?.ok_or_else(missing_receipt)?;
+                let mut payload = serde_json::json!({
+                    "receipt": stored.receipt,
+                    "device_id": stored.device_id,
+                });
+                self.mark_hashed_device_id(&mut payload);
+                payload
+            }
+        };
+        self.request(
+            reqwest::Method::POST,
+            &self.endpoints.info,
+            Some(payload),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Fetches one page of a license's activations; `None` is the first page.
+    pub async fn list_activations(
+        &self,
+        license_key: &str,
+        page: Option<u32>,
+    ) -> Result<ActivationsPage, AlureError> {
+        let mut payload = serde_json::json!({ "license_key": license_key });
+        if let Some(page) = page {
+            payload["page"] = page.into();
+        }
+        self.request(
+            reqwest::Method::POST,
+            &self.endpoints.activations,
+            Some(payload),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Every activation of a license, fetching pages as the stream is
+    /// polled. The stream ends after the first error.
+    pub fn activations<'a>(
+        &'a self,
+        license_key: &'a str,
+    ) -> impl Stream<Item = Result<ActivationInfo, AlureError>> + 'a {
+        stream::try_unfold(Some(None), move |page| async move {
+            let Some(page) = page else {
+                return Ok::<_, AlureError>(None);
+            };
+            let result = self.list_activations(license_key, page).await?;
+            let items = stream::iter(result.items.into_iter().map(Ok));
+            Ok(Some((items, result.next_page.map(Some))))
+        })
+        .try_flatten()
+    }
+
+    /// Revokes a single activation, freeing its seat. The server only accepts
+    /// this from an admin session; license-key clients get a 403.
+    pub async fn deactivate(&self, activation_id: &str) -> Result<(), AlureError> {
+        let payload = serde_json::json!({
+            "activation_id": activation_id,
+            "reason": "deactivated",
+        });
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let result = self
+            .request::<serde::de::IgnoredAny>(
+                reqwest::Method::POST,
+                &self.endpoints.revoke,
+                Some(payload),
+                None,
+                Some(vec![(REQUEST_ID_HEADER.to_string(), request_id.clone())]),
+            )
+            .await;
+        self.log_event(
+            "deactivate",
+            &request_id,
+            result.as_ref().map(|_| "ok".to_string()),
+        );
+        result?;
+        Ok(())
+    }
+
+    /// Moves the stored activation to `new_device_id`: the license is
+    /// activated for the new device and the stored record replaced, then the
+    /// old activation is released with `license_key`. When the license has
+    /// no free seat, the old activation is released first and the activation
+    /// retried once; should that retry fail, the old record is kept but its
+    /// seat is gone. Any other failed activation leaves everything
+    /// untouched. A failed release after a successful activation is logged
+    /// and leaves the old activation holding its seat.
+    pub async fn migrate_device(
+        &self,
+        license_key: &str,
+        new_device_id: String,
+    ) -> Result<ActivateResponse, AlureError> {
+        let stored = self.receipts.load_receipt()?;
+        let old_activation = stored
+            .as_ref()
+            .and_then(|record| record.activation_id.clone())
+            .filter(|value| !value.is_empty());
+        let activation = self
+            .request_activation(license_key, new_device_id.clone(), None, None, None)
+            .await;
+        let (response, record, old_activation) = match (activation, old_activation) {
+            (Ok((response, record)), old_activation) => (response, record, old_activation),
+            (
+                Err(AlureError::License {
+                    code: LicenseErrorCode::SeatLimitReached,
+                    ..
+                }),
+                Some(old_activation),
+            ) => {
+                tracing::debug!("no free seat, releasing the previous activation first");
+                self.release_activation(license_key, &old_activation)
+                    .await?;
+                let (response, record) = self
+                    .request_activation(license_key, new_device_id, None, None, None)
+                    .await?;
+                (response, record, None)
+            }
+            (Err(err), _) => return Err(err),
+        };
+        self.receipts.save_receipt(&record)?;
+        tracing::debug!(fingerprint = %record.fingerprint(), "migrated device and stored receipt");
+        if let Some(old_activation) =
+            old_activation.filter(|value| *value != response.activation_id)
+        {
+            if let Err(err) = self.release_activation(license_key, &old_activation).await {
+                tracing::warn!(error = %err, "could not release the previous activation");
+            }
+        }
+        Ok(response)
+    }
+
+    /// Frees the seat of one of `license_key`'s activations. Unlike
+    /// `deactivate`, the license key is enough to authenticate it.
+    async fn release_activation(
+        &self,
+        license_key: &str,
+        activation_id: &str,
+    ) -> Result<(), AlureError> {
+        let payload = serde_json::json!({
+            "license_key": license_key,
+            "activation_id": activation_id,
+        });
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let result = self
+            .request::<serde::de::IgnoredAny>(
+                reqwest::Method::POST,
+                &self.endpoints.deactivate,
+                Some(payload),
+                None,
+                Some(vec![(REQUEST_ID_HEADER.to_string(), request_id.clone())]),
+            )
+            .await;
+        self.log_event(
+            "deactivate",
+            &request_id,
+            result.as_ref().map(|_| "ok".to_string()),
+        );
+        result?;
+        Ok(())
+    }
+
+    /// Returns the stored activation when its receipt is still valid for this
+    /// device, and only calls `activate` when it is missing, expired or in grace.
+    pub async fn ensure_activated(
+        &self,
+        license_key: &str,
+        device_id: Option<String>,
+        app_version: Option<String>,
+        device_meta: Option<serde_json::Value>,
+    ) -> Result<ActivateResponse, AlureError> {
+        self.activate_if_needed(license_key, device_id, app_version, device_meta, false)
+            .await
+    }
+
+    /// Like `ensure_activated`, but `force` always re-activates. A response
+    /// built from the stored activation has `from_cache` set.
+    pub async fn activate_if_needed(
+        &self,
+        license_key: &str,
+        device_id: Option<String>,
+        app_version: Option<String>,
+        device_meta: Option<serde_json::Value>,
+        force: bool,
+    ) -> Result<ActivateResponse, AlureError> {
+        let device_id = match device_id {
+            Some(value) => value,
+            None => self.default_device_id()?,
+        };
+        if !force {
+            if let Some(response) = self.stored_activation(&device_id)? {
+                tracing::debug!(activation_id = %response.activation_id, "reusing stored activation");
+                return Ok(response);
+            }
+        }
+        self.activate(license_key, Some(device_id), app_version, device_meta)
+            .await
+    }
+
+    /// The stored activation, when it belongs to `device_id` and its receipt
+    /// is valid, outside grace, and issued for the stored `activation_id`.
+    fn stored_activation(&self, device_id: &str) -> Result<Option<ActivateResponse>, AlureError> {
+        let Some(stored) = self.receipts.load_receipt()? else {
+            return Ok(None);
+        };
+        let Some(activation_id) = stored.activation_id.filter(|id| !id.is_empty()) else {
+            return Ok(None);
+        };
+        if stored.device_id != device_id {
+            return Ok(None);
+        }
+        let claims = self.verifier.parse(&stored.receipt).ok();
+        let claimed = claims
+            .as_ref()
+            .and_then(|payload| payload.get("activation_id")?.as_str());
+        if claimed.is_some_and(|claimed| claimed != activation_id) {
+            return Ok(None);
+        }
+        let result = self.validate_at_now(
+            &stored.receipt,
+            device_id,
+            self.verifier.is_configured_for_signatures(),
+        );
+        if !result.valid || result.reason.is_some() {
+            return Ok(None);
+        }
+        Ok(Some(ActivateResponse {
+            receipt: stored.receipt,
+            activation_id,
+            expires_at: result.expires_at,
+            grace_period_days: result.grace_period_days.unwrap_or(0),
+            server_time: String::new(),
+            from_cache: true,
+            project_id: claims.as_ref().and_then(project_id_claim),
+            features: claims.as_ref().and_then(features_claim),
+            tier: claims.as_ref().and_then(tier_claim),
+            claims,
+            idempotency_key: None,
+        }))
+    }
+
+    /// Asks the server about `receipt`. A `grace_period_days` in the
+    /// response is persisted and, for as long as this receipt is the one
+    /// checked, overrides the receipt's own claim in every offline check;
+    /// a later response with a different value replaces it, one without
+    /// leaves it in place.
+    pub async fn verify_online(
+        &self,
+        receipt: Option<String>,
+        device_id: Option<String>,
+        device_meta: Option<serde_json::Value>,
+    ) -> Result<VerifyResult, AlureError> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let result = self
+            .send_verify(receipt, device_id, device_meta, &request_id)
+            .await;
+        self.log_event(
+            "verify",
+            &request_id,
+            result.as_ref().map(|result| {
+                serde_json::to_value(result.status)
+                    .ok()
+                    .and_then(|status| status.as_str().map(str::to_string))
+                    .unwrap_or_default()
+            }),
+        );
+        result
+    }
+
+    async fn send_verify(
+        &self,
+        receipt: Option<String>,
+        device_id: Option<String>,
+        device_meta: Option<serde_json::Value>,
+        request_id: &str,
+    ) -> Result<VerifyResult, AlureError> {
+        let (receipt, device_id) = match (receipt, device_id) {
+            (Some(receipt), Some(device_id)) => (receipt, device_id),
+            _ => {
+                let stored = self.receipts.load_receipt()?.ok_or_else(missing_receipt)?;
+                (stored.receipt, stored.device_id)
+            }
+        };
+        let mut payload = serde_json::json!({
+            "receipt": receipt,
+            "device_id": device_id,
+        });
+        self.mark_hashed_device_id(&mut payload);
+        if let Some(meta) = device_meta {
+            payload["device_meta"] = meta;
+        }
+        let sent_at = self.clock.now();
+        let (data, headers) = self
+            .request_with_headers(
+                reqwest::Method::POST,
+                &self.endpoints.verify,
+                Some(payload),
+                None,
+                Some(vec![(
+                    REQUEST_ID_HEADER.to_string(),
+                    request_id.to_string(),
+                )]),
+            )
+            .await?;
+        let received_at = self.clock.now();
+        if self.verify_response_signatures {
+            self.check_response_signature(&self.endpoints.verify, &data)?;
+        }
+        let mut result = VerifyResult::from_value(data);
+        result.headers = headers;
+        if self.sync_server_time {
+            if let Some(server_time) = result
+                .server_time
+                .as_deref()
+                .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            {
+                let server_time = server_time.with_timezone(&Utc);
+                if let Err(err) = self.apply_clock_drift(server_time, sent_at, received_at) {
+                    tracing::warn!(error = %err, "could not persist clock drift");
+                }
+            }
+        }
+        self.record_seen_time();
+        if let Some(grace_period_days) = result.grace_period_days {
+            let grace = GraceOverride {
+                receipt_fingerprint: ReceiptVerifier::fingerprint(&receipt),
+                grace_period_days,
+                received_at: self.now(),
+            };
+            if let Err(err) = self.storage.save_grace_override(&grace) {
+                tracing::warn!(error = %err, "could not persist the server grace period");
+            }
+        }
+        self.cache_online_result(&receipt, &result);
+        Ok(result)
+    }
+
+    fn check_response_signature(
+        &self,
+        path: &str,
+        data: &serde_json::Value,
+    ) -> Result<(), AlureError> {
+        let failure = |reason: &str| AlureError::ResponseSignature {
+            path: path.to_string(),
+            reason: reason.to_string(),
+        };
+        let signature = data
+            .get("signature")
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| failure("missing_signature"))?;
+        let mut unsigned = data.clone();
+        if let Some(object) = unsigned.as_object_mut() {
+            object.remove("signature");
+        }
+        self.verifier
+            .verify_detached(canonical_json(&unsigned).as_bytes(), signature)
+            .map_err(|_| failure("signature_mismatch"))
+    }
+
+    /// Remembers a valid online answer for `verify`; an invalid one drops
+    /// the cache so it can never outlive a revocation.
+    fn cache_online_result(&self, receipt: &str, result: &VerifyResult) {
+        let outcome = if result.allows_access() {
+            self.storage.save_online_verification(&OnlineVerification {
+                verified_at: self.now(),
+                receipt_fingerprint: ReceiptVerifier::fingerprint(receipt),
+                result: result.clone(),
+            })
+        } else {
+            self.storage.delete_online_verification()
+        };
+        if let Err(err) = outcome {
+            tracing::warn!(error = %err, "could not update the online verification cache");
+        }
+    }
+
+    /// Soft-offline check of the stored receipt: trusts the last successful
+    /// online verification while it is younger than `max_online_age`, then
+    /// goes online, and only when the server is unreachable falls back to
+    /// offline validation.
+    pub async fn verify(
+        &self,
+        device_meta: Option<serde_json::Value>,
+        max_online_age: std::time::Duration,
+    ) -> Result<ReceiptValidationResult, AlureError> {
+        let Some(stored) = self.receipts.load_receipt()? else {
+            return Ok(ReceiptValidationResult::invalid("missing_receipt"));
         };
-        self.storage.save_receipt(&record)?;
-        Ok(ActivateResponse {
-            receipt,
-            activation_id,
-            expires_at,
-            grace_period_days,
-            server_time,
-        })
+        if let Some(cached) = self.storage.load_online_verification()? {
+            let age = self.now() - cached.verified_at;
+            let fresh = age.to_std().is_ok_and(|age| age <= max_online_age);
+            if fresh && cached.receipt_fingerprint == stored.fingerprint() {
+                return Ok(ReceiptValidationResult {
+                    valid: cached.result.valid,
+                    reason: cached.result.reason,
+                    expires_at: cached.result.expires_at,
+                    grace_period_days: None,
+                    grace_until: None,
+                });
+            }
+        }
+        match self.refresh(device_meta).await {
+            Err(err) if is_unreachable(&err) => {
+                tracing::debug!(error = %err, "server unreachable, validating offline");
+                self.verify_offline(None, None, self.verifier.is_configured_for_signatures())
+            }
+            other => other,
+        }
     }
 
-    pub async fn verify_online(
+    /// Defense in depth: the receipt must pass `validate_offline` with its
+    /// signature checked and `verify_online` must allow access. A failing
+    /// side is named in `reason` (`offline_failed: ...` or
+    /// `online_failed: ...`); when the offline check fails the server is not
+    /// contacted. Unlike `verify`, network errors are returned, never masked.
+    pub async fn verify_strict(
         &self,
         receipt: Option<String>,
         device_id: Option<String>,
-    ) -> Result<serde_json::Value, AlureError> {
+        device_meta: Option<serde_json::Value>,
+    ) -> Result<ReceiptValidationResult, AlureError> {
         let (receipt, device_id) = match (receipt, device_id) {
             (Some(receipt), Some(device_id)) => (receipt, device_id),
-            _ => {
-                let stored = self
-                    .storage
-                    .load_receipt()?
-                    .ok_or_else(|| AlureError::Http {
-                        status: 400,
-                        message: "missing_receipt".to_string(),
-                    })?;
-                (stored.receipt, stored.device_id)
-            }
+            _ => match self.receipts.load_receipt()? {
+                Some(stored) => (stored.receipt, stored.device_id),
+                None => return Ok(ReceiptValidationResult::invalid("missing_receipt")),
+            },
         };
-        let payload = serde_json::json!({
-            "receipt": receipt,
-            "device_id": device_id,
-        });
-        self.request(
-            reqwest::Method::POST,
-            "/licenses/verify",
-            Some(payload),
-            None,
-            None,
-        )
-        .await
+        let offline = self.validate_at_now(&receipt, &device_id, true);
+        if !offline.valid {
+            let reason = offline.reason.as_deref().unwrap_or("invalid");
+            return Ok(ReceiptValidationResult {
+                reason: Some(format!("offline_failed: {reason}")),
+                ..offline
+            });
+        }
+        let online = self
+            .verify_online(Some(receipt), Some(device_id), device_meta)
+            .await?;
+        if !online.allows_access() {
+            let reason = match (&online.reason, online.revoked) {
+                (Some(reason), _) => reason.clone(),
+                (None, true) => "revoked".to_string(),
+                (None, false) => "invalid".to_string(),
+            };
+            return Ok(ReceiptValidationResult {
+                valid: false,
+                reason: Some(format!("online_failed: {reason}")),
+                ..offline
+            });
+        }
+        Ok(offline)
+    }
+
+    /// Re-verifies the stored receipt online, persisting any `new_receipt`
+    /// issued by the server, and returns the resulting validation state.
+    pub async fn refresh(
+        &self,
+        device_meta: Option<serde_json::Value>,
+    ) -> Result<ReceiptValidationResult, AlureError> {
+        let stored = self.receipts.load_receipt()?.ok_or_else(missing_receipt)?;
+        let data = self
+            .verify_online(
+                Some(stored.receipt.clone()),
+                Some(stored.device_id.clone()),
+                device_meta,
+            )
+            .await?;
+        if !data.allows_access() {
+            let reason = match (data.reason, data.revoked) {
+                (Some(reason), _) => reason,
+                (None, true) => "revoked".to_string(),
+                (None, false) => "invalid".to_string(),
+            };
+            return Ok(ReceiptValidationResult {
+                valid: false,
+                reason: Some(reason),
+                expires_at: data.expires_at,
+                grace_period_days: None,
+                grace_until: None,
+            });
+        }
+        let mut record = stored;
+        if let Some(new_receipt) = data.new_receipt {
+            record.receipt = new_receipt;
+            record.project_id = self.extract_project_id(&record.receipt).ok().flatten();
+            self.receipts.save_receipt(&record)?;
+            tracing::debug!(fingerprint = %record.fingerprint(), "stored refreshed receipt");
+        }
+        Ok(self.validate_at_now(
+            &record.receipt,
+            &record.device_id,
+            self.verifier.is_configured_for_signatures(),
+        ))
     }
 
     pub fn verify_offline(
@@ -205,52 +2235,174 @@ impl AlureClient {
         let (receipt, device_id) = match (receipt, device_id) {
             (Some(receipt), Some(device_id)) => (receipt, device_id),
             _ => {
-                let stored = self.storage.load_receipt()?;
+                let stored = self.receipts.load_receipt()?;
                 if let Some(stored) = stored {
                     (stored.receipt, stored.device_id)
                 } else {
-                    return Ok(ReceiptValidationResult {
-                        valid: false,
-                        reason: Some("missing_receipt".to_string()),
-                        expires_at: None,
-                        grace_period_days: None,
-                    });
+                    return Ok(ReceiptValidationResult::invalid("missing_receipt"));
                 }
             }
         };
-        Ok(self
-            .verifier
-            .validate_offline(&receipt, &device_id, None, verify_signature))
+        Ok(self.validate_at_now(&receipt, &device_id, verify_signature))
+    }
+
+    /// Like `verify_offline`, but evaluated at `at` instead of now, to tell
+    /// whether a receipt was (or will be) valid then. The clock rollback
+    /// check and the last-seen time are left out, as neither concerns `at`.
+    pub fn verify_offline_at(
+        &self,
+        receipt: Option<String>,
+        device_id: Option<String>,
+        at: DateTime<Utc>,
+        verify_signature: bool,
+    ) -> Result<ReceiptValidationResult, AlureError> {
+        let (receipt, device_id) = match (receipt, device_id) {
+            (Some(receipt), Some(device_id)) => (receipt, device_id),
+            _ => match self.receipts.load_receipt()? {
+                Some(stored) => (stored.receipt, stored.device_id),
+                None => return Ok(ReceiptValidationResult::invalid("missing_receipt")),
+            },
+        };
+        Ok(self.verifier.validate_with_grace_at(
+            &receipt,
+            &device_id,
+            at,
+            verify_signature,
+            self.grace_override_for(&receipt),
+        ))
+    }
+
+    /// The stored receipt's plan; `None` without a stored receipt or claim.
+    pub fn current_plan(&self) -> Result<Option<String>, AlureError> {
+        match self.receipts.load_receipt()? {
+            Some(stored) => Ok(self.verifier.plan(&stored.receipt)?),
+            None => Ok(None),
+        }
+    }
+
+    /// The stored receipt's features (or module keys), only once it passes
+    /// offline validation with its signature checked. An invalid receipt
+    /// fails with `ReceiptError` carrying the validation reason.
+    pub fn authorized_features(&self) -> Result<Vec<String>, AlureError> {
+        let stored = self.receipts.load_receipt()?.ok_or_else(missing_receipt)?;
+        let result = self.validate_at_now(&stored.receipt, &stored.device_id, true);
+        if !result.valid {
+            let reason = result
+                .reason
+                .unwrap_or_else(|| "invalid_receipt".to_string());
+            return Err(ReceiptError(reason).into());
+        }
+        let claims = self.verifier.parse(&stored.receipt)?;
+        Ok(features_claim(&claims).unwrap_or_default())
     }
 
+    /// Verifies a signed license bundle (see `LicenseBundle`) against the
+    /// configured key and stores its receipt, without any network access.
+    pub fn import_bundle(&self, path: impl AsRef<Path>) -> Result<ReceiptRecord, AlureError> {
+        let bytes = std::fs::read(path.as_ref())?;
+        let record = LicenseBundle::from_slice(&bytes)?.open(&self.verifier)?;
+        self.receipts.save_receipt(&record)?;
+        tracing::debug!(fingerprint = %record.fingerprint(), "imported license bundle");
+        Ok(record)
+    }
+
+    /// Asks for the latest release on `channel`. A release whose version
+    /// equals `current_version` counts as up to date.
     pub async fn check_update(
         &self,
         project_id: &str,
         channel: &str,
         current_version: Option<String>,
-    ) -> Result<serde_json::Value, AlureError> {
+    ) -> Result<UpdateCheck, AlureError> {
         let mut query = vec![
             ("project_id".to_string(), project_id.to_string()),
             ("channel".to_string(), channel.to_string()),
         ];
-        if let Some(current_version) = current_version {
-            query.push(("current_version".to_string(), current_version));
+        if let Some(current_version) = &current_version {
+            query.push(("current_version".to_string(), current_version.clone()));
         }
-        self.request(
-            reqwest::Method::GET,
-            "/updates/latest",
-            None,
-            Some(query),
-            None,
-        )
-        .await
+        let data: serde_json::Value = self
+            .request(
+                reqwest::Method::GET,
+                &self.endpoints.update_latest,
+                None,
+                Some(query),
+                None,
+            )
+            .await
+            .map_err(|err| match err {
+                AlureError::Http {
+                    status: 404,
+                    request_id,
+                    ..
+                } => AlureError::UpdateNotFound {
+                    project_id: project_id.to_string(),
+                    channel: channel.to_string(),
+                    request_id,
+                },
+                other => other,
+            })?;
+        if self.verify_update_signatures {
+            self.check_response_signature(&self.endpoints.update_latest, &data)?;
+            let asset = data.get("asset").filter(|asset| !asset.is_null());
+            if asset.is_some_and(|asset| asset.get("sha256").and_then(|v| v.as_str()).is_none()) {
+                return Err(AlureError::ResponseSignature {
+                    path: self.endpoints.update_latest.clone(),
+                    reason: "unbound_asset".to_string(),
+                });
+            }
+        }
+        let server_time = data
+            .get("server_time")
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+        let available = data
+            .get("update_available")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        let info = match available {
+            true => Some(serde_json::from_value::<UpdateInfo>(data)?),
+            false => None,
+        };
+        match info {
+            Some(info) if current_version.as_deref() != Some(info.latest_version.as_str()) => {
+                Ok(UpdateCheck::Available(Box::new(info)))
+            }
+            _ => Ok(UpdateCheck::UpToDate {
+                current_version,
+                server_time,
+            }),
+        }
+    }
+
+    /// Runs `check_update` on every channel concurrently, keyed by channel.
+    /// A failing channel (e.g. `UpdateNotFound`) only fails its own entry.
+    pub async fn check_updates(
+        &self,
+        project_id: &str,
+        channels: &[&str],
+        current_version: Option<String>,
+    ) -> HashMap<String, Result<UpdateCheck, AlureError>> {
+        let checks = channels.iter().map(|channel| {
+            let current_version = current_version.clone();
+            async move {
+                let check = self
+                    .check_update(project_id, channel, current_version)
+                    .await;
+                (channel.to_string(), check)
+            }
+        });
+        futures_util::future::join_all(checks)
+            .await
+            .into_iter()
+            .collect()
     }
 
     pub fn project_id_from_receipt(&self, receipt: Option<String>) -> Result<Option<String>, AlureError> {
         let receipt = match receipt {
             Some(receipt) => receipt,
             None => {
-                let stored = self.storage.load_receipt()?;
+                let stored = self.receipts.load_receipt()?;
                 if let Some(stored) = stored {
                     stored.receipt
                 } else {
@@ -266,20 +2418,29 @@ impl AlureClient {
         receipt: &str,
         device_id: &str,
         asset_id: &str,
-    ) -> Result<serde_json::Value, AlureError> {
-        let payload = serde_json::json!({
+    ) -> Result<DownloadToken, AlureError> {
+        let mut payload = serde_json::json!({
             "receipt": receipt,
             "device_id": device_id,
             "asset_id": asset_id,
         });
-        self.request(
-            reqwest::Method::POST,
-            "/updates/download-token",
-            Some(payload),
-            None,
-            None,
-        )
-        .await
+        self.mark_hashed_device_id(&mut payload);
+        let token: DownloadToken = self
+            .request(
+                reqwest::Method::POST,
+                &self.endpoints.download_token,
+                Some(payload),
+                None,
+                None,
+            )
+            .await?;
+        if token.token.is_empty() {
+            return Err(AlureError::EmptyResponse(format!(
+                "{} (missing token)",
+                self.endpoints.download_token
+            )));
+        }
+        Ok(token)
     }
 
     pub async fn download_asset(
@@ -290,83 +2451,583 @@ impl AlureClient {
         token: Option<String>,
         dest_path: Option<PathBuf>,
     ) -> Result<PathBuf, AlureError> {
-        let token = match token {
-            Some(token) => token,
+        // Kept for callers that only need the path.
+        let result = self
+            .download_asset_file(asset_id, receipt, device_id, token, dest_path)
+            .await?;
+        Ok(result.path)
+    }
+
+    /// Downloads `asset_ids` into the downloads directory, at most
+    /// `concurrency` at a time over the shared connection pool. One result
+    /// per asset, in input order; a failed asset does not stop the others.
+    /// The receipt is resolved once up front, so a missing one fails the
+    /// whole call.
+    pub async fn download_assets(
+        &self,
+        asset_ids: &[&str],
+        concurrency: usize,
+        receipt: Option<String>,
+        device_id: Option<String>,
+    ) -> Result<Vec<Result<DownloadResult, AlureError>>, AlureError> {
+        let (receipt, device_id) = match (receipt, device_id) {
+            (Some(receipt), Some(device_id)) => (receipt, device_id),
+            _ => {
+                let stored = self.receipts.load_receipt()?.ok_or_else(missing_receipt)?;
+                (stored.receipt, stored.device_id)
+            }
+        };
+        let mut results: Vec<_> = stream::iter(asset_ids.iter().enumerate())
+            .map(|(index, asset_id)| {
+                let (receipt, device_id) = (receipt.clone(), device_id.clone());
+                async move {
+                    let result = self
+                        .download_asset_file(asset_id, Some(receipt), Some(device_id), None, None)
+                        .await;
+                    (index, result)
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        results.sort_by_key(|(index, _)| *index);
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+
+    /// Like `download_asset`, but also reports the size, checksum and
+    /// whether an earlier download at the same path was replaced.
+    pub async fn download_asset_file(
+        &self,
+        asset_id: &str,
+        receipt: Option<String>,
+        device_id: Option<String>,
+        token: Option<String>,
+        dest_path: Option<PathBuf>,
+    ) -> Result<DownloadResult, AlureError> {
+        // Only a token fetched here may be replaced after a rejection.
+        let mut may_refresh = token.is_none();
+        let mut token = self
+            .resolve_download_token(asset_id, receipt.clone(), device_id.clone(), token)
+            .await?;
+        let mut url = self.asset_url(asset_id, &token);
+        let partial = match &dest_path {
+            Some(path) => {
+                if let Some(parent) = path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                {
+                    tokio::fs::create_dir_all(parent).await.map_err(|err| {
+                        StorageError(format!("create_dir_failed: {}: {err}", parent.display()))
+                    })?;
+                }
+                let mut partial = path.clone().into_os_string();
+                partial.push(".part");
+                PathBuf::from(partial)
+            }
+            None => self
+                .storage
+                .downloads_dir()?
+                .join(format!("{asset_id}.part")),
+        };
+
+        // A resumed file that fails the checksum may be a corrupt partial:
+        // start over from scratch once rather than resuming it again.
+        let mut resume = true;
+        let (headers, content) = loop {
+            let (headers, resumed) = match self.fetch_download_to(&url, &partial, resume).await {
+                Err(err) if may_refresh && is_token_rejection(&err) => {
+                    tracing::debug!(asset_id, "download token rejected, requesting a new one");
+                    may_refresh = false;
+                    token = self
+                        .resolve_download_token(asset_id, receipt.clone(), device_id.clone(), None)
+                        .await?;
+                    url = self.asset_url(asset_id, &token);
+                    continue;
+                }
+                result => result?,
+            };
+            let content = tokio::fs::read(&partial).await?;
+            match verify_checksum(&token, &content) {
+                Ok(()) => break (headers, content),
+                Err(err) => {
+                    let _ = tokio::fs::remove_file(&partial).await;
+                    if resumed {
+                        tracing::warn!(asset_id, "resumed download failed checksum, restarting");
+                        resume = false;
+                        continue;
+                    }
+                    return Err(err);
+                }
+            }
+        };
+
+        let target = match dest_path {
+            Some(path) => path,
+            None => {
+                let filename = headers
+                    .get(reqwest::header::CONTENT_DISPOSITION)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(extract_filename)
+                    .unwrap_or_else(|| format!("{asset_id}.bin"));
+                self.storage.downloads_dir()?.join(filename)
+            }
+        };
+        if let Err(err) = self
+            .verify_asset_signature(asset_id, &token, &content)
+            .await
+        {
+            // Only the partial is ours: `target` may be a file the caller
+            // or an earlier download put there.
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(err);
+        }
+        let replaced_existing = tokio::fs::try_exists(&target).await.unwrap_or(false);
+        tokio::fs::rename(&partial, &target).await?;
+        Ok(DownloadResult {
+            asset_id: asset_id.to_string(),
+            path: target,
+            bytes_written: content.len() as u64,
+            replaced_existing,
+            sha256: sha256_hex(&content),
+        })
+    }
+
+    /// Like `download_asset`, but keeps the asset in memory. Bodies larger
+    /// than `max_bytes` are rejected with `asset_too_large` while streaming.
+    pub async fn download_asset_bytes(
+        &self,
+        asset_id: &str,
+        receipt: Option<String>,
+        device_id: Option<String>,
+        token: Option<String>,
+        max_bytes: usize,
+    ) -> Result<Vec<u8>, AlureError> {
+        let mut may_refresh = token.is_none();
+        let mut token = self
+            .resolve_download_token(asset_id, receipt.clone(), device_id.clone(), token)
+            .await?;
+        let content = loop {
+            let request = self.download_request(self.asset_url(asset_id, &token))?;
+            let request_id = request_id_of(&request);
+            let mut buffer = Throttled::new(
+                CappedBuffer::new(max_bytes),
+                self.download_limiter.as_deref(),
+            );
+            match self
+                .send(
+                    &self.endpoints.download,
+                    request,
+                    request_id,
+                    Some(&mut buffer),
+                )
+                .await
+            {
+                Ok(_) => break buffer.into_inner().into_inner(),
+                Err(err) if may_refresh && is_token_rejection(&err) => {
+                    tracing::debug!(asset_id, "download token rejected, requesting a new one");
+                    may_refresh = false;
+                    token = self
+                        .resolve_download_token(asset_id, receipt.clone(), device_id.clone(), None)
+                        .await?;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        verify_checksum(&token, &content)?;
+        self.verify_asset_signature(asset_id, &token, &content)
+            .await?;
+        Ok(content)
+    }
+
+    async fn resolve_download_token(
+        &self,
+        asset_id: &str,
+        receipt: Option<String>,
+        device_id: Option<String>,
+        token: Option<String>,
+    ) -> Result<DownloadToken, AlureError> {
+        Ok(match token {
+            Some(token) => DownloadToken {
+                token,
+                expires_at: None,
+                download_url: None,
+                sha256: None,
+                signature: None,
+            },
             None => {
                 let (receipt, device_id) = match (receipt, device_id) {
                     (Some(receipt), Some(device_id)) => (receipt, device_id),
                     _ => {
-                        let stored = self
-                            .storage
-                            .load_receipt()?
-                            .ok_or_else(|| AlureError::Http {
-                                status: 400,
-                                message: "missing_receipt".to_string(),
-                            })?;
+                        let stored = self.receipts.load_receipt()?.ok_or_else(missing_receipt)?;
                         (stored.receipt, stored.device_id)
                     }
                 };
-                let token_resp = self
-                    .request_download_token(&receipt, &device_id, asset_id)
-                    .await?;
-                token_resp
-                    .get("token")
-                    .and_then(|value| value.as_str())
-                    .unwrap_or_default()
-                    .to_string()
+                self.request_download_token(&receipt, &device_id, asset_id)
+                    .await?
+            }
+        })
+    }
+
+    fn asset_url(&self, asset_id: &str, token: &DownloadToken) -> String {
+        match &token.download_url {
+            Some(url) if url.starts_with('/') => format!("{}{}", self.current_base_url(), url),
+            Some(url) => url.clone(),
+            None => self.download_url(asset_id, "", &token.token),
+        }
+    }
+
+    /// Checks the detached signature when `with_asset_signatures` is on.
+    async fn verify_asset_signature(
+        &self,
+        asset_id: &str,
+        token: &DownloadToken,
+        content: &[u8],
+    ) -> Result<(), AlureError> {
+        if !self.verify_asset_signatures {
+            return Ok(());
+        }
+        let signature = match &token.signature {
+            Some(signature) => signature.clone(),
+            None => {
+                let url = self.download_url(asset_id, ".sig", &token.token);
+                let resp = self.fetch_download(url).await?;
+                String::from_utf8_lossy(&resp.body).into_owned()
             }
         };
+        self.verifier
+            .verify_detached(content, &signature)
+            .map_err(|err| ReceiptError(format!("asset_{}", err.0)).into())
+    }
 
-        let url = format!(
-            "{}/updates/download/{}?token={}",
-            self.base_url,
+    fn download_url(&self, asset_id: &str, suffix: &str, token: &str) -> String {
+        format!(
+            "{}{}/{}{}?token={}",
+            self.current_base_url(),
+            self.endpoints.download,
             asset_id,
-            urlencoding::encode(&token)
-        );
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(self.timeout_seconds))
-            .build()?;
-        let resp = client.get(url).send().await?;
-        let status = resp.status();
-        if !status.is_success() {
-            let message = resp.text().await.unwrap_or_default();
-            return Err(AlureError::Http {
-                status: status.as_u16(),
-                message,
-            });
+            suffix,
+            urlencoding::encode(token)
+        )
+    }
+
+    /// Downloads `url` into `partial`, continuing an existing partial file
+    /// with a `Range` request when `resume` is set. Returns the response
+    /// headers and whether the server actually resumed.
+    async fn fetch_download_to(
+        &self,
+        url: &str,
+        partial: &Path,
+        resume: bool,
+    ) -> Result<(HeaderMap, bool), AlureError> {
+        let offset = match tokio::fs::metadata(partial).await {
+            Ok(metadata) if resume => metadata.len(),
+            _ => 0,
+        };
+        let mut request = self.download_request(url.to_string())?;
+        if offset > 0 {
+            insert_header(&mut request.headers, "Range", &format!("bytes={offset}-"))?;
         }
-        let content = resp.bytes().await?;
-        let filename = resp
-            .headers()
-            .get(reqwest::header::CONTENT_DISPOSITION)
-            .and_then(|value| value.to_str().ok())
-            .and_then(extract_filename)
-            .unwrap_or_else(|| format!("{asset_id}.bin"));
-        let target = match dest_path {
-            Some(path) => path,
-            None => {
-                let downloads = self.storage.downloads_dir()?;
-                downloads.join(filename)
+        let request_id = request_id_of(&request);
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(offset > 0)
+            .write(true)
+            .truncate(offset == 0)
+            .open(partial)
+            .await?;
+        let mut file = Throttled::new(file, self.download_limiter.as_deref());
+        let resp = match self
+            .send(
+                &self.endpoints.download,
+                request,
+                request_id,
+                Some(&mut file),
+            )
+            .await
+        {
+            Ok(resp) => resp,
+            // The partial is already complete or longer than the asset.
+            Err(AlureError::Http { status: 416, .. }) if offset > 0 => {
+                drop(file);
+                return Box::pin(self.fetch_download_to(url, partial, false)).await;
+            }
+            Err(err) => {
+                // Keep whatever arrived so the next attempt can resume it.
+                drop(file);
+                if tokio::fs::metadata(partial)
+                    .await
+                    .is_ok_and(|metadata| metadata.len() == 0)
+                {
+                    let _ = tokio::fs::remove_file(partial).await;
+                }
+                return Err(err);
             }
         };
-        tokio::fs::write(&target, &content).await?;
-        Ok(target)
+        drop(file);
+        let resumed = offset > 0 && resp.status == reqwest::StatusCode::PARTIAL_CONTENT.as_u16();
+        if offset > 0 && !resumed {
+            // The server ignored the range and sent everything after the old
+            // partial bytes; keep only the fresh copy.
+            let content = tokio::fs::read(partial).await?;
+            tokio::fs::write(partial, &content[offset as usize..]).await?;
+        }
+        Ok((resp.headers, resumed))
+    }
+
+    fn download_request(&self, url: String) -> Result<TransportRequest, AlureError> {
+        let mut request = TransportRequest {
+            method: reqwest::Method::GET,
+            url,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            timeout: self
+                .http_options
+                .download_timeout
+                .or(self.http_options.request_timeout),
+        };
+        insert_header(
+            &mut request.headers,
+            REQUEST_ID_HEADER,
+            &uuid::Uuid::new_v4().to_string(),
+        )?;
+        Ok(request)
+    }
+
+    async fn fetch_download(&self, url: String) -> Result<TransportResponse, AlureError> {
+        let request = self.download_request(url)?;
+        let request_id = request_id_of(&request);
+        self.send(&self.endpoints.download, request, request_id, None)
+            .await
+    }
+
+    /// Removes downloads (including partial `.part` files) last modified more
+    /// than `max_age` ago by the client's clock.
+    pub fn cleanup_downloads(
+        &self,
+        max_age: std::time::Duration,
+    ) -> Result<Vec<PathBuf>, AlureError> {
+        let now = self.now();
+        Ok(self.storage.cleanup_downloads(|entry| {
+            (now - DateTime::<Utc>::from(entry.modified))
+                .to_std()
+                .map(|age| age <= max_age)
+                .unwrap_or(true)
+        })?)
     }
 
     fn extract_project_id(&self, receipt: &str) -> Result<Option<String>, ReceiptError> {
         let payload = self.verifier.parse(receipt)?;
-        Ok(payload
-            .get("project_id")
-            .and_then(|value| value.as_str())
-            .map(str::to_string))
+        Ok(project_id_claim(&payload))
+    }
+}
+
+fn project_id_claim(payload: &serde_json::Value) -> Option<String> {
+    payload
+        .get("project_id")
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+}
+
+fn features_claim(payload: &serde_json::Value) -> Option<Vec<String>> {
+    let strings = |values: &Vec<serde_json::Value>, key: Option<&str>| {
+        values
+            .iter()
+            .filter_map(|value| match key {
+                Some(key) => value.get(key)?.as_str(),
+                None => value.as_str(),
+            })
+            .map(str::to_string)
+            .collect()
+    };
+    if let Some(features) = payload.get("features").and_then(|value| value.as_array()) {
+        return Some(strings(features, None));
+    }
+    payload
+        .get("modules")
+        .and_then(|value| value.as_array())
+        .map(|modules| strings(modules, Some("key")))
+}
+
+/// A 401/403 from the download route, typically an expired token.
+fn is_token_rejection(err: &AlureError) -> bool {
+    matches!(
+        err,
+        AlureError::Http {
+            status: 401 | 403,
+            ..
+        }
+    )
+}
+
+/// `EventRecord::result` for a failure: a code, never the server's message.
+fn event_failure(err: &AlureError) -> String {
+    match err {
+        AlureError::License { code, .. } => code.as_str().to_string(),
+        AlureError::Http { status, .. } => format!("http_{status}"),
+        AlureError::Receipt(err) => err.0.clone(),
+        _ if err.exit_code() == exit_code::NETWORK => "network".to_string(),
+        _ => "error".to_string(),
+    }
+}
+
+fn tier_claim(payload: &serde_json::Value) -> Option<String> {
+    payload
+        .get("tier")
+        .or_else(|| payload.get("plan"))
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+}
+
+/// The error for an operation that needs a stored receipt when there is none.
+fn missing_receipt() -> AlureError {
+    AlureError::Http {
+        status: 400,
+        message: "missing_receipt".to_string(),
+        request_id: None,
+    }
+}
+
+/// Maps a 204 onto `T`: `null` covers `()` and `Option<_>`, `{}` covers
+/// maps and structs whose fields all have defaults; anything else is an
+/// `EmptyResponse` error rather than a misleading deserialization failure.
+fn empty_response<T: for<'de> Deserialize<'de>>(path: &str) -> Result<T, AlureError> {
+    serde_json::from_value(serde_json::Value::Null)
+        .or_else(|_| serde_json::from_value(serde_json::json!({})))
+        .map_err(|_| AlureError::EmptyResponse(path.to_string()))
+}
+
+/// Failures that say nothing about the license: network errors and 5xx.
+fn is_unreachable(err: &AlureError) -> bool {
+    match err {
+        AlureError::Reqwest(_) | AlureError::Transport(_) => true,
+        AlureError::Http { status, .. } => *status >= 500,
+        _ => false,
     }
 }
 
+/// Prefers the id echoed by the server, which is what its logs will show.
+fn response_request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn request_id_of(request: &TransportRequest) -> String {
+    request
+        .headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Compact JSON with object keys sorted, whatever map order serde_json
+/// was built with.
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|key| {
+                    format!(
+                        "{}:{}",
+                        serde_json::Value::String(key.clone()),
+                        canonical_json(&map[key])
+                    )
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        serde_json::Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn sha256_matches(content: &[u8], expected: &str) -> bool {
+    sha256_hex(content).eq_ignore_ascii_case(expected.trim())
+}
+
+/// Checks a downloaded asset against the token's `sha256`, when it has one.
+fn verify_checksum(token: &DownloadToken, content: &[u8]) -> Result<(), AlureError> {
+    match &token.sha256 {
+        Some(expected) if !sha256_matches(content, expected) => {
+            Err(ReceiptError("asset_checksum_mismatch".to_string()).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn error_message(body: &[u8]) -> String {
+    if body.len() <= MAX_ERROR_BODY {
+        return String::from_utf8_lossy(body).into_owned();
+    }
+    let mut message = String::from_utf8_lossy(&body[..MAX_ERROR_BODY]).into_owned();
+    message.push_str("...[truncated]");
+    message
+}
+
+fn decode_jwk_keys(keys: &[String]) -> Result<Vec<Vec<u8>>, ReceiptError> {
+    keys.iter()
+        .map(|key| {
+            Base64UrlCodec
+                .decode(key.trim_end_matches('='))
+                .map_err(|_| ReceiptError("invalid_public_key".to_string()))
+        })
+        .collect()
+}
+
+/// `max-age` from a `Cache-Control` value; `no-cache`/`no-store` mean 0.
+fn cache_max_age(value: &str) -> Option<u64> {
+    let directives: Vec<&str> = value.split(',').map(str::trim).collect();
+    if directives.iter().any(|directive| {
+        directive.eq_ignore_ascii_case("no-cache") || directive.eq_ignore_ascii_case("no-store")
+    }) {
+        return Some(0);
+    }
+    directives.iter().find_map(|directive| {
+        let (name, seconds) = directive.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("max-age") {
+            seconds.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+fn insert_header(headers: &mut HeaderMap, name: &str, value: &str) -> Result<(), AlureError> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|err| AlureError::Transport(format!("invalid_header: {err}")))?;
+    let value = HeaderValue::from_str(value)
+        .map_err(|err| AlureError::Transport(format!("invalid_header: {err}")))?;
+    headers.insert(name, value);
+    Ok(())
+}
+
+/// The `filename` of a `Content-Disposition` header, reduced to its last
+/// component. Absolute names and names with `..` are refused, so a response
+/// cannot place a file outside the downloads directory.
 fn extract_filename(content_disposition: &str) -> Option<String> {
     let filename_marker = "filename=";
-    content_disposition.find(filename_marker).map(|idx| {
-        content_disposition[idx + filename_marker.len()..]
-            .trim_matches('"')
-            .to_string()
-    })
+    let idx = content_disposition.find(filename_marker)?;
+    let name = Path::new(content_disposition[idx + filename_marker.len()..].trim_matches('"'));
+    if name.is_absolute()
+        || name
+            .components()
+            .any(|component| component == std::path::Component::ParentDir)
+    {
+        return None;
+    }
+    name.file_name()?.to_str().map(str::to_string)
 }
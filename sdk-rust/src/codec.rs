@@ -0,0 +1,46 @@
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine as _,
+};
+
+use crate::errors::ReceiptError;
+
+/// Encoding of the payload and signature segments of a receipt version,
+/// registered with `ReceiptVerifier::with_codec`.
+pub trait TokenCodec: std::fmt::Debug + Send + Sync {
+    fn decode(&self, segment: &str) -> Result<Vec<u8>, ReceiptError>;
+    fn encode(&self, bytes: &[u8]) -> String;
+}
+
+/// URL-safe base64 without padding: `v1` receipts and JWTs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Base64UrlCodec;
+
+impl TokenCodec for Base64UrlCodec {
+    fn decode(&self, segment: &str) -> Result<Vec<u8>, ReceiptError> {
+        URL_SAFE_NO_PAD
+            .decode(segment)
+            .map_err(|_| ReceiptError("invalid_encoding".to_string()))
+    }
+
+    fn encode(&self, bytes: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+}
+
+/// Standard padded base64, for servers that never adopted the URL-safe
+/// alphabet.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Base64StdCodec;
+
+impl TokenCodec for Base64StdCodec {
+    fn decode(&self, segment: &str) -> Result<Vec<u8>, ReceiptError> {
+        STANDARD
+            .decode(segment)
+            .map_err(|_| ReceiptError("invalid_encoding".to_string()))
+    }
+
+    fn encode(&self, bytes: &[u8]) -> String {
+        STANDARD.encode(bytes)
+    }
+}
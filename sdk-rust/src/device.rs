@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{AlureError, StorageError};
+use crate::storage::FileStorage;
+
+/// Inputs hashed into `AlureClient::default_device_id`.
+///
+/// Disabling the MAC address replaces it with a random install id persisted
+/// in the storage dir: no hardware identifier is read, but the id changes if
+/// that dir is wiped or the app is reinstalled elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceIdSources {
+    pub hostname: bool,
+    pub mac_address: bool,
+    pub username: bool,
+}
+
+impl Default for DeviceIdSources {
+    fn default() -> Self {
+        Self {
+            hostname: true,
+            mac_address: true,
+            username: true,
+        }
+    }
+}
+
+impl DeviceIdSources {
+    /// Only the persisted install id: no hostname, MAC address or username.
+    pub fn private() -> Self {
+        Self {
+            hostname: false,
+            mac_address: false,
+            username: false,
+        }
+    }
+}
+
+/// Canonical `device_meta` payload sent on activation and verification.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+}
+
+impl DeviceMeta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fills `os` and `arch` from `std::env::consts`, plus `os_version` and
+    /// `hostname` when the platform reports them.
+    pub fn detect() -> Self {
+        Self {
+            os: Some(std::env::consts::OS.to_string()),
+            os_version: whoami::fallible::distro().ok(),
+            arch: Some(std::env::consts::ARCH.to_string()),
+            app_version: None,
+            hostname: whoami::fallible::hostname().ok(),
+        }
+    }
+
+    pub fn os(mut self, os: impl Into<String>) -> Self {
+        self.os = Some(os.into());
+        self
+    }
+
+    pub fn os_version(mut self, os_version: impl Into<String>) -> Self {
+        self.os_version = Some(os_version.into());
+        self
+    }
+
+    pub fn arch(mut self, arch: impl Into<String>) -> Self {
+        self.arch = Some(arch.into());
+        self
+    }
+
+    pub fn app_version(mut self, app_version: impl Into<String>) -> Self {
+        self.app_version = Some(app_version.into());
+        self
+    }
+
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+}
+
+impl From<DeviceMeta> for serde_json::Value {
+    fn from(meta: DeviceMeta) -> Self {
+        serde_json::to_value(meta).unwrap_or_default()
+    }
+}
+
+pub(crate) fn derive_device_id(
+    sources: DeviceIdSources,
+    namespace: &uuid::Uuid,
+    storage: &FileStorage,
+) -> Result<String, AlureError> {
+    let mut components: Vec<(&'static str, Result<String, String>)> = Vec::new();
+    if sources.hostname {
+        let host = hostname::get()
+            .map(|value| value.to_string_lossy().to_string())
+            .map_err(|err| err.to_string());
+        components.push(("hostname", host));
+    }
+    if sources.mac_address {
+        let mac = mac_address::get_mac_address()
+            .map(|addr| {
+                addr.map(|addr| addr.to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            })
+            .map_err(|err| err.to_string());
+        components.push(("mac_address", mac));
+    } else {
+        components.push(("install_id", storage.install_id().map_err(|err| err.0)));
+    }
+    if sources.username {
+        let user = whoami::fallible::username().map_err(|err| err.to_string());
+        components.push(("username", user));
+    }
+    derive_from_components(namespace, components)
+}
+
+/// Hashes the available components into a v5 UUID under `namespace`. A
+/// failing component is replaced by a fixed placeholder so the id stays
+/// deterministic; only when every component fails is an error returned.
+pub(crate) fn derive_from_components(
+    namespace: &uuid::Uuid,
+    components: Vec<(&'static str, Result<String, String>)>,
+) -> Result<String, AlureError> {
+    let mut unavailable = Vec::new();
+    let mut parts = Vec::new();
+    for (name, value) in components {
+        match value {
+            Ok(value) => parts.push(value),
+            Err(err) => {
+                tracing::warn!(component = name, error = %err, "device id component unavailable");
+                unavailable.push(name);
+                parts.push(format!("unavailable-{name}"));
+            }
+        }
+    }
+    if parts.len() == unavailable.len() {
+        return Err(
+            StorageError(format!("device_id_unavailable: {}", unavailable.join(","))).into(),
+        );
+    }
+    let raw = parts.join("-");
+    Ok(uuid::Uuid::new_v5(namespace, raw.as_bytes()).to_string())
+}
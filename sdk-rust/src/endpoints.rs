@@ -0,0 +1,52 @@
+/// API routes, relative to the client's base URL. The defaults match the
+/// Alure server; override single routes for a server mounted differently:
+///
+/// ```
+/// let endpoints = alure_sdk::Endpoints {
+///     verify: "/v2/license/verify".to_string(),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    pub activate: String,
+    pub verify: String,
+    pub check: String,
+    /// Seats, plan and owner read by `license_info`.
+    pub info: String,
+    pub activations: String,
+    pub revoke: String,
+    /// Releases one activation, authenticated by its license key; used by
+    /// `migrate_device`.
+    pub deactivate: String,
+    /// Receives `activate_with_diagnostics` uploads, with the activation id
+    /// in the `activation_id` query parameter. The Alure server has no such
+    /// route, so it is unset by default.
+    pub diagnostics: Option<String>,
+    /// JWKS served to `fetch_verifying_keys`.
+    pub keys: String,
+    pub update_latest: String,
+    pub download_token: String,
+    /// Prefix of asset downloads: `{download}/{asset_id}` and
+    /// `{download}/{asset_id}.sig`.
+    pub download: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            activate: "/licenses/activate".to_string(),
+            verify: "/licenses/verify".to_string(),
+            check: "/licenses/check".to_string(),
+            info: "/licenses/info".to_string(),
+            activations: "/licenses/activations".to_string(),
+            revoke: "/licenses/revoke".to_string(),
+            deactivate: "/licenses/deactivate".to_string(),
+            diagnostics: None,
+            keys: "/licenses/keys".to_string(),
+            update_latest: "/updates/latest".to_string(),
+            download_token: "/updates/download-token".to_string(),
+            download: "/updates/download".to_string(),
+        }
+    }
+}
@@ -3,9 +3,38 @@ use thiserror::Error;
 #[derive(Debug, Error)]
 pub enum AlureError {
     #[error("http {status}: {message}")]
-    Http { status: u16, message: String },
+    Http {
+        status: u16,
+        message: String,
+        request_id: Option<String>,
+    },
+    #[error("license error {code}: {detail}")]
+    License {
+        code: LicenseErrorCode,
+        detail: String,
+        request_id: Option<String>,
+    },
+    /// `check_update` got a 404: the project or channel does not exist.
+    #[error("no release channel {channel} for project {project_id}")]
+    UpdateNotFound {
+        project_id: String,
+        channel: String,
+        request_id: Option<String>,
+    },
+    /// A response that must be signed (see `with_signed_verify_responses`)
+    /// is unsigned (`missing_signature`) or fails the check
+    /// (`signature_mismatch`).
+    #[error("response signature check failed for {path}: {reason}")]
+    ResponseSignature { path: String, reason: String },
+    /// A malformed setting, e.g. an `ALURE_*` variable read by `from_env`.
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("unexpected empty response from {0}")]
+    EmptyResponse(String),
     #[error("request failed: {0}")]
     Reqwest(#[from] reqwest::Error),
+    #[error("transport error: {0}")]
+    Transport(String),
     #[error("serialization error: {0}")]
     Serde(#[from] serde_json::Error),
     #[error("storage error: {0}")]
@@ -16,6 +45,187 @@ pub enum AlureError {
     Io(#[from] std::io::Error),
 }
 
+impl AlureError {
+    /// Maps a non-success response to `License` when the body carries a known
+    /// license error code, falling back to a generic `Http` error.
+    pub fn from_response(status: u16, message: String, request_id: Option<String>) -> Self {
+        match LicenseErrorCode::from_body(&message) {
+            Some(code) => AlureError::License {
+                code,
+                detail: message,
+                request_id,
+            },
+            None => AlureError::Http {
+                status,
+                message,
+                request_id,
+            },
+        }
+    }
+
+    /// The `X-Request-Id` of the failed call, to quote to support.
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            AlureError::Http { request_id, .. }
+            | AlureError::License { request_id, .. }
+            | AlureError::UpdateNotFound { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Process exit code for CLIs; see `exit_code` for the table.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AlureError::License { code, .. } => match code {
+                LicenseErrorCode::LicenseExpired => exit_code::EXPIRED,
+                LicenseErrorCode::LicenseRevoked => exit_code::REVOKED,
+                LicenseErrorCode::SeatLimitReached | LicenseErrorCode::InvalidLicenseKey => {
+                    exit_code::LICENSE_REJECTED
+                }
+            },
+            AlureError::Http { message, .. } if message == "missing_receipt" => {
+                exit_code::NOT_ACTIVATED
+            }
+            AlureError::Http { status, .. } if *status >= 500 || matches!(status, 408 | 429) => {
+                exit_code::NETWORK
+            }
+            AlureError::Reqwest(err) if err.is_builder() => exit_code::CONFIG,
+            AlureError::Reqwest(_) | AlureError::EmptyResponse(_) => exit_code::NETWORK,
+            AlureError::Transport(message)
+                if message.starts_with("invalid_certificate")
+                    || message.starts_with("invalid_client_identity")
+                    || message.starts_with("unsupported_operation") =>
+            {
+                exit_code::CONFIG
+            }
+            AlureError::Transport(_) => exit_code::NETWORK,
+            AlureError::UpdateNotFound { .. } | AlureError::Config(_) => exit_code::CONFIG,
+            AlureError::Receipt(err) => exit_code::for_reason(&err.0),
+            AlureError::ResponseSignature { .. } => exit_code::INVALID_RECEIPT,
+            AlureError::Http { .. }
+            | AlureError::Serde(_)
+            | AlureError::Storage(_)
+            | AlureError::Io(_) => exit_code::ERROR,
+        }
+    }
+
+    /// A short sentence to show end users, without internal details.
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            AlureError::License {
+                code: LicenseErrorCode::SeatLimitReached,
+                ..
+            } => "This license is already active on the maximum number of devices.",
+            AlureError::UpdateNotFound { .. } => "No updates are published for this channel.",
+            _ => exit_code::message(self.exit_code()),
+        }
+    }
+}
+
+/// Stable process exit codes shared by `AlureError::exit_code` and
+/// `ReceiptValidationResult::exit_code`. Values never change meaning.
+pub mod exit_code {
+    /// Valid license, including the grace period.
+    pub const OK: i32 = 0;
+    /// Unexpected failure: storage, IO, malformed responses, other HTTP errors.
+    pub const ERROR: i32 = 1;
+    pub const EXPIRED: i32 = 2;
+    pub const DEVICE_MISMATCH: i32 = 3;
+    /// Server unreachable, timed out, or failing with 5xx/408/429.
+    pub const NETWORK: i32 = 4;
+    /// Bad public key, certificate, transport or channel configuration.
+    pub const CONFIG: i32 = 5;
+    /// Receipt malformed, badly signed, stale or not yet valid, or a signed
+    /// server response that fails its check.
+    pub const INVALID_RECEIPT: i32 = 6;
+    pub const REVOKED: i32 = 7;
+    /// No stored receipt: the device was never activated.
+    pub const NOT_ACTIVATED: i32 = 8;
+    /// The server refused the license key (unknown key, seat limit).
+    pub const LICENSE_REJECTED: i32 = 9;
+
+    /// Code for a receipt error or validation reason such as
+    /// `device_mismatch` or `invalid_bundle: ...`. The side named by
+    /// `verify_strict` (`offline_failed: ...`, `online_failed: ...`) is
+    /// skipped.
+    pub fn for_reason(reason: &str) -> i32 {
+        let reason = reason
+            .strip_prefix("offline_failed:")
+            .or_else(|| reason.strip_prefix("online_failed:"))
+            .unwrap_or(reason);
+        match reason.split(':').next().unwrap_or_default().trim() {
+            "expired" | "license_expired" => EXPIRED,
+            "device_mismatch" => DEVICE_MISMATCH,
+            "revoked" | "license_revoked" => REVOKED,
+            "missing_receipt" => NOT_ACTIVATED,
+            "invalid_public_key" | "public_key_required" => CONFIG,
+            _ => INVALID_RECEIPT,
+        }
+    }
+
+    /// The user-facing message for `code`.
+    pub fn message(code: i32) -> &'static str {
+        match code {
+            OK => "The license is valid.",
+            EXPIRED => "The license has expired.",
+            DEVICE_MISMATCH => "The license is activated on a different device.",
+            NETWORK => "Could not reach the license server. Check your connection and try again.",
+            CONFIG => "The application is misconfigured. Please contact support.",
+            INVALID_RECEIPT => "The license data is invalid. Please activate again.",
+            REVOKED => "The license has been revoked.",
+            NOT_ACTIVATED => "This device is not activated.",
+            LICENSE_REJECTED => "The license key was rejected.",
+            _ => "An unexpected error occurred.",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LicenseErrorCode {
+    SeatLimitReached,
+    LicenseExpired,
+    LicenseRevoked,
+    InvalidLicenseKey,
+}
+
+impl LicenseErrorCode {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "seat_limit_reached" | "activation_limit_reached" => Some(Self::SeatLimitReached),
+            "license_expired" => Some(Self::LicenseExpired),
+            "license_revoked" => Some(Self::LicenseRevoked),
+            "invalid_license_key" | "license_not_found" => Some(Self::InvalidLicenseKey),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SeatLimitReached => "seat_limit_reached",
+            Self::LicenseExpired => "license_expired",
+            Self::LicenseRevoked => "license_revoked",
+            Self::InvalidLicenseKey => "invalid_license_key",
+        }
+    }
+
+    fn from_body(body: &str) -> Option<Self> {
+        let payload: serde_json::Value = match serde_json::from_str(body) {
+            Ok(payload) => payload,
+            Err(_) => return Self::from_code(body.trim()),
+        };
+        ["code", "error", "message"]
+            .iter()
+            .filter_map(|key| payload.get(*key).and_then(|value| value.as_str()))
+            .find_map(Self::from_code)
+    }
+}
+
+impl std::fmt::Display for LicenseErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("http {status}: {message}")]
 pub struct HttpError {
@@ -0,0 +1,233 @@
+//! gRPC transport for backends that expose licensing over
+//! `alure.licensing.v1.Licensing` (see `proto/licensing.proto`) instead of
+//! REST. Replies are translated back into the REST JSON shapes, so the
+//! client parses them exactly as it would a `/licenses/*` response.
+
+use ::http::header::{HeaderMap, ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, HOST, USER_AGENT};
+use ::http::uri::PathAndQuery;
+use tonic::codec::ProstCodec;
+use tonic::metadata::MetadataMap;
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+
+use crate::errors::AlureError;
+use crate::transport::{Transport, TransportFuture, TransportRequest, TransportResponse};
+
+pub const SERVICE_NAME: &str = "alure.licensing.v1.Licensing";
+pub const ACTIVATE_PATH: &str = "/alure.licensing.v1.Licensing/Activate";
+pub const VERIFY_PATH: &str = "/alure.licensing.v1.Licensing/Verify";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ActivateRequest {
+    #[prost(string, tag = "1")]
+    pub license_key: String,
+    #[prost(string, tag = "2")]
+    pub device_id: String,
+    #[prost(string, optional, tag = "3")]
+    pub app_version: Option<String>,
+    /// `device_meta` serialized as JSON.
+    #[prost(string, optional, tag = "4")]
+    pub device_meta_json: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ActivateReply {
+    #[prost(string, tag = "1")]
+    pub receipt: String,
+    #[prost(string, tag = "2")]
+    pub activation_id: String,
+    #[prost(string, optional, tag = "3")]
+    pub expires_at: Option<String>,
+    #[prost(int64, tag = "4")]
+    pub grace_period_days: i64,
+    #[prost(string, tag = "5")]
+    pub server_time: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VerifyRequest {
+    #[prost(string, tag = "1")]
+    pub receipt: String,
+    #[prost(string, tag = "2")]
+    pub device_id: String,
+    /// `device_meta` serialized as JSON.
+    #[prost(string, optional, tag = "3")]
+    pub device_meta_json: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VerifyReply {
+    #[prost(bool, tag = "1")]
+    pub valid: bool,
+    #[prost(string, optional, tag = "2")]
+    pub status: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub expires_at: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub reason: Option<String>,
+    #[prost(bool, tag = "5")]
+    pub revoked: bool,
+    #[prost(string, optional, tag = "6")]
+    pub new_receipt: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub server_time: Option<String>,
+}
+
+/// Sends `activate` and `verify_online` as unary gRPC calls over `channel`.
+/// Any other operation fails with `AlureError::Transport("unsupported_operation: ...")`.
+///
+/// gRPC statuses come back as the equivalent HTTP status with a
+/// `{"statusCode", "message"}` body, so license errors map as over REST.
+#[derive(Debug, Clone)]
+pub struct GrpcTransport {
+    channel: Channel,
+}
+
+impl GrpcTransport {
+    pub fn new(channel: Channel) -> Self {
+        Self { channel }
+    }
+
+    async fn unary<Req, Reply>(
+        &self,
+        path: &'static str,
+        message: Req,
+        headers: HeaderMap,
+    ) -> Result<tonic::Response<Reply>, Status>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Reply: prost::Message + Default + Send + Sync + 'static,
+    {
+        let mut grpc = tonic::client::Grpc::new(self.channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|err| Status::unavailable(err.to_string()))?;
+        let mut request = tonic::Request::new(message);
+        *request.metadata_mut() = MetadataMap::from_headers(headers);
+        grpc.unary(
+            request,
+            PathAndQuery::from_static(path),
+            ProstCodec::default(),
+        )
+        .await
+    }
+
+    async fn call(&self, request: TransportRequest) -> Result<TransportResponse, AlureError> {
+        let path = request.url.split(['?', '#']).next().unwrap_or_default();
+        let body: serde_json::Value = if request.body.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&request.body)?
+        };
+        let mut headers = request.headers;
+        for name in [ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, HOST, USER_AGENT] {
+            headers.remove(name);
+        }
+        let reply = if path.ends_with("/licenses/activate") {
+            let message = ActivateRequest {
+                license_key: string_field(&body, "license_key"),
+                device_id: string_field(&body, "device_id"),
+                app_version: body
+                    .get("app_version")
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string),
+                device_meta_json: body.get("device_meta").map(|meta| meta.to_string()),
+            };
+            self.unary::<_, ActivateReply>(ACTIVATE_PATH, message, headers)
+                .await
+                .map(|reply| reply.map(activate_json))
+        } else if path.ends_with("/licenses/verify") {
+            let message = VerifyRequest {
+                receipt: string_field(&body, "receipt"),
+                device_id: string_field(&body, "device_id"),
+                device_meta_json: body.get("device_meta").map(|meta| meta.to_string()),
+            };
+            self.unary::<_, VerifyReply>(VERIFY_PATH, message, headers)
+                .await
+                .map(|reply| reply.map(verify_json))
+        } else {
+            return Err(AlureError::Transport(format!(
+                "unsupported_operation: {path}"
+            )));
+        };
+        match reply {
+            Ok(reply) => {
+                let (metadata, json, _) = reply.into_parts();
+                Ok(TransportResponse {
+                    status: 200,
+                    headers: metadata.into_headers(),
+                    body: serde_json::to_vec(&json)?,
+                })
+            }
+            Err(status) => {
+                let code = http_status(status.code());
+                let body = serde_json::json!({
+                    "statusCode": code,
+                    "message": status.message(),
+                });
+                Ok(TransportResponse {
+                    status: code,
+                    headers: status.metadata().clone().into_headers(),
+                    body: serde_json::to_vec(&body)?,
+                })
+            }
+        }
+    }
+}
+
+impl Transport for GrpcTransport {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        Box::pin(self.call(request))
+    }
+}
+
+fn string_field(body: &serde_json::Value, key: &str) -> String {
+    body.get(key)
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn activate_json(reply: ActivateReply) -> serde_json::Value {
+    serde_json::json!({
+        "receipt": reply.receipt,
+        "activation_id": reply.activation_id,
+        "expires_at": reply.expires_at,
+        "grace_period_days": reply.grace_period_days,
+        "server_time": reply.server_time,
+    })
+}
+
+fn verify_json(reply: VerifyReply) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "valid": reply.valid,
+        "revoked": reply.revoked,
+        "expires_at": reply.expires_at,
+        "reason": reply.reason,
+        "new_receipt": reply.new_receipt,
+        "server_time": reply.server_time,
+    });
+    // Leave `status` out when unset so `VerifyResult` infers it, as it does
+    // for REST servers that omit it.
+    if let Some(status) = reply.status {
+        json["status"] = status.into();
+    }
+    json
+}
+
+fn http_status(code: Code) -> u16 {
+    match code {
+        Code::Ok => 200,
+        Code::InvalidArgument | Code::OutOfRange => 400,
+        Code::Unauthenticated => 401,
+        Code::PermissionDenied => 403,
+        Code::NotFound => 404,
+        Code::AlreadyExists | Code::Aborted | Code::FailedPrecondition => 409,
+        Code::ResourceExhausted => 429,
+        Code::Cancelled => 499,
+        Code::Unimplemented => 501,
+        Code::Unavailable => 503,
+        Code::DeadlineExceeded => 504,
+        Code::Unknown | Code::Internal | Code::DataLoss => 500,
+    }
+}
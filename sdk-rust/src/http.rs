@@ -0,0 +1,217 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Resolve, Resolving};
+use reqwest::Certificate;
+
+use crate::errors::AlureError;
+
+/// Network settings applied to the client's shared `reqwest::Client`.
+#[derive(Clone, Default)]
+pub struct HttpOptions {
+    /// Source address for outgoing connections, e.g. an IPv6 address to
+    /// force IPv6 on dual-stack hosts.
+    pub local_address: Option<IpAddr>,
+    /// Static `host -> address` overrides, bypassing DNS for those hosts.
+    pub resolve: Vec<(String, SocketAddr)>,
+    /// Custom DNS resolver used for every other host.
+    pub resolver: Option<Arc<dyn Resolve>>,
+    /// Budget for DNS plus establishing the connection, so an unreachable
+    /// server fails fast even when the overall timeout is generous.
+    pub connect_timeout: Option<Duration>,
+    /// Overall budget for API calls; defaults to the client's
+    /// `timeout_seconds`.
+    pub request_timeout: Option<Duration>,
+    /// Overall budget for `download_asset`; defaults to `request_timeout`.
+    pub download_timeout: Option<Duration>,
+    /// Longest gap allowed between two chunks of a response body.
+    pub read_timeout: Option<Duration>,
+    /// Extra trusted root certificates, PEM or DER encoded, e.g. an
+    /// internal CA signing the licensing server's certificate.
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Also load the operating system's certificate store as roots (needs
+    /// the `native-roots` feature; ignored otherwise).
+    pub system_roots: bool,
+    /// Certificate presented to servers that require mutual TLS.
+    #[cfg(feature = "mtls")]
+    pub client_identity: Option<ClientIdentity>,
+}
+
+impl std::fmt::Debug for HttpOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("HttpOptions");
+        debug
+            .field("local_address", &self.local_address)
+            .field("resolve", &self.resolve)
+            .field("resolver", &self.resolver.as_ref().map(|_| "custom"))
+            .field("connect_timeout", &self.connect_timeout)
+            .field("request_timeout", &self.request_timeout)
+            .field("download_timeout", &self.download_timeout)
+            .field("read_timeout", &self.read_timeout)
+            .field("root_certificates", &self.root_certificates.len())
+            .field("system_roots", &self.system_roots);
+        #[cfg(feature = "mtls")]
+        debug.field("client_identity", &self.client_identity);
+        debug.finish()
+    }
+}
+
+impl HttpOptions {
+    pub fn resolve(mut self, host: impl Into<String>, addr: SocketAddr) -> Self {
+        self.resolve.push((host.into(), addr));
+        self
+    }
+
+    pub fn root_certificate(mut self, certificate: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(certificate.into());
+        self
+    }
+
+    #[cfg(feature = "mtls")]
+    pub fn client_identity(mut self, identity: ClientIdentity) -> Self {
+        self.client_identity = Some(identity);
+        self
+    }
+}
+
+/// Client certificate and private key for mutual TLS (feature `mtls`).
+#[cfg(feature = "mtls")]
+#[derive(Clone)]
+pub enum ClientIdentity {
+    /// PEM with the certificate chain followed by its private key (PKCS#8,
+    /// PKCS#1 or SEC1).
+    Pem(Vec<u8>),
+    /// PKCS#12 archive (`.p12` / `.pfx`) protected by `password`.
+    Pkcs12 { der: Vec<u8>, password: String },
+}
+
+#[cfg(feature = "mtls")]
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material or the archive password.
+        match self {
+            ClientIdentity::Pem(_) => f.write_str("Pem(..)"),
+            ClientIdentity::Pkcs12 { .. } => f.write_str("Pkcs12(..)"),
+        }
+    }
+}
+
+struct SharedResolver(Arc<dyn Resolve>);
+
+impl Resolve for SharedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        self.0.resolve(name)
+    }
+}
+
+pub(crate) fn build_http_client(
+    options: &HttpOptions,
+    timeout_seconds: u64,
+) -> Result<reqwest::Client, AlureError> {
+    let timeout = options
+        .request_timeout
+        .unwrap_or(Duration::from_secs(timeout_seconds));
+    // `AlureClient` follows redirects itself, see `follow_redirect`.
+    let mut builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(connect_timeout) = options.connect_timeout {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    if let Some(addr) = options.local_address {
+        builder = builder.local_address(addr);
+    }
+    for (host, addr) in &options.resolve {
+        builder = builder.resolve(host, *addr);
+    }
+    if let Some(resolver) = &options.resolver {
+        builder = builder.dns_resolver(Arc::new(SharedResolver(resolver.clone())));
+    }
+    for certificate in &options.root_certificates {
+        builder = builder.add_root_certificate(parse_certificate(certificate)?);
+    }
+    #[cfg(feature = "native-roots")]
+    if options.system_roots {
+        let certs = rustls_native_certs::load_native_certs().map_err(|err| {
+            AlureError::Transport(format!("invalid_certificate: system store: {err}"))
+        })?;
+        for cert in certs {
+            // One unparsable entry in the OS store should not lock us out.
+            if let Ok(cert) = Certificate::from_der(&cert.0) {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+    }
+    #[cfg(feature = "mtls")]
+    if let Some(identity) = &options.client_identity {
+        // `Identity::from_pem` yields a rustls identity, which the default
+        // native-tls backend refuses.
+        builder = builder.use_rustls_tls().identity(load_identity(identity)?);
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(feature = "mtls")]
+fn load_identity(identity: &ClientIdentity) -> Result<reqwest::Identity, AlureError> {
+    let invalid =
+        |detail: String| AlureError::Transport(format!("invalid_client_identity: {detail}"));
+    let pem = match identity {
+        ClientIdentity::Pem(pem) => pem.clone(),
+        // The rustls backend only takes PEM, so unpack the archive into one.
+        ClientIdentity::Pkcs12 { der, password } => {
+            let pfx = p12::PFX::parse(der).map_err(|err| invalid(format!("{err:?}")))?;
+            if !pfx.verify_mac(password) {
+                return Err(invalid("wrong PKCS#12 password".to_string()));
+            }
+            let certs = pfx
+                .cert_x509_bags(password)
+                .map_err(|err| invalid(format!("{err:?}")))?;
+            let keys = pfx
+                .key_bags(password)
+                .map_err(|err| invalid(format!("{err:?}")))?;
+            let Some(key) = keys.first() else {
+                return Err(invalid("no private key in PKCS#12".to_string()));
+            };
+            let mut pem = String::new();
+            for cert in &certs {
+                pem.push_str(&pem_block("CERTIFICATE", cert));
+            }
+            pem.push_str(&pem_block("PRIVATE KEY", key));
+            pem.into_bytes()
+        }
+    };
+    reqwest::Identity::from_pem(&pem).map_err(|err| invalid(err.to_string()))
+}
+
+#[cfg(feature = "mtls")]
+fn pem_block(label: &str, der: &[u8]) -> String {
+    use base64::Engine;
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut block = format!("-----BEGIN {label}-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        block.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        block.push('\n');
+    }
+    block.push_str(&format!("-----END {label}-----\n"));
+    block
+}
+
+fn parse_certificate(bytes: &[u8]) -> Result<Certificate, AlureError> {
+    let invalid = |detail: String| AlureError::Transport(format!("invalid_certificate: {detail}"));
+    if bytes.starts_with(b"-----BEGIN") {
+        // reqwest only reports a malformed PEM when the client is built, so
+        // check it here for an error that names the certificate.
+        let mut reader = bytes;
+        match rustls_pemfile::certs(&mut reader) {
+            Ok(certs) if !certs.is_empty() => {}
+            Ok(_) => return Err(invalid("no CERTIFICATE block in PEM".to_string())),
+            Err(err) => return Err(invalid(err.to_string())),
+        }
+        Certificate::from_pem(bytes).map_err(|err| invalid(err.to_string()))
+    } else {
+        Certificate::from_der(bytes).map_err(|err| invalid(err.to_string()))
+    }
+}
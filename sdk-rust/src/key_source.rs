@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+/// Where `AlureClient::with_key_sources` looks for the receipt public key
+/// (an Ed25519 SPKI PEM). Sources are tried in order and the first that
+/// yields a key wins.
+///
+/// ```no_run
+/// # async fn build(client: alure_sdk::AlureClient) -> Result<(), alure_sdk::AlureError> {
+/// use alure_sdk::KeySource;
+///
+/// let client = client
+///     .with_key_sources(&[
+///         KeySource::Url("https://licensing.example.com/public_key.pem".to_string()),
+///         KeySource::Embedded("-----BEGIN PUBLIC KEY-----\n...\n-----END PUBLIC KEY-----\n"),
+///     ])
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    /// Fetched with a `GET`; best-effort, so a failed fetch falls back to
+    /// the copy cached by the last successful one, then to the next source.
+    Url(String),
+    Pem(String),
+    File(PathBuf),
+    /// A PEM compiled into the binary, typically `include_str!`.
+    Embedded(&'static str),
+}
@@ -0,0 +1,53 @@
+use crate::errors::StorageError;
+use crate::storage::{ReceiptRecord, ReceiptStorage};
+
+const SERVICE: &str = "alure";
+
+/// Stores the `ReceiptRecord` as JSON in the OS secure store (Keychain,
+/// Credential Manager, kernel keyutils), one entry per project.
+pub struct KeyringStorage {
+    entry: keyring::Entry,
+}
+
+impl std::fmt::Debug for KeyringStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyringStorage").finish_non_exhaustive()
+    }
+}
+
+impl KeyringStorage {
+    pub fn new(project_id: &str) -> Result<Self, StorageError> {
+        let entry = keyring::Entry::new(SERVICE, project_id).map_err(keyring_error)?;
+        Ok(Self { entry })
+    }
+}
+
+impl ReceiptStorage for KeyringStorage {
+    fn save_receipt(&self, record: &ReceiptRecord) -> Result<(), StorageError> {
+        let content = serde_json::to_string(record)
+            .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
+        self.entry.set_password(&content).map_err(keyring_error)
+    }
+
+    fn load_receipt(&self) -> Result<Option<ReceiptRecord>, StorageError> {
+        let content = match self.entry.get_password() {
+            Ok(content) => content,
+            Err(keyring::Error::NoEntry) => return Ok(None),
+            Err(err) => return Err(keyring_error(err)),
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| StorageError(format!("parse_failed: {err}")))
+    }
+
+    fn delete_receipt(&self) -> Result<(), StorageError> {
+        match self.entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(keyring_error(err)),
+        }
+    }
+}
+
+fn keyring_error(err: keyring::Error) -> StorageError {
+    StorageError(format!("keyring_unavailable: {err}"))
+}
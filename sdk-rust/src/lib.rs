@@ -1,9 +1,57 @@
+mod bundle;
 mod client;
+mod clock;
+mod codec;
+mod device;
+mod endpoints;
 mod errors;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod http;
+mod key_source;
+#[cfg(feature = "keyring")]
+mod keyring_storage;
+mod metrics;
 mod receipt;
+mod refresh;
 mod storage;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
 
-pub use client::{ActivateResponse, AlureClient};
-pub use errors::{AlureError, HttpError, ReceiptError, StorageError};
-pub use receipt::{ReceiptValidationResult, ReceiptVerifier};
-pub use storage::{FileStorage, ReceiptRecord};
+pub use bundle::{BundlePayload, LicenseBundle};
+pub use client::{
+    ActivateResponse, ActivationInfo, ActivationOutcome, ActivationsPage, AlureClient,
+    DeviceBinding, Diagnostics, DownloadResult, DownloadToken, LicenseCheck, LicenseInfo,
+    ReleaseInfo, UpdateAsset, UpdateCheck, UpdateInfo, VerifyResult, VerifyStatus,
+    MAX_DIAGNOSTICS_BYTES,
+};
+pub use clock::{Clock, SystemClock, TestClock};
+pub use codec::{Base64StdCodec, Base64UrlCodec, TokenCodec};
+pub use device::{DeviceIdSources, DeviceMeta};
+pub use endpoints::Endpoints;
+pub use errors::{exit_code, AlureError, HttpError, LicenseErrorCode, ReceiptError, StorageError};
+#[cfg(feature = "grpc")]
+pub use grpc::GrpcTransport;
+#[cfg(feature = "mtls")]
+pub use http::ClientIdentity;
+pub use http::HttpOptions;
+pub use key_source::KeySource;
+#[cfg(feature = "keyring")]
+pub use keyring_storage::KeyringStorage;
+pub use metrics::{AtomicMetrics, ErrorKind, MetricsSink, Operation};
+pub use receipt::{
+    verify_offline, CheckOutcome, ClaimDecryptor, DeviceHasher, PreHashedDeviceHasher,
+    ReceiptValidationResult, ReceiptVerifier, SaltedSha256DeviceHasher, Sha256DeviceHasher,
+    TrustPolicy, ValidationCheck, ValidationTrace,
+};
+pub use refresh::{ExpiryWatch, RefreshTask};
+pub use storage::{
+    CachedKeys, DownloadEntry, EventRecord, FileStorage, GraceOverride, OnlineVerification,
+    PendingActivation, ReceiptRecord, ReceiptStorage, RecordHook, ResolvedKey, StorageFormat,
+};
+#[cfg(feature = "testing")]
+pub use testing::TestReceiptBuilder;
+pub use transport::{
+    TowerTransport, Transport, TransportFuture, TransportRequest, TransportResponse,
+};
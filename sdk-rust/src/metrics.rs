@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// How a failed call failed, as reported to `MetricsSink::on_error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// No response: DNS, connect, TLS or timeout failures.
+    Network,
+    /// 5xx responses.
+    Server,
+    /// Responses carrying a license error code (revoked, expired, ...).
+    License,
+    /// Any other non-success response.
+    Client,
+}
+
+/// The API call a request belongs to, resolved by the client against its
+/// configured `Endpoints`, so sinks need not know the route paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Activate,
+    Verify,
+    /// Any other route, including downloads.
+    Other,
+}
+
+/// Receives one event per HTTP call made by the client. Implementations
+/// must be cheap: they run inline on every request.
+pub trait MetricsSink: std::fmt::Debug + Send + Sync {
+    /// Called for every response, successful or not. `path` is the route
+    /// as requested, before any redirect.
+    fn on_request(&self, operation: Operation, path: &str, status: u16, duration: Duration);
+    /// Called once per failed call, after `on_request` when there was a
+    /// response.
+    fn on_error(&self, kind: ErrorKind);
+}
+
+/// Lock-free counters, readable at any time.
+#[derive(Debug, Default)]
+pub struct AtomicMetrics {
+    requests: AtomicU64,
+    activations_ok: AtomicU64,
+    activations_failed: AtomicU64,
+    verifications_ok: AtomicU64,
+    verifications_failed: AtomicU64,
+    network_errors: AtomicU64,
+    server_errors: AtomicU64,
+    license_errors: AtomicU64,
+    client_errors: AtomicU64,
+}
+
+impl AtomicMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Responses received, whatever their status.
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn activations_ok(&self) -> u64 {
+        self.activations_ok.load(Ordering::Relaxed)
+    }
+
+    /// Activation calls answered with a non-success status.
+    pub fn activations_failed(&self) -> u64 {
+        self.activations_failed.load(Ordering::Relaxed)
+    }
+
+    pub fn verifications_ok(&self) -> u64 {
+        self.verifications_ok.load(Ordering::Relaxed)
+    }
+
+    /// Verification calls answered with a non-success status.
+    pub fn verifications_failed(&self) -> u64 {
+        self.verifications_failed.load(Ordering::Relaxed)
+    }
+
+    pub fn network_errors(&self) -> u64 {
+        self.network_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn server_errors(&self) -> u64 {
+        self.server_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn license_errors(&self) -> u64 {
+        self.license_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn client_errors(&self) -> u64 {
+        self.client_errors.load(Ordering::Relaxed)
+    }
+}
+
+impl MetricsSink for AtomicMetrics {
+    fn on_request(&self, operation: Operation, _path: &str, status: u16, _duration: Duration) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let ok = (200..300).contains(&status);
+        let counter = match (operation, ok) {
+            (Operation::Activate, true) => &self.activations_ok,
+            (Operation::Activate, false) => &self.activations_failed,
+            (Operation::Verify, true) => &self.verifications_ok,
+            (Operation::Verify, false) => &self.verifications_failed,
+            (Operation::Other, _) => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, kind: ErrorKind) {
+        let counter = match kind {
+            ErrorKind::Network => &self.network_errors,
+            ErrorKind::Server => &self.server_errors,
+            ErrorKind::License => &self.license_errors,
+            ErrorKind::Client => &self.client_errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
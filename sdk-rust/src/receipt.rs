@@ -1,155 +1,1106 @@
-use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
-use chrono::{DateTime, Duration, Utc};
-use ed25519_dalek::{Signature, VerifyingKey};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine as _,
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use pkcs8::spki::{ObjectIdentifier, SubjectPublicKeyInfoRef};
 use pkcs8::DecodePublicKey;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::errors::ReceiptError;
+use crate::clock::{Clock, SystemClock};
+use crate::codec::{Base64UrlCodec, TokenCodec};
+use crate::errors::{exit_code, ReceiptError};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Suffix of a receipt version (`v1+gzip`) whose payload is gzip-compressed
+/// JSON; JWTs say the same with a `zip: "gzip"` header.
+const GZIP_SUFFIX: &str = "+gzip";
+/// Largest decompressed payload accepted, so a tiny token cannot expand
+/// into gigabytes.
+const MAX_DECOMPRESSED_PAYLOAD: u64 = 1024 * 1024;
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ReceiptValidationResult {
     pub valid: bool,
     pub reason: Option<String>,
     pub expires_at: Option<String>,
     pub grace_period_days: Option<i64>,
+    /// `expires_at + grace_period_days` (RFC3339), the end of access.
+    #[serde(default)]
+    pub grace_until: Option<String>,
+}
+
+impl ReceiptValidationResult {
+    pub fn invalid(reason: impl Into<String>) -> Self {
+        Self {
+            valid: false,
+            reason: Some(reason.into()),
+            expires_at: None,
+            grace_period_days: None,
+            grace_until: None,
+        }
+    }
+
+    /// Valid and not running on the grace period.
+    pub fn is_active(&self) -> bool {
+        self.valid && !self.is_in_grace()
+    }
+
+    /// Expired, but still inside the grace period.
+    pub fn is_in_grace(&self) -> bool {
+        self.valid && self.reason.as_deref() == Some("grace_period")
+    }
+
+    /// Whether the app should unlock: active or in grace.
+    pub fn allows_access(&self) -> bool {
+        self.valid
+    }
+
+    /// `exit_code::OK` when access is allowed, else the code for `reason`.
+    pub fn exit_code(&self) -> i32 {
+        match (self.valid, self.reason.as_deref()) {
+            (true, _) => exit_code::OK,
+            (false, Some(reason)) => exit_code::for_reason(reason),
+            (false, None) => exit_code::INVALID_RECEIPT,
+        }
+    }
+
+    pub fn user_message(&self) -> &'static str {
+        match self.is_in_grace() {
+            true => "The license has expired but is still usable during the grace period.",
+            false => exit_code::message(self.exit_code()),
+        }
+    }
+
+    /// True while the receipt is active but expires within `window` of
+    /// `now`, for "your license expires soon" prompts. Perpetual receipts
+    /// never do.
+    pub fn expires_within(&self, window: Duration, now: DateTime<Utc>) -> bool {
+        let Some(expires_at) = self
+            .expires_at
+            .as_deref()
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        else {
+            return false;
+        };
+        self.is_active() && expires_at.with_timezone(&Utc) - now <= window
+    }
+}
+
+/// Computes the `device_id_hash` claim the server embeds in receipts.
+pub trait DeviceHasher: std::fmt::Debug + Send + Sync {
+    fn hash(&self, device_id: &str) -> String;
+}
+
+/// Hex SHA-256 of the raw device id (the server default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256DeviceHasher;
+
+impl DeviceHasher for Sha256DeviceHasher {
+    fn hash(&self, device_id: &str) -> String {
+        format!("{:x}", Sha256::digest(device_id.as_bytes()))
+    }
+}
+
+/// For callers that hash device ids themselves: the id already is the
+/// `device_id_hash` claim and is only normalized to lowercase hex.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreHashedDeviceHasher;
+
+impl DeviceHasher for PreHashedDeviceHasher {
+    fn hash(&self, device_id: &str) -> String {
+        device_id.trim().to_ascii_lowercase()
+    }
+}
+
+/// Hex SHA-256 of `salt || device_id`.
+#[derive(Debug, Clone)]
+pub struct SaltedSha256DeviceHasher {
+    pub salt: String,
+}
+
+impl DeviceHasher for SaltedSha256DeviceHasher {
+    fn hash(&self, device_id: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update(device_id.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Decrypts the `enc` section of a receipt payload, for deployments that
+/// keep confidential claims (e.g. a customer id) out of the clear. The
+/// signature covers `enc` as sent, so decrypted claims are as trusted as
+/// the rest of the receipt.
+pub trait ClaimDecryptor: std::fmt::Debug + Send + Sync {
+    /// Returns the claims hidden in `enc`, whatever its JSON shape.
+    fn decrypt(
+        &self,
+        enc: &serde_json::Value,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, ReceiptError>;
+}
+
+/// A key parsed once at construction. `Invalid` keeps a malformed PEM
+/// passed to `ReceiptVerifier::new` failing at first use, as it always has.
+#[derive(Debug, Clone)]
+enum PublicKey {
+    Parsed(VerifyingKey),
+    Invalid,
+}
+
+impl PublicKey {
+    fn from_pem(pem: &str) -> Self {
+        parse_pem(pem).map_or(PublicKey::Invalid, PublicKey::Parsed)
+    }
+
+    fn verifying_key(&self) -> Result<VerifyingKey, ReceiptError> {
+        match self {
+            PublicKey::Parsed(key) => Ok(*key),
+            PublicKey::Invalid => Err(ReceiptError("invalid_public_key".to_string())),
+        }
+    }
+}
+
+fn parse_pem(pem: &str) -> Result<VerifyingKey, ReceiptError> {
+    VerifyingKey::from_public_key_pem(pem.trim())
+        .map_err(|_| ReceiptError("invalid_public_key".to_string()))
 }
 
 #[derive(Debug, Clone)]
 pub struct ReceiptVerifier {
-    public_key_pem: Option<String>,
+    /// Accepted signing keys; a signature by any of them is valid, which
+    /// lets old receipts keep verifying across key rotations.
+    public_keys: Vec<PublicKey>,
+    device_hasher: Arc<dyn DeviceHasher>,
+    /// Segment codec per receipt version prefix (`v1`, ...).
+    codecs: HashMap<String, Arc<dyn TokenCodec>>,
+    max_receipt_age: Option<Duration>,
+    plan_claim: String,
+    claim_decryptor: Option<Arc<dyn ClaimDecryptor>>,
+    /// "Now" for validations called without an explicit time.
+    clock: Arc<dyn Clock>,
 }
 
 impl ReceiptVerifier {
     pub fn new(public_key_pem: Option<String>) -> Self {
-        Self { public_key_pem }
+        Self::with_key(public_key_pem.as_deref().map(PublicKey::from_pem))
     }
 
-    pub fn parse(&self, token: &str) -> Result<serde_json::Value, ReceiptError> {
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 3 || parts[0] != "v1" {
-            return Err(ReceiptError("invalid_receipt_format".to_string()));
+    /// Builds a verifier from a raw 32-byte ed25519 public key.
+    pub fn from_raw_key(bytes: &[u8]) -> Result<Self, ReceiptError> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| ReceiptError("invalid_public_key".to_string()))?;
+        let key = VerifyingKey::from_bytes(&bytes)
+            .map_err(|_| ReceiptError("invalid_public_key".to_string()))?;
+        Ok(Self::with_key(Some(PublicKey::Parsed(key))))
+    }
+
+    pub fn from_hex_key(hex: &str) -> Result<Self, ReceiptError> {
+        let hex = hex.trim();
+        if !hex.len().is_multiple_of(2) {
+            return Err(ReceiptError("invalid_public_key".to_string()));
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(hex.get(idx..idx + 2).unwrap_or_default(), 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| ReceiptError("invalid_public_key".to_string()))?;
+        Self::from_raw_key(&bytes)
+    }
+
+    /// Accepts standard or URL-safe base64, with or without padding.
+    pub fn from_base64_key(encoded: &str) -> Result<Self, ReceiptError> {
+        let encoded = encoded.trim();
+        let bytes = STANDARD
+            .decode(encoded)
+            .or_else(|_| URL_SAFE_NO_PAD.decode(encoded.trim_end_matches('=')))
+            .map_err(|_| ReceiptError("invalid_public_key".to_string()))?;
+        Self::from_raw_key(&bytes)
+    }
+
+    /// Like `new`, but requires a public key and parses it up front so a
+    /// missing or malformed PEM fails at construction instead of first use.
+    pub fn try_new(public_key_pem: Option<String>) -> Result<Self, ReceiptError> {
+        let pem = public_key_pem
+            .ok_or_else(|| ReceiptError("public_key_required".to_string()))?;
+        Ok(Self::with_key(Some(PublicKey::Parsed(parse_pem(&pem)?))))
+    }
+
+    /// Builds a verifier from a PEM compiled into the binary, typically
+    /// `include_str!("public_key.pem")`. Parse it once in a unit test so a
+    /// bad key fails the build pipeline rather than the shipped app.
+    pub fn from_embedded_key(pem: &'static str) -> Result<Self, ReceiptError> {
+        Self::validate_public_key(pem)?;
+        Ok(Self::with_key(Some(PublicKey::Parsed(parse_pem(pem)?))))
+    }
+
+    /// Loads every `*.pem` in `dir` as an accepted key. Unreadable or
+    /// unparseable files are skipped and reported in the returned warnings;
+    /// it only fails when no key loads at all.
+    pub fn from_key_dir(dir: impl AsRef<Path>) -> Result<(Self, Vec<String>), ReceiptError> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir)
+            .map_err(|err| ReceiptError(format!("key_dir_unreadable: {}: {err}", dir.display())))?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "pem"))
+            .collect();
+        paths.sort();
+        let mut keys = Vec::new();
+        let mut warnings = Vec::new();
+        for path in paths {
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|pem| {
+                    VerifyingKey::from_public_key_pem(pem.trim()).map_err(|err| err.to_string())
+                });
+            match loaded {
+                Ok(key) => keys.push(PublicKey::Parsed(key)),
+                Err(err) => {
+                    tracing::warn!(path = %path.display(), error = %err, "skipping public key");
+                    warnings.push(format!("{}: {err}", path.display()));
+                }
+            }
+        }
+        if keys.is_empty() {
+            return Err(ReceiptError(format!("no_public_keys: {}", dir.display())));
+        }
+        Ok((Self::with_keys(keys), warnings))
+    }
+
+    fn with_key(public_key: Option<PublicKey>) -> Self {
+        Self::with_keys(public_key.into_iter().collect())
+    }
+
+    fn with_keys(public_keys: Vec<PublicKey>) -> Self {
+        let v1: Arc<dyn TokenCodec> = Arc::new(Base64UrlCodec);
+        Self {
+            public_keys,
+            device_hasher: Arc::new(Sha256DeviceHasher),
+            codecs: HashMap::from([("v1".to_string(), v1)]),
+            max_receipt_age: None,
+            plan_claim: "plan".to_string(),
+            claim_decryptor: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Swaps the accepted keys for raw 32-byte Ed25519 keys, keeping the
+    /// hasher, codecs and max age.
+    pub(crate) fn with_raw_keys(mut self, keys: &[Vec<u8>]) -> Result<Self, ReceiptError> {
+        self.public_keys = keys
+            .iter()
+            .map(|bytes| {
+                let bytes: [u8; 32] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| ReceiptError("invalid_public_key".to_string()))?;
+                VerifyingKey::from_bytes(&bytes)
+                    .map(PublicKey::Parsed)
+                    .map_err(|_| ReceiptError("invalid_public_key".to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Swaps the accepted keys for a single PEM key, keeping the hasher,
+    /// codecs and max age.
+    pub(crate) fn with_pem_key(mut self, pem: &str) -> Result<Self, ReceiptError> {
+        self.public_keys = vec![PublicKey::Parsed(parse_pem(pem)?)];
+        Ok(self)
+    }
+
+    /// Accepts `<version>.<payload>.<sig>` receipts whose segments use
+    /// `codec`; `v1` defaults to `Base64UrlCodec`.
+    pub fn with_codec(
+        mut self,
+        version: impl Into<String>,
+        codec: impl TokenCodec + 'static,
+    ) -> Self {
+        self.codecs.insert(version.into(), Arc::new(codec));
+        self
+    }
+
+    /// Rejects receipts whose `issued_at` is older than `max_age` (or
+    /// missing) with reason `stale`, so a receipt must be re-issued online
+    /// within the window. Unlimited by default.
+    pub fn with_max_receipt_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_receipt_age = max_age;
+        self
+    }
+
+    /// Reads the plan from `claim` (e.g. `tier`) instead of `plan`.
+    pub fn with_plan_claim(mut self, claim: impl Into<String>) -> Self {
+        self.plan_claim = claim.into();
+        self
+    }
+
+    /// The receipt's plan (`free`, `pro`, ...); `None` when the claim is
+    /// absent or not a string. The signature is not checked.
+    pub fn plan(&self, token: &str) -> Result<Option<String>, ReceiptError> {
+        Ok(self
+            .parse(token)?
+            .get(&self.plan_claim)
+            .and_then(|value| value.as_str())
+            .map(str::to_string))
+    }
+
+    /// Time source for `validate_offline` and friends (all but
+    /// `validate_offline_at`); defaults to `SystemClock`.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Replaces the default bare SHA-256 used to match `device_id_hash`.
+    pub fn with_device_hasher(mut self, hasher: impl DeviceHasher + 'static) -> Self {
+        self.device_hasher = Arc::new(hasher);
+        self
+    }
+
+    /// Makes `parse` replace a payload's `enc` section with the claims
+    /// `decryptor` returns. Clear claims win over decrypted ones with the
+    /// same name. Without a decryptor `enc` is left as is.
+    pub fn with_claim_decryptor(mut self, decryptor: impl ClaimDecryptor + 'static) -> Self {
+        self.claim_decryptor = Some(Arc::new(decryptor));
+        self
+    }
+
+    pub fn hash_device_id(&self, device_id: &str) -> String {
+        self.device_hasher.hash(device_id)
+    }
+
+    pub fn is_configured_for_signatures(&self) -> bool {
+        !self.public_keys.is_empty()
+    }
+
+    /// Checks that `pem` is an ed25519 SPKI public key, with a descriptive
+    /// `invalid_public_key: ...` error for startup config validation.
+    pub fn validate_public_key(pem: &str) -> Result<(), ReceiptError> {
+        let invalid = |detail: String| ReceiptError(format!("invalid_public_key: {detail}"));
+        let (label, document) = pkcs8::Document::from_pem(pem.trim())
+            .map_err(|err| invalid(format!("malformed PEM ({err})")))?;
+        if label != "PUBLIC KEY" {
+            return Err(invalid(format!(
+                "expected a PUBLIC KEY block, found {label}"
+            )));
+        }
+        let spki = SubjectPublicKeyInfoRef::try_from(document.as_bytes())
+            .map_err(|err| invalid(format!("malformed key data ({err})")))?;
+        if spki.algorithm.oid != ED25519_OID {
+            return Err(invalid(format!(
+                "expected an Ed25519 key, found algorithm {}",
+                spki.algorithm.oid
+            )));
         }
-        let payload_bytes = URL_SAFE_NO_PAD
-            .decode(parts[1])
+        VerifyingKey::from_public_key_der(document.as_bytes())
+            .map_err(|err| invalid(format!("unusable Ed25519 key ({err})")))?;
+        Ok(())
+    }
+
+    /// The exact bytes the Ed25519 signature covers: for versioned receipts
+    /// (`v1.<payload>.<sig>`) the encoded payload segment alone, without the
+    /// version; for JWTs the encoded `header.payload`. The signature itself
+    /// is not checked.
+    pub fn signing_input(&self, token: &str) -> Result<Vec<u8>, ReceiptError> {
+        let parts = self
+            .split_token(token)
+            .ok_or_else(|| ReceiptError("invalid_receipt_format".to_string()))?;
+        Ok(parts.signing_input.as_bytes().to_vec())
+    }
+
+    /// Short, non-reversible id of a receipt for logs: the first 12 hex
+    /// chars of its SHA-256.
+    pub fn fingerprint(token: &str) -> String {
+        let digest = format!("{:x}", Sha256::digest(token.as_bytes()));
+        digest[..12].to_string()
+    }
+
+    /// Decodes the claims of a `v1.<payload>.<sig>` receipt or of an EdDSA
+    /// JWT. JWT `exp`, `nbf` and `sub` are mapped onto `expires_at`,
+    /// `not_before` and `device_id_hash` unless those are already present.
+    pub fn parse(&self, token: &str) -> Result<serde_json::Value, ReceiptError> {
+        let parts = self
+            .split_token(token)
+            .ok_or_else(|| ReceiptError("invalid_receipt_format".to_string()))?;
+        let mut payload_bytes = parts
+            .codec
+            .decode(parts.payload)
             .map_err(|_| ReceiptError("invalid_receipt_payload".to_string()))?;
+        if parts.gzip {
+            payload_bytes = gunzip(&payload_bytes)?;
+        }
         let payload = serde_json::from_slice(&payload_bytes)
             .map_err(|_| ReceiptError("invalid_receipt_payload".to_string()))?;
-        Ok(payload)
+        let claims = match parts.header {
+            Some(_) => jwt_claims(payload),
+            None => payload,
+        };
+        self.decrypt_claims(claims)
     }
 
-    pub fn verify_signature(&self, token: &str) -> Result<bool, ReceiptError> {
-        let public_key_pem = self
-            .public_key_pem
-            .as_ref()
-            .ok_or_else(|| ReceiptError("public_key_required".to_string()))?;
+    fn decrypt_claims(
+        &self,
+        mut claims: serde_json::Value,
+    ) -> Result<serde_json::Value, ReceiptError> {
+        let (Some(decryptor), Some(object)) = (&self.claim_decryptor, claims.as_object_mut())
+        else {
+            return Ok(claims);
+        };
+        let Some(enc) = object.remove("enc") else {
+            return Ok(claims);
+        };
+        for (name, value) in decryptor.decrypt(&enc)? {
+            object.entry(name).or_insert(value);
+        }
+        Ok(claims)
+    }
+
+    /// Decodes `token` for display, without checking the signature:
+    ///
+    /// ```json
+    /// {
+    ///   "format": "v1" | "jwt",
+    ///   "header": {"version": "v1"} | <JOSE header>,
+    ///   "claims": { ... },
+    ///   "fingerprint": "<ReceiptVerifier::fingerprint>",
+    ///   "signature_present": true,
+    ///   "signature_note": "present, not verified"
+    /// }
+    /// ```
+    ///
+    /// Fails like `parse` on a malformed token.
+    pub fn inspect(&self, token: &str) -> Result<serde_json::Value, ReceiptError> {
+        let token = token.trim();
+        let parts = self
+            .split_token(token)
+            .ok_or_else(|| ReceiptError("invalid_receipt_format".to_string()))?;
+        let claims = self.parse(token)?;
+        let (format, header) = match parts.header {
+            Some(header) => ("jwt".to_string(), header),
+            None => {
+                let version = token.split('.').next().unwrap_or_default();
+                (
+                    version.to_string(),
+                    serde_json::json!({ "version": version }),
+                )
+            }
+        };
+        let signature_present = !parts.signature.is_empty();
+        Ok(serde_json::json!({
+            "format": format,
+            "header": header,
+            "claims": claims,
+            "fingerprint": Self::fingerprint(token),
+            "signature_present": signature_present,
+            "signature_note": if signature_present {
+                "present, not verified"
+            } else {
+                "missing"
+            },
+        }))
+    }
+
+    pub fn parse_bytes(&self, token: &[u8]) -> Result<serde_json::Value, ReceiptError> {
+        self.parse(token_str(token)?)
+    }
+
+    /// Recognises `<version>.<payload>.<sig>` for registered versions (also
+    /// as `<version>+gzip`) and JWTs (whose first segment decodes to a JSON
+    /// header with an `alg`).
+    fn split_token<'a>(&self, token: &'a str) -> Option<TokenParts<'a>> {
         let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 3 || parts[0] != "v1" {
+        if parts.len() != 3 {
+            return None;
+        }
+        let (version, gzip) = match parts[0].strip_suffix(GZIP_SUFFIX) {
+            Some(version) => (version, true),
+            None => (parts[0], false),
+        };
+        if let Some(codec) = self.codecs.get(version) {
+            return Some(TokenParts {
+                signing_input: parts[1],
+                payload: parts[1],
+                signature: parts[2],
+                header: None,
+                codec: codec.clone(),
+                gzip,
+            });
+        }
+        let header: serde_json::Value = Base64UrlCodec
+            .decode(parts[0])
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())?;
+        header.get("alg")?;
+        let gzip = header
+            .get("zip")
+            .and_then(|value| value.as_str())
+            .is_some_and(|zip| zip.eq_ignore_ascii_case("gzip"));
+        Some(TokenParts {
+            signing_input: &token[..parts[0].len() + 1 + parts[1].len()],
+            payload: parts[1],
+            signature: parts[2],
+            header: Some(header),
+            codec: Arc::new(Base64UrlCodec),
+            gzip,
+        })
+    }
+
+    fn verifying_keys(&self) -> Result<Vec<VerifyingKey>, ReceiptError> {
+        if self.public_keys.is_empty() {
+            return Err(ReceiptError("public_key_required".to_string()));
+        }
+        self.public_keys
+            .iter()
+            .map(PublicKey::verifying_key)
+            .collect()
+    }
+
+    /// SHA-256 (hex) of each accepted key's raw 32 bytes, the form license
+    /// bundles quote in `key_fingerprint`.
+    pub fn key_fingerprints(&self) -> Result<Vec<String>, ReceiptError> {
+        Ok(self.verifying_keys()?.iter().map(key_fingerprint).collect())
+    }
+
+    pub(crate) fn key_by_fingerprint(
+        &self,
+        fingerprint: &str,
+    ) -> Result<Option<VerifyingKey>, ReceiptError> {
+        Ok(self
+            .verifying_keys()?
+            .into_iter()
+            .find(|key| key_fingerprint(key).eq_ignore_ascii_case(fingerprint.trim())))
+    }
+
+    pub fn verify_signature(&self, token: &str) -> Result<bool, ReceiptError> {
+        let verifying_keys = self.verifying_keys()?;
+        let Some(parts) = self.split_token(token) else {
             return Ok(false);
+        };
+        match &parts.header {
+            Some(header) => check_alg(header)?,
+            None => check_alg(&self.parse(token)?)?,
         }
-        let payload = parts[1].as_bytes();
-        let signature_bytes = URL_SAFE_NO_PAD
-            .decode(parts[2])
+        let payload = parts.signing_input.as_bytes();
+        let signature_bytes = parts
+            .codec
+            .decode(parts.signature)
             .map_err(|_| ReceiptError("invalid_signature".to_string()))?;
         let signature = Signature::from_slice(&signature_bytes)
             .map_err(|_| ReceiptError("invalid_signature".to_string()))?;
-        let verifying_key = VerifyingKey::from_public_key_pem(public_key_pem)
-            .map_err(|_| ReceiptError("invalid_public_key".to_string()))?;
-        Ok(verifying_key.verify(payload, &signature).is_ok())
+        Ok(verifying_keys
+            .iter()
+            .any(|key| key.verify(payload, &signature).is_ok()))
+    }
+
+    /// Verifies an ed25519 signature over an arbitrary message signed with
+    /// the receipt key (config blobs, feature toggles, ...).
+    pub fn verify_raw(&self, message: &[u8], signature: &[u8]) -> Result<bool, ReceiptError> {
+        let verifying_keys = self.verifying_keys()?;
+        let signature = Signature::from_slice(signature)
+            .map_err(|_| ReceiptError("invalid_signature".to_string()))?;
+        Ok(verifying_keys
+            .iter()
+            .any(|key| key.verify(message, &signature).is_ok()))
+    }
+
+    /// `verify_raw` with a base64 signature, standard or URL-safe.
+    pub fn verify_raw_base64(&self, message: &[u8], signature: &str) -> Result<bool, ReceiptError> {
+        let signature = signature.trim();
+        let signature_bytes = STANDARD
+            .decode(signature)
+            .or_else(|_| URL_SAFE_NO_PAD.decode(signature.trim_end_matches('=')))
+            .map_err(|_| ReceiptError("invalid_signature".to_string()))?;
+        self.verify_raw(message, &signature_bytes)
+    }
+
+    /// Checks a detached signature over `data`, e.g. a downloaded asset,
+    /// turning a mismatch into a `signature_mismatch` error.
+    pub fn verify_detached(&self, data: &[u8], signature: &str) -> Result<(), ReceiptError> {
+        if self.verify_raw_base64(data, signature)? {
+            Ok(())
+        } else {
+            Err(ReceiptError("signature_mismatch".to_string()))
+        }
     }
 
+    /// Same as `validate_offline` for a token held as raw bytes; non-UTF-8
+    /// input is reported as `invalid_receipt_format`.
+    pub fn validate_offline_bytes(
+        &self,
+        token: &[u8],
+        device_id: &str,
+        verify_signature: bool,
+    ) -> ReceiptValidationResult {
+        match token_str(token) {
+            Ok(token) => self.validate_offline(token, device_id, verify_signature),
+            Err(err) => ReceiptValidationResult::invalid(err.0),
+        }
+    }
+
+    /// Validates `token` for `device_id` at the verifier's clock (see
+    /// `with_clock`).
     pub fn validate_offline(
         &self,
         token: &str,
         device_id: &str,
-        now: Option<DateTime<Utc>>,
         verify_signature: bool,
     ) -> ReceiptValidationResult {
+        self.validate_offline_verbose(token, device_id, verify_signature)
+            .0
+    }
+
+    /// `validate_offline` at a given time instead of the verifier's clock,
+    /// e.g. to audit whether a receipt was valid when an event happened.
+    pub fn validate_offline_at(
+        &self,
+        token: &str,
+        device_id: &str,
+        at: DateTime<Utc>,
+        verify_signature: bool,
+    ) -> ReceiptValidationResult {
+        self.validate_with_grace_at(token, device_id, at, verify_signature, None)
+    }
+
+    /// `validate_offline` plus a trace of every check it ran, in order, with
+    /// the values compared. Validation stops at the first failing check, so
+    /// later checks are absent from the trace.
+    pub fn validate_offline_verbose(
+        &self,
+        token: &str,
+        device_id: &str,
+        verify_signature: bool,
+    ) -> (ReceiptValidationResult, ValidationTrace) {
+        let policy = TrustPolicy {
+            signature: verify_signature,
+            ..TrustPolicy::default()
+        };
+        self.validate_offline_traced(token, device_id, self.clock.now(), policy, None)
+    }
+
+    /// `validate_offline` with `grace_period_days` taking precedence over
+    /// the receipt's claim, e.g. a value set by the server online.
+    pub fn validate_offline_with_grace(
+        &self,
+        token: &str,
+        device_id: &str,
+        verify_signature: bool,
+        grace_period_days: Option<i64>,
+    ) -> ReceiptValidationResult {
+        let now = self.clock.now();
+        self.validate_with_grace_at(token, device_id, now, verify_signature, grace_period_days)
+    }
+
+    /// `validate_offline_with_grace` at `now`, for the client's
+    /// drift-corrected time and `verify_offline_at`.
+    pub(crate) fn validate_with_grace_at(
+        &self,
+        token: &str,
+        device_id: &str,
+        now: DateTime<Utc>,
+        verify_signature: bool,
+        grace_period_days: Option<i64>,
+    ) -> ReceiptValidationResult {
+        let policy = TrustPolicy {
+            signature: verify_signature,
+            ..TrustPolicy::default()
+        };
+        self.validate_offline_traced(token, device_id, now, policy, grace_period_days)
+            .0
+    }
+
+    /// `validate_offline` with each check required or skipped by `policy`.
+    /// Skipped checks show up as `Skipped` in the verbose trace.
+    pub fn validate_offline_with_policy(
+        &self,
+        token: &str,
+        device_id: &str,
+        policy: TrustPolicy,
+    ) -> ReceiptValidationResult {
+        self.validate_offline_traced(token, device_id, self.clock.now(), policy, None)
+            .0
+    }
+
+    fn validate_offline_traced(
+        &self,
+        token: &str,
+        device_id: &str,
+        now: DateTime<Utc>,
+        policy: TrustPolicy,
+        grace_override: Option<i64>,
+    ) -> (ReceiptValidationResult, ValidationTrace) {
+        let mut trace = ValidationTrace {
+            now,
+            checks: Vec::new(),
+        };
         let payload = match self.parse(token) {
-            Ok(payload) => payload,
+            Ok(payload) => {
+                trace.record("format", CheckOutcome::Passed, None);
+                payload
+            }
             Err(err) => {
-                return ReceiptValidationResult {
-                    valid: false,
-                    reason: Some(err.0),
-                    expires_at: None,
-                    grace_period_days: None,
-                }
+                trace.record("format", CheckOutcome::Failed, Some(err.0.clone()));
+                return (ReceiptValidationResult::invalid(err.0), trace);
             }
         };
-        if verify_signature {
-            match self.verify_signature(token) {
-                Ok(true) => {}
-                Ok(false) => {
-                    return ReceiptValidationResult {
-                        valid: false,
-                        reason: Some("invalid_signature".to_string()),
-                        expires_at: None,
-                        grace_period_days: None,
-                    }
-                }
-                Err(err) => {
-                    return ReceiptValidationResult {
-                        valid: false,
-                        reason: Some(err.0),
-                        expires_at: None,
-                        grace_period_days: None,
-                    }
-                }
+        if policy.signature {
+            let failure = match self.verify_signature(token) {
+                Ok(true) => None,
+                Ok(false) => Some("invalid_signature".to_string()),
+                Err(err) => Some(err.0),
+            };
+            if let Some(reason) = failure {
+                trace.record("signature", CheckOutcome::Failed, Some(reason.clone()));
+                return (ReceiptValidationResult::invalid(reason), trace);
             }
+            trace.record("signature", CheckOutcome::Passed, None);
+        } else {
+            trace.record("signature", CheckOutcome::Skipped, None);
         }
 
-        let device_hash = Sha256::digest(device_id.as_bytes());
-        let device_hash_hex = format!("{:x}", device_hash);
-        if payload
-            .get("device_id_hash")
+        match payload
+            .get("not_before")
             .and_then(|value| value.as_str())
-            != Some(device_hash_hex.as_str())
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
         {
-            return ReceiptValidationResult {
-                valid: false,
-                reason: Some("device_mismatch".to_string()),
-                expires_at: None,
-                grace_period_days: None,
-            };
+            Some(not_before) => {
+                let detail = Some(format!("not_before={not_before}, now={now}"));
+                if now < not_before {
+                    trace.record("not_before", CheckOutcome::Failed, detail);
+                    return (ReceiptValidationResult::invalid("not_yet_valid"), trace);
+                }
+                trace.record("not_before", CheckOutcome::Passed, detail);
+            }
+            None => trace.record("not_before", CheckOutcome::Skipped, None),
+        }
+
+        if policy.device_match {
+            let device_hash_hex = self.hash_device_id(device_id);
+            let receipt_hash = payload
+                .get("device_id_hash")
+                .and_then(|value| value.as_str());
+            let detail = Some(format!(
+                "expected={}, receipt={}",
+                hash_prefix(&device_hash_hex),
+                receipt_hash.map(hash_prefix).unwrap_or("none")
+            ));
+            if receipt_hash != Some(device_hash_hex.as_str()) {
+                trace.record("device", CheckOutcome::Failed, detail);
+                return (ReceiptValidationResult::invalid("device_mismatch"), trace);
+            }
+            trace.record("device", CheckOutcome::Passed, detail);
+        } else {
+            trace.record_skipped_by_policy("device");
+        }
+
+        match self.max_receipt_age {
+            Some(max_age) => {
+                let issued_at = payload
+                    .get("issued_at")
+                    .and_then(|value| value.as_str())
+                    .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                    .map(|value| value.with_timezone(&Utc));
+                let detail = Some(match issued_at {
+                    Some(issued_at) => format!(
+                        "issued_at={issued_at}, max_age={}s, now={now}",
+                        max_age.num_seconds()
+                    ),
+                    None => "missing issued_at".to_string(),
+                });
+                if issued_at.is_none_or(|issued_at| now - issued_at > max_age) {
+                    trace.record("max_age", CheckOutcome::Failed, detail);
+                    return (ReceiptValidationResult::invalid("stale"), trace);
+                }
+                trace.record("max_age", CheckOutcome::Passed, detail);
+            }
+            None => trace.record("max_age", CheckOutcome::Skipped, None),
         }
 
         let expires_at = payload
             .get("expires_at")
             .and_then(|value| value.as_str())
             .map(str::to_string);
-        let grace_days = payload
-            .get("grace_period_days")
-            .and_then(|value| value.as_i64())
-            .unwrap_or(0);
-        let now_dt = now.unwrap_or_else(Utc::now);
-        if let Some(expires_at_str) = expires_at.clone() {
-            if let Ok(exp_dt) = DateTime::parse_from_rfc3339(&expires_at_str) {
+        let grace_days = grace_override.unwrap_or_else(|| {
+            payload
+                .get("grace_period_days")
+                .and_then(|value| value.as_i64())
+                .unwrap_or(0)
+        });
+        let mut grace_until = None;
+        match expires_at
+            .as_deref()
+            .filter(|_| policy.expiry)
+            .map(|value| (value, DateTime::parse_from_rfc3339(value)))
+        {
+            None if !policy.expiry => trace.record_skipped_by_policy("expiry"),
+            None => trace.record(
+                "expiry",
+                CheckOutcome::Skipped,
+                Some("perpetual".to_string()),
+            ),
+            Some((raw, Err(_))) => trace.record(
+                "expiry",
+                CheckOutcome::Skipped,
+                Some(format!("unparseable expires_at={raw}")),
+            ),
+            Some((_, Ok(exp_dt))) => {
                 let exp_dt = exp_dt.with_timezone(&Utc);
-                if now_dt > exp_dt {
-                    let grace_limit = exp_dt + Duration::days(grace_days);
-                    if now_dt > grace_limit {
-                        return ReceiptValidationResult {
-                            valid: false,
-                            reason: Some("expired".to_string()),
-                            expires_at: Some(expires_at_str),
-                            grace_period_days: Some(grace_days),
-                        };
-                    }
-                    return ReceiptValidationResult {
-                        valid: true,
-                        reason: Some("grace_period".to_string()),
-                        expires_at: Some(expires_at_str),
+                let grace_limit = exp_dt + Duration::days(grace_days);
+                grace_until = Some(grace_limit.to_rfc3339_opts(SecondsFormat::Secs, true));
+                let detail = Some(format!("expires_at={exp_dt}, now={now}"));
+                if now > exp_dt {
+                    trace.record("expiry", CheckOutcome::Failed, detail);
+                    let detail = Some(format!(
+                        "grace_until={grace_limit}, grace_period_days={grace_days}, now={now}"
+                    ));
+                    let expired = now > grace_limit;
+                    let outcome = if expired {
+                        CheckOutcome::Failed
+                    } else {
+                        CheckOutcome::Passed
+                    };
+                    trace.record("grace", outcome, detail);
+                    let result = ReceiptValidationResult {
+                        valid: !expired,
+                        reason: Some(if expired { "expired" } else { "grace_period" }.to_string()),
+                        expires_at,
                         grace_period_days: Some(grace_days),
+                        grace_until,
                     };
+                    if !expired && self.revoked(&payload, policy, &mut trace) {
+                        return (ReceiptValidationResult::invalid("revoked"), trace);
+                    }
+                    return (result, trace);
                 }
+                trace.record("expiry", CheckOutcome::Passed, detail);
             }
         }
+        if self.revoked(&payload, policy, &mut trace) {
+            return (ReceiptValidationResult::invalid("revoked"), trace);
+        }
 
-        ReceiptValidationResult {
+        let result = ReceiptValidationResult {
             valid: true,
             reason: None,
             expires_at,
             grace_period_days: Some(grace_days),
+            grace_until,
+        };
+        (result, trace)
+    }
+
+    /// Records the revocation check. Offline, only a `revoked: true` claim
+    /// can reveal a revocation, so it runs only when `policy` requires it.
+    fn revoked(
+        &self,
+        payload: &serde_json::Value,
+        policy: TrustPolicy,
+        trace: &mut ValidationTrace,
+    ) -> bool {
+        if !policy.revocation {
+            trace.record_revocation();
+            return false;
+        }
+        let revoked = payload
+            .get("revoked")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        let outcome = match revoked {
+            true => CheckOutcome::Failed,
+            false => CheckOutcome::Passed,
+        };
+        trace.record("revocation", outcome, Some(format!("revoked={revoked}")));
+        revoked
+    }
+}
+
+/// Which offline checks `validate_offline_with_policy` enforces. The
+/// default, also used by `validate_offline`, checks signature, device and
+/// expiry but leaves revocation to `verify_online`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustPolicy {
+    pub signature: bool,
+    /// The receipt's `device_id_hash` must match the device.
+    pub device_match: bool,
+    /// `expires_at` and the grace period; skipping it trusts the server's
+    /// expiry instead.
+    pub expiry: bool,
+    /// Rejects receipts carrying a `revoked: true` claim.
+    pub revocation: bool,
+}
+
+impl Default for TrustPolicy {
+    fn default() -> Self {
+        Self {
+            signature: true,
+            device_match: true,
+            expiry: true,
+            revocation: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// One step of `validate_offline_verbose`: `format`, `signature`,
+/// `not_before`, `device`, `max_age`, `expiry`, `grace` or `revocation`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationCheck {
+    pub name: String,
+    pub outcome: CheckOutcome,
+    /// The values compared, e.g. `expected=1a2b3c4d, receipt=5e6f7a8b` (hash
+    /// prefixes only) or the timestamps involved.
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationTrace {
+    /// The instant the receipt was judged against.
+    pub now: DateTime<Utc>,
+    pub checks: Vec<ValidationCheck>,
+}
+
+impl ValidationTrace {
+    pub fn check(&self, name: &str) -> Option<&ValidationCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// The first failing check, i.e. the one that decided the result (for an
+    /// in-grace receipt this is `expiry`, even though it is still valid).
+    pub fn first_failure(&self) -> Option<&ValidationCheck> {
+        self.checks
+            .iter()
+            .find(|check| check.outcome == CheckOutcome::Failed)
+    }
+
+    fn record(&mut self, name: &str, outcome: CheckOutcome, detail: Option<String>) {
+        self.checks.push(ValidationCheck {
+            name: name.to_string(),
+            outcome,
+            detail,
+        });
+    }
+
+    fn record_skipped_by_policy(&mut self, name: &str) {
+        self.record(
+            name,
+            CheckOutcome::Skipped,
+            Some("skipped by policy".to_string()),
+        );
+    }
+
+    /// Revocation needs the server; offline it is skipped by default.
+    fn record_revocation(&mut self) {
+        self.record(
+            "revocation",
+            CheckOutcome::Skipped,
+            Some("requires verify_online".to_string()),
+        );
+    }
+}
+
+fn hash_prefix(hash: &str) -> &str {
+    hash.get(..8).unwrap_or(hash)
+}
+
+/// One-shot offline validation without constructing an `AlureClient`.
+///
+/// ```
+/// let result = alure_sdk::verify_offline("v1.e30.c2ln", "device-123", None, false);
+/// assert!(!result.valid);
+/// assert_eq!(result.reason.as_deref(), Some("device_mismatch"));
+/// ```
+pub fn verify_offline(
+    receipt: &str,
+    device_id: &str,
+    public_key_pem: Option<String>,
+    verify_signature: bool,
+) -> ReceiptValidationResult {
+    ReceiptVerifier::new(public_key_pem).validate_offline(receipt, device_id, verify_signature)
+}
+
+struct TokenParts<'a> {
+    /// The signed bytes: the payload segment for versioned receipts,
+    /// `header.payload` for JWTs.
+    signing_input: &'a str,
+    payload: &'a str,
+    signature: &'a str,
+    /// Decoded JOSE header; `None` for versioned receipts.
+    header: Option<serde_json::Value>,
+    codec: Arc<dyn TokenCodec>,
+    /// The decoded payload is gzip-compressed JSON.
+    gzip: bool,
+}
+
+fn gunzip(compressed: &[u8]) -> Result<Vec<u8>, ReceiptError> {
+    use std::io::Read;
+    let invalid = || ReceiptError("invalid_receipt_payload".to_string());
+    let mut payload = Vec::new();
+    flate2::read::GzDecoder::new(compressed)
+        .take(MAX_DECOMPRESSED_PAYLOAD + 1)
+        .read_to_end(&mut payload)
+        .map_err(|_| invalid())?;
+    if payload.len() as u64 > MAX_DECOMPRESSED_PAYLOAD {
+        return Err(invalid());
+    }
+    Ok(payload)
+}
+
+fn jwt_claims(mut payload: serde_json::Value) -> serde_json::Value {
+    let Some(claims) = payload.as_object_mut() else {
+        return payload;
+    };
+    for (jwt, claim) in [("exp", "expires_at"), ("nbf", "not_before")] {
+        let timestamp = claims
+            .get(jwt)
+            .and_then(|value| value.as_i64())
+            .and_then(|secs| DateTime::from_timestamp(secs, 0));
+        if let (Some(timestamp), false) = (timestamp, claims.contains_key(claim)) {
+            claims.insert(
+                claim.to_string(),
+                timestamp.to_rfc3339_opts(SecondsFormat::Secs, true).into(),
+            );
+        }
+    }
+    if let (Some(sub), false) = (
+        claims.get("sub").cloned(),
+        claims.contains_key("device_id_hash"),
+    ) {
+        claims.insert("device_id_hash".to_string(), sub);
+    }
+    payload
+}
+
+fn key_fingerprint(key: &VerifyingKey) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+fn token_str(token: &[u8]) -> Result<&str, ReceiptError> {
+    std::str::from_utf8(token).map_err(|_| ReceiptError("invalid_receipt_format".to_string()))
+}
+
+/// Receipts are always ed25519; an explicit `alg` (v1 claim or JWT header)
+/// must agree.
+fn check_alg(payload: &serde_json::Value) -> Result<(), ReceiptError> {
+    match payload.get("alg") {
+        None => Ok(()),
+        Some(serde_json::Value::String(alg))
+            if alg.eq_ignore_ascii_case("EdDSA") || alg.eq_ignore_ascii_case("Ed25519") =>
+        {
+            Ok(())
         }
+        Some(_) => Err(ReceiptError("unsupported_alg".to_string())),
     }
 }
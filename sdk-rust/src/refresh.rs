@@ -0,0 +1,233 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use tokio::sync::watch;
+use tokio::task::{AbortHandle, JoinHandle};
+
+use crate::client::AlureClient;
+use crate::receipt::ReceiptValidationResult;
+
+const MAX_BACKOFF_FACTOR: u32 = 8;
+pub(crate) const DEFAULT_STATUS_RECHECK: Duration = Duration::from_secs(60);
+
+/// Tasks spawned by a client, shared by its clones and aborted when the last
+/// of them is dropped. The tasks themselves run on a clone detached from
+/// this set, so they never keep it alive.
+#[derive(Debug, Default)]
+pub(crate) struct BackgroundTasks(Mutex<Vec<AbortHandle>>);
+
+impl BackgroundTasks {
+    fn track(&self, handle: &JoinHandle<()>) {
+        let mut handles = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        handles.retain(|handle| !handle.is_finished());
+        handles.push(handle.abort_handle());
+    }
+}
+
+impl Drop for BackgroundTasks {
+    fn drop(&mut self) {
+        let handles = self
+            .0
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for handle in handles.drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+/// Handle to a background refresh loop started by
+/// [`AlureClient::spawn_refresh_task`]. The loop stops on `stop` or when the
+/// client and all its clones are dropped; dropping this handle alone does
+/// not stop it.
+#[derive(Debug)]
+pub struct RefreshTask {
+    pub handle: JoinHandle<()>,
+    pub status: watch::Receiver<ReceiptValidationResult>,
+}
+
+impl RefreshTask {
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+}
+
+impl AlureClient {
+    /// Periodically calls `refresh` every `interval` (plus up to 10% jitter).
+    /// Consecutive failures double the wait, up to 8x the interval, and publish
+    /// the offline validation of the stored receipt instead.
+    ///
+    /// The task lives as long as this client or any clone of it; once the
+    /// last one is dropped it is aborted and `status` reports the sender
+    /// closed.
+    pub fn spawn_refresh_task(&self, interval: Duration) -> RefreshTask {
+        let client = self.detached();
+        let (sender, status) = watch::channel(client.offline_status());
+        let handle = tokio::spawn(async move {
+            let mut failures = 0u32;
+            loop {
+                let backoff = 2u32.saturating_pow(failures).min(MAX_BACKOFF_FACTOR);
+                tokio::time::sleep(with_jitter(interval * backoff)).await;
+                let result = match client.refresh(None).await {
+                    Ok(result) => {
+                        failures = 0;
+                        result
+                    }
+                    Err(_) => {
+                        failures = failures.saturating_add(1);
+                        match client.verify_offline(None, None, false) {
+                            Ok(result) => result,
+                            Err(_) => continue,
+                        }
+                    }
+                };
+                if sender.send(result).is_err() {
+                    break;
+                }
+            }
+        });
+        self.background_tasks.track(&handle);
+        RefreshTask { handle, status }
+    }
+}
+
+/// Handle to the task started by [`AlureClient::expiry_watch`]; the task
+/// also ends once every receiver is dropped, or with the client's last
+/// clone.
+#[derive(Debug)]
+pub struct ExpiryWatch {
+    pub handle: JoinHandle<()>,
+    pub status: watch::Receiver<ReceiptValidationResult>,
+}
+
+impl ExpiryWatch {
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+}
+
+impl AlureClient {
+    /// Re-evaluates the stored receipt offline and publishes each change.
+    /// The task sleeps until just past the next expiry/grace boundary, but
+    /// never longer than `with_status_recheck_interval`, so stored receipt
+    /// updates and clock adjustments are picked up as well. Like
+    /// `spawn_refresh_task`, the task is aborted once the client and all its
+    /// clones are dropped.
+    pub fn expiry_watch(&self) -> ExpiryWatch {
+        let client = self.detached();
+        let (sender, status) = watch::channel(client.offline_status());
+        let handle = tokio::spawn(async move {
+            loop {
+                let result = client.offline_status();
+                let wait = client.status_wait(&result);
+                sender.send_if_modified(|current| {
+                    if *current == result {
+                        return false;
+                    }
+                    *current = result;
+                    true
+                });
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = sender.closed() => break,
+                }
+            }
+        });
+        self.background_tasks.track(&handle);
+        ExpiryWatch { handle, status }
+    }
+
+    /// Yields the stored receipt's status now and then on every change:
+    /// expiry and grace boundaries (timed as in `expiry_watch`) and online
+    /// re-verifications requested with `refresh_status`. Consecutive
+    /// identical states are yielded once.
+    pub fn status_stream(&self) -> impl Stream<Item = ReceiptValidationResult> + Send + 'static {
+        let client = self.clone();
+        let refreshes = self.status_refresh.subscribe();
+        stream::unfold(
+            (client, refreshes, None::<ReceiptValidationResult>),
+            move |(client, mut refreshes, last)| async move {
+                let mut last = last;
+                loop {
+                    let result = match &last {
+                        None => client.offline_status(),
+                        Some(current) => {
+                            let wait = client.status_wait(current);
+                            tokio::select! {
+                                _ = tokio::time::sleep(wait) => client.offline_status(),
+                                Ok(()) = refreshes.changed() => match client.refresh(None).await {
+                                    Ok(result) => result,
+                                    Err(_) => client.offline_status(),
+                                },
+                            }
+                        }
+                    };
+                    if last.as_ref() != Some(&result) {
+                        last = Some(result.clone());
+                        return Some((result, (client, refreshes, last)));
+                    }
+                }
+            },
+        )
+    }
+
+    /// Makes every `status_stream` of this client (and its clones)
+    /// re-verify online, falling back to the offline status when that fails.
+    pub fn refresh_status(&self) {
+        self.status_refresh
+            .send_modify(|count| *count = count.wrapping_add(1));
+    }
+
+    /// A clone for a spawned task: it shares everything but the task set,
+    /// so the task does not keep its own abort guard alive.
+    fn detached(&self) -> AlureClient {
+        let mut client = self.clone();
+        client.background_tasks = Default::default();
+        client
+    }
+
+    /// Time until just past `result`'s next transition, capped at the
+    /// recheck interval.
+    fn status_wait(&self, result: &ReceiptValidationResult) -> Duration {
+        let now = self.now();
+        next_transition(result, now)
+            .and_then(|at| (at - now).to_std().ok())
+            .map(|wait| wait + Duration::from_millis(1))
+            .map_or(self.status_recheck, |wait| wait.min(self.status_recheck))
+    }
+
+    fn offline_status(&self) -> ReceiptValidationResult {
+        self.verify_offline(None, None, false)
+            .unwrap_or_else(|err| ReceiptValidationResult::invalid(err.to_string()))
+    }
+}
+
+/// The next instant at which `result` would change: expiry, then the end of
+/// the grace period.
+fn next_transition(result: &ReceiptValidationResult, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let expires_at = DateTime::parse_from_rfc3339(result.expires_at.as_deref()?)
+        .ok()?
+        .with_timezone(&Utc);
+    if now <= expires_at {
+        return Some(expires_at);
+    }
+    let grace_limit = expires_at + chrono::Duration::days(result.grace_period_days.unwrap_or(0));
+    (now <= grace_limit).then_some(grace_limit)
+}
+
+fn with_jitter(delay: Duration) -> Duration {
+    let max_jitter = delay.as_millis() as u64 / 10;
+    if max_jitter == 0 {
+        return delay;
+    }
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|value| value.subsec_nanos() as u64)
+        .unwrap_or(0);
+    delay + Duration::from_millis(seed % max_jitter)
+}
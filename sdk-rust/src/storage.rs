@@ -1,49 +1,289 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
+use crate::client::VerifyResult;
 use crate::errors::StorageError;
+use crate::receipt::ReceiptVerifier;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ReceiptRecord {
     pub receipt: String,
     pub device_id: String,
     pub activation_id: Option<String>,
     pub project_id: Option<String>,
+    /// Integrator-defined fields stored alongside the receipt, e.g. a cached
+    /// tier; see `FileStorage::with_before_save`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Transform applied by `FileStorage` to a record on save or load.
+pub type RecordHook = Arc<dyn Fn(&mut ReceiptRecord) -> Result<(), StorageError> + Send + Sync>;
+
+impl ReceiptRecord {
+    pub fn fingerprint(&self) -> String {
+        ReceiptVerifier::fingerprint(&self.receipt)
+    }
+}
+
+/// Where the client persists its `ReceiptRecord`.
+pub trait ReceiptStorage: std::fmt::Debug + Send + Sync {
+    fn save_receipt(&self, record: &ReceiptRecord) -> Result<(), StorageError>;
+    fn load_receipt(&self) -> Result<Option<ReceiptRecord>, StorageError>;
+    fn delete_receipt(&self) -> Result<(), StorageError>;
+}
+
+/// The last successful online verification, cached for
+/// `AlureClient::verify`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnlineVerification {
+    pub verified_at: DateTime<Utc>,
+    /// `ReceiptRecord::fingerprint` of the receipt that was verified.
+    pub receipt_fingerprint: String,
+    pub result: VerifyResult,
+}
+
+/// A grace period set by the server in a `verify_online` response. It
+/// applies only while `receipt_fingerprint` is the stored receipt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraceOverride {
+    pub receipt_fingerprint: String,
+    pub grace_period_days: i64,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Receipt keys fetched by `AlureClient::fetch_verifying_keys`, with the
+/// HTTP caching metadata needed to revalidate them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedKeys {
+    /// Raw Ed25519 keys, base64url without padding (the JWK `x` member).
+    pub keys: Vec<String>,
+    pub etag: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+    /// `Cache-Control: max-age`; `None` means always revalidate.
+    pub max_age_seconds: Option<u64>,
+}
+
+/// Public key last fetched from a `KeySource::Url`, reused when that URL
+/// is unreachable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedKey {
+    pub url: String,
+    pub pem: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// An activation recorded by `AlureClient::activate_or_queue` while the
+/// server was unreachable, completed by `flush_pending_activations`. The
+/// license key is kept as-is in `pending_activations.json`, a file readable
+/// only by its owner on Unix.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingActivation {
+    pub license_key: String,
+    pub device_id: String,
+    #[serde(default)]
+    pub app_version: Option<String>,
+    #[serde(default)]
+    pub device_meta: Option<serde_json::Value>,
+    /// Reused on every attempt, so the server can dedupe an activation
+    /// whose response was lost.
+    pub idempotency_key: String,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// One line of `events.jsonl`, the audit log kept when
+/// `AlureClient::with_event_log` is set. Never holds a receipt, license key
+/// or device id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub at: DateTime<Utc>,
+    /// `activate`, `verify` or `deactivate`.
+    pub action: String,
+    pub success: bool,
+    /// `ok`, the verify status (e.g. `revoked`), or what failed: a license
+    /// error code, `http_<status>`, a receipt reason or `network`.
+    pub result: String,
+    #[serde(default)]
+    pub request_id: Option<String>,
 }
 
 #[derive(Debug, Clone)]
+pub struct DownloadEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    pub partial: bool,
+}
+
+/// Encoding `FileStorage` writes the receipt file in. Loading detects the
+/// encoding of the file, so changing it keeps existing receipts readable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageFormat {
+    /// Indented JSON, easy to inspect by hand.
+    #[default]
+    Json,
+    /// JSON without whitespace.
+    CompactJson,
+    /// MessagePack (feature `msgpack`), the smallest of the three.
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+impl StorageFormat {
+    fn encode(self, payload: &serde_json::Value) -> Result<Vec<u8>, StorageError> {
+        let serialize_failed = |err: String| StorageError(format!("serialize_failed: {err}"));
+        match self {
+            StorageFormat::Json => {
+                serde_json::to_vec_pretty(payload).map_err(|err| serialize_failed(err.to_string()))
+            }
+            StorageFormat::CompactJson => {
+                serde_json::to_vec(payload).map_err(|err| serialize_failed(err.to_string()))
+            }
+            #[cfg(feature = "msgpack")]
+            StorageFormat::MessagePack => {
+                rmp_serde::to_vec_named(payload).map_err(|err| serialize_failed(err.to_string()))
+            }
+        }
+    }
+
+    fn decode(content: &[u8]) -> Result<serde_json::Value, StorageError> {
+        let parse_failed = |err: String| StorageError(format!("parse_failed: {err}"));
+        // Both JSON variants start with `{`; a MessagePack map never does.
+        let is_json = content
+            .iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_some_and(|byte| *byte == b'{');
+        if is_json {
+            return serde_json::from_slice(content).map_err(|err| parse_failed(err.to_string()));
+        }
+        #[cfg(feature = "msgpack")]
+        {
+            rmp_serde::from_slice(content).map_err(|err| parse_failed(err.to_string()))
+        }
+        #[cfg(not(feature = "msgpack"))]
+        Err(parse_failed("not a JSON receipt file".to_string()))
+    }
+}
+
+#[derive(Clone)]
 pub struct FileStorage {
     base_dir: PathBuf,
     receipt_path: PathBuf,
+    format: StorageFormat,
+    /// False for `open_existing`: directories are then never created.
+    create_dirs: bool,
+    /// Serializes `append_event` across clones sharing this storage.
+    events_lock: Arc<Mutex<()>>,
+    before_save: Option<RecordHook>,
+    after_load: Option<RecordHook>,
+}
+
+impl std::fmt::Debug for FileStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileStorage")
+            .field("base_dir", &self.base_dir)
+            .field("receipt_path", &self.receipt_path)
+            .field("format", &self.format)
+            .field("create_dirs", &self.create_dirs)
+            .field("before_save", &self.before_save.is_some())
+            .field("after_load", &self.after_load.is_some())
+            .finish()
+    }
 }
 
 impl FileStorage {
     pub fn new(base_dir: Option<PathBuf>) -> Result<Self, StorageError> {
+        let mut storage = Self::open_existing(base_dir)?;
+        storage.create_dirs = true;
+        std::fs::create_dir_all(&storage.base_dir)
+            .map_err(|err| StorageError(format!("create_dir_failed: {err}")))?;
+        Ok(storage)
+    }
+
+    /// Like `new`, but never creates the base dir: reads of a missing dir
+    /// return `Ok(None)` and only writes fail. Suits verification-only use
+    /// and read-only filesystems.
+    pub fn open_existing(base_dir: Option<PathBuf>) -> Result<Self, StorageError> {
         let dir = match base_dir {
             Some(path) => path,
             None => dirs::home_dir()
                 .ok_or_else(|| StorageError("missing_home_dir".to_string()))?
                 .join(".alure"),
         };
-        std::fs::create_dir_all(&dir)
-            .map_err(|err| StorageError(format!("create_dir_failed: {err}")))?;
         let receipt_path = dir.join("receipt.json");
         Ok(Self {
             base_dir: dir,
             receipt_path,
+            format: StorageFormat::default(),
+            create_dirs: false,
+            events_lock: Arc::default(),
+            before_save: None,
+            after_load: None,
         })
     }
 
+    /// Uses `filename` inside the base dir instead of `receipt.json`, so
+    /// several apps or profiles can share one directory.
+    pub fn with_filename(mut self, filename: impl AsRef<Path>) -> Self {
+        self.receipt_path = self.base_dir.join(filename);
+        self
+    }
+
+    /// Writes the receipt file in `format` instead of indented JSON.
+    pub fn with_format(mut self, format: StorageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Runs `hook` on a copy of every record before it is written, e.g. to
+    /// add `extra` fields or encrypt some of them. An error aborts the save.
+    pub fn with_before_save(
+        mut self,
+        hook: impl Fn(&mut ReceiptRecord) -> Result<(), StorageError> + Send + Sync + 'static,
+    ) -> Self {
+        self.before_save = Some(Arc::new(hook));
+        self
+    }
+
+    /// Runs `hook` on every record read back, undoing `with_before_save`.
+    pub fn with_after_load(
+        mut self,
+        hook: impl Fn(&mut ReceiptRecord) -> Result<(), StorageError> + Send + Sync + 'static,
+    ) -> Self {
+        self.after_load = Some(Arc::new(hook));
+        self
+    }
+
     pub fn save_receipt(&self, record: &ReceiptRecord) -> Result<(), StorageError> {
-        let payload = serde_json::json!({
+        let mut hooked;
+        let record = match &self.before_save {
+            Some(hook) => {
+                hooked = record.clone();
+                hook(&mut hooked)?;
+                &hooked
+            }
+            None => record,
+        };
+        let mut payload = serde_json::json!({
             "receipt": record.receipt,
             "device_id": record.device_id,
             "activation_id": record.activation_id,
             "project_id": record.project_id,
         });
-        let content = serde_json::to_string_pretty(&payload)
-            .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
-        std::fs::write(&self.receipt_path, content)
+        if !record.extra.is_empty() {
+            payload["extra"] = serde_json::json!(record.extra);
+        }
+        let content = self.format.encode(&payload)?;
+        let mut tmp_path = self.receipt_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        std::fs::write(&tmp_path, content)
+            .map_err(|err| StorageError(format!("write_failed: {err}")))?;
+        std::fs::rename(&tmp_path, &self.receipt_path)
             .map_err(|err| StorageError(format!("write_failed: {err}")))?;
         Ok(())
     }
@@ -52,11 +292,10 @@ impl FileStorage {
         if !self.receipt_path.exists() {
             return Ok(None);
         }
-        let content = std::fs::read_to_string(&self.receipt_path)
+        let content = std::fs::read(&self.receipt_path)
             .map_err(|err| StorageError(format!("read_failed: {err}")))?;
-        let payload: serde_json::Value = serde_json::from_str(&content)
-            .map_err(|err| StorageError(format!("parse_failed: {err}")))?;
-        Ok(Some(ReceiptRecord {
+        let payload = StorageFormat::decode(&content)?;
+        let mut record = ReceiptRecord {
             receipt: payload
                 .get("receipt")
                 .and_then(|value| value.as_str())
@@ -75,17 +314,343 @@ impl FileStorage {
                 .get("project_id")
                 .and_then(|value| value.as_str())
                 .map(str::to_string),
-        }))
+            extra: payload
+                .get("extra")
+                .and_then(|value| value.as_object())
+                .map(|extra| {
+                    extra
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        if let Some(hook) = &self.after_load {
+            hook(&mut record)?;
+        }
+        Ok(Some(record))
+    }
+
+    pub fn delete_receipt(&self) -> Result<(), StorageError> {
+        match std::fs::remove_file(&self.receipt_path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(StorageError(format!("remove_failed: {err}"))),
+        }
+    }
+
+    pub fn save_online_verification(
+        &self,
+        verification: &OnlineVerification,
+    ) -> Result<(), StorageError> {
+        let content = serde_json::to_string_pretty(verification)
+            .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
+        write_atomic(&self.base_dir.join("last_online.json"), content.as_bytes())
+    }
+
+    pub fn load_online_verification(&self) -> Result<Option<OnlineVerification>, StorageError> {
+        let path = self.base_dir.join("last_online.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|err| StorageError(format!("read_failed: {err}")))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| StorageError(format!("parse_failed: {err}")))
+    }
+
+    pub fn delete_online_verification(&self) -> Result<(), StorageError> {
+        match std::fs::remove_file(self.base_dir.join("last_online.json")) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(StorageError(format!("remove_failed: {err}"))),
+        }
+    }
+
+    pub fn save_grace_override(&self, grace: &GraceOverride) -> Result<(), StorageError> {
+        let content = serde_json::to_string_pretty(grace)
+            .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
+        let path = self.base_dir.join("grace_override.json");
+        write_atomic(&path, content.as_bytes())
+    }
+
+    pub fn load_grace_override(&self) -> Result<Option<GraceOverride>, StorageError> {
+        let path = self.base_dir.join("grace_override.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|err| StorageError(format!("read_failed: {err}")))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| StorageError(format!("parse_failed: {err}")))
+    }
+
+    pub fn save_cached_keys(&self, keys: &CachedKeys) -> Result<(), StorageError> {
+        let content = serde_json::to_string_pretty(keys)
+            .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
+        write_atomic(&self.base_dir.join("keys.json"), content.as_bytes())
+    }
+
+    pub fn load_cached_keys(&self) -> Result<Option<CachedKeys>, StorageError> {
+        let path = self.base_dir.join("keys.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|err| StorageError(format!("read_failed: {err}")))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| StorageError(format!("parse_failed: {err}")))
+    }
+
+    pub fn save_resolved_key(&self, key: &ResolvedKey) -> Result<(), StorageError> {
+        let content = serde_json::to_string_pretty(key)
+            .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
+        write_atomic(&self.base_dir.join("resolved_key.json"), content.as_bytes())
+    }
+
+    pub fn load_resolved_key(&self) -> Result<Option<ResolvedKey>, StorageError> {
+        let path = self.base_dir.join("resolved_key.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|err| StorageError(format!("read_failed: {err}")))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| StorageError(format!("parse_failed: {err}")))
+    }
+
+    pub fn save_pending_activations(
+        &self,
+        pending: &[PendingActivation],
+    ) -> Result<(), StorageError> {
+        let path = self.base_dir.join("pending_activations.json");
+        if pending.is_empty() {
+            return match std::fs::remove_file(&path) {
+                Ok(()) => Ok(()),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(err) => Err(StorageError(format!("remove_failed: {err}"))),
+            };
+        }
+        let content = serde_json::to_string_pretty(pending)
+            .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
+        write_atomic_private(&path, content.as_bytes())
+    }
+
+    pub fn load_pending_activations(&self) -> Result<Vec<PendingActivation>, StorageError> {
+        let path = self.base_dir.join("pending_activations.json");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|err| StorageError(format!("read_failed: {err}")))?;
+        serde_json::from_str(&content).map_err(|err| StorageError(format!("parse_failed: {err}")))
+    }
+
+    /// Appends `event` to `events.jsonl`, dropping the oldest entries beyond
+    /// `max_entries`. The file is replaced whole, so a crash never leaves a
+    /// torn line behind.
+    pub fn append_event(
+        &self,
+        event: &EventRecord,
+        max_entries: usize,
+    ) -> Result<(), StorageError> {
+        let _guard = self
+            .events_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut events = self.read_events()?;
+        events.push(event.clone());
+        let mut content = String::new();
+        for event in &events[events.len().saturating_sub(max_entries)..] {
+            let line = serde_json::to_string(event)
+                .map_err(|err| StorageError(format!("serialize_failed: {err}")))?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+        write_atomic(&self.base_dir.join("events.jsonl"), content.as_bytes())
+    }
+
+    /// The events in `events.jsonl`, oldest first.
+    pub fn read_events(&self) -> Result<Vec<EventRecord>, StorageError> {
+        let content = match std::fs::read_to_string(self.base_dir.join("events.jsonl")) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(StorageError(format!("read_failed: {err}"))),
+        };
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|err| StorageError(format!("parse_failed: {err}")))
+            })
+            .collect()
+    }
+
+    /// Clock offset in seconds, server minus local (positive when the local
+    /// clock is behind), recorded by `AlureClient::sync_time` and by
+    /// `verify_online` with `with_server_time_sync`.
+    pub fn save_clock_drift(&self, drift_seconds: i64) -> Result<(), StorageError> {
+        write_atomic(&self.base_dir.join("clock_drift"), drift_seconds.to_string().as_bytes())
+    }
+
+    /// Latest (drift-corrected) time the client has observed, used to
+    /// detect a clock set back to extend an offline receipt.
+    pub fn save_max_seen_time(&self, seen: DateTime<Utc>) -> Result<(), StorageError> {
+        write_atomic(&self.base_dir.join("max_seen_time"), seen.to_rfc3339().as_bytes())
+    }
+
+    pub fn load_max_seen_time(&self) -> Result<Option<DateTime<Utc>>, StorageError> {
+        match std::fs::read_to_string(self.base_dir.join("max_seen_time")) {
+            Ok(content) => DateTime::parse_from_rfc3339(content.trim())
+                .map(|seen| Some(seen.with_timezone(&Utc)))
+                .map_err(|err| StorageError(format!("parse_failed: {err}"))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(StorageError(format!("read_failed: {err}"))),
+        }
+    }
+
+    pub fn load_clock_drift(&self) -> Result<Option<i64>, StorageError> {
+        match std::fs::read_to_string(self.base_dir.join("clock_drift")) {
+            Ok(content) => content
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|err| StorageError(format!("parse_failed: {err}"))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(StorageError(format!("read_failed: {err}"))),
+        }
     }
 
     pub fn receipts_path(&self) -> &Path {
         &self.receipt_path
     }
 
+    /// Random per-install identifier, created on first use and persisted
+    /// next to the receipt.
+    pub fn install_id(&self) -> Result<String, StorageError> {
+        let path = self.base_dir.join("install_id");
+        if let Ok(existing) = std::fs::read_to_string(&path) {
+            let existing = existing.trim();
+            if !existing.is_empty() {
+                return Ok(existing.to_string());
+            }
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        std::fs::write(&path, &id).map_err(|err| StorageError(format!("write_failed: {err}")))?;
+        Ok(id)
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// The `downloads` dir inside the base dir, created if missing unless
+    /// this storage came from `open_existing`.
     pub fn downloads_dir(&self) -> Result<PathBuf, StorageError> {
         let downloads = self.base_dir.join("downloads");
-        std::fs::create_dir_all(&downloads)
-            .map_err(|err| StorageError(format!("create_dir_failed: {err}")))?;
+        if self.create_dirs {
+            std::fs::create_dir_all(&downloads)
+                .map_err(|err| StorageError(format!("create_dir_failed: {err}")))?;
+        }
         Ok(downloads)
     }
+
+    /// Every file in the downloads dir, sorted by path. The directory itself
+    /// is the index: there is no separate record to fall out of step with
+    /// downloads or `cleanup_downloads`.
+    pub fn list_downloads(&self) -> Result<Vec<DownloadEntry>, StorageError> {
+        let entries = match std::fs::read_dir(self.base_dir.join("downloads")) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(StorageError(format!("read_dir_failed: {err}"))),
+        };
+        let mut items = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|err| StorageError(format!("read_dir_failed: {err}")))?;
+            let metadata = entry
+                .metadata()
+                .map_err(|err| StorageError(format!("metadata_failed: {err}")))?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            items.push(DownloadEntry {
+                partial: path.extension().is_some_and(|ext| ext == "part"),
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                path,
+            });
+        }
+        items.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(items)
+    }
+
+    /// Deletes every download for which `keep` returns false and returns the
+    /// removed paths.
+    pub fn cleanup_downloads(
+        &self,
+        keep: impl Fn(&DownloadEntry) -> bool,
+    ) -> Result<Vec<PathBuf>, StorageError> {
+        let mut removed = Vec::new();
+        for entry in self.list_downloads()? {
+            if keep(&entry) {
+                continue;
+            }
+            std::fs::remove_file(&entry.path)
+                .map_err(|err| StorageError(format!("remove_failed: {err}")))?;
+            removed.push(entry.path);
+        }
+        Ok(removed)
+    }
+}
+
+/// Writes `content` through a `.tmp` sibling and a rename, so readers never
+/// see a partial file.
+fn write_atomic(path: &Path, content: &[u8]) -> Result<(), StorageError> {
+    let mut tmp_path = path.to_path_buf().into_os_string();
+    tmp_path.push(".tmp");
+    std::fs::write(&tmp_path, content)
+        .map_err(|err| StorageError(format!("write_failed: {err}")))?;
+    std::fs::rename(&tmp_path, path).map_err(|err| StorageError(format!("write_failed: {err}")))
+}
+
+/// Like `write_atomic`, but the file is readable only by its owner (mode
+/// 0600 on Unix, set before any content is written), for files holding
+/// secrets.
+fn write_atomic_private(path: &Path, content: &[u8]) -> Result<(), StorageError> {
+    let mut tmp_path = path.to_path_buf().into_os_string();
+    tmp_path.push(".tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    // A leftover tmp file would keep its old, possibly wider, mode.
+    let _ = std::fs::remove_file(&tmp_path);
+    options
+        .open(&tmp_path)
+        .and_then(|mut file| file.write_all(content))
+        .map_err(|err| StorageError(format!("write_failed: {err}")))?;
+    std::fs::rename(&tmp_path, path).map_err(|err| StorageError(format!("write_failed: {err}")))
+}
+
+impl ReceiptStorage for FileStorage {
+    fn save_receipt(&self, record: &ReceiptRecord) -> Result<(), StorageError> {
+        FileStorage::save_receipt(self, record)
+    }
+
+    fn load_receipt(&self) -> Result<Option<ReceiptRecord>, StorageError> {
+        FileStorage::load_receipt(self)
+    }
+
+    fn delete_receipt(&self) -> Result<(), StorageError> {
+        FileStorage::delete_receipt(self)
+    }
 }
@@ -0,0 +1,129 @@
+//! Signed receipts for downstream tests (feature `testing`): build a `v1`
+//! token with `TestReceiptBuilder`, hand `public_key_pem` to the client and
+//! the full offline path runs as it would against a real server.
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::pkcs8::EncodePublicKey;
+use ed25519_dalek::Signer;
+pub use ed25519_dalek::SigningKey;
+
+use crate::codec::{Base64UrlCodec, TokenCodec};
+use crate::errors::AlureError;
+use crate::receipt::{DeviceHasher, Sha256DeviceHasher};
+
+/// Builds `v1.<payload>.<sig>` receipts signed with a caller-supplied key.
+///
+/// ```
+/// use alure_sdk::{ReceiptVerifier, TestReceiptBuilder};
+///
+/// let builder = TestReceiptBuilder::from_seed([7; 32])
+///     .device_id("device-123")
+///     .expires_at("2099-01-01T00:00:00Z")
+///     .features(["export"]);
+/// let verifier = ReceiptVerifier::new(Some(builder.public_key_pem()));
+/// let result = verifier.validate_offline(&builder.build(), "device-123", true);
+/// assert!(result.valid);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestReceiptBuilder {
+    signing_key: SigningKey,
+    claims: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TestReceiptBuilder {
+    /// Starts from the claims the server always sets (`v`, `license_id`,
+    /// `project_id`, `activation_id`, `plan`, `issued_at` now), a
+    /// perpetual license without grace, bound to no device.
+    pub fn new(signing_key: SigningKey) -> Self {
+        let claims = serde_json::json!({
+            "v": 1,
+            "license_id": "lic-test",
+            "project_id": "proj-test",
+            "activation_id": "act-test",
+            "plan": "pro",
+            "issued_at": Utc::now().to_rfc3339(),
+            "expires_at": null,
+            "grace_period_days": 0,
+        });
+        Self {
+            signing_key,
+            claims: claims.as_object().cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self::new(SigningKey::from_bytes(&seed))
+    }
+
+    /// Binds the receipt to `device_id` through its SHA-256, as
+    /// `Sha256DeviceHasher` expects.
+    pub fn device_id(self, device_id: &str) -> Self {
+        self.claim("device_id_hash", Sha256DeviceHasher.hash(device_id))
+    }
+
+    /// RFC3339 expiry.
+    pub fn expires_at(self, expires_at: &str) -> Self {
+        self.claim("expires_at", expires_at)
+    }
+
+    pub fn expires_at_datetime(self, expires_at: DateTime<Utc>) -> Self {
+        self.claim("expires_at", expires_at.to_rfc3339())
+    }
+
+    pub fn issued_at(self, issued_at: DateTime<Utc>) -> Self {
+        self.claim("issued_at", issued_at.to_rfc3339())
+    }
+
+    pub fn grace_period_days(self, days: i64) -> Self {
+        self.claim("grace_period_days", days)
+    }
+
+    pub fn features<I, S>(self, features: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let features: Vec<String> = features.into_iter().map(Into::into).collect();
+        self.claim("features", features)
+    }
+
+    /// Sets or replaces any other claim.
+    pub fn claim(mut self, name: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.claims.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// The claims as they will be signed.
+    pub fn claims(&self) -> serde_json::Value {
+        serde_json::Value::Object(self.claims.clone())
+    }
+
+    /// The signed `v1` token.
+    pub fn build(&self) -> String {
+        let payload = Base64UrlCodec.encode(self.claims().to_string().as_bytes());
+        let signature = self.signing_key.sign(payload.as_bytes());
+        format!(
+            "v1.{payload}.{}",
+            Base64UrlCodec.encode(&signature.to_bytes())
+        )
+    }
+
+    /// SPKI PEM of the signing key, for `AlureClient::new` or
+    /// `ReceiptVerifier::new`.
+    pub fn public_key_pem(&self) -> String {
+        self.signing_key
+            .verifying_key()
+            .to_public_key_pem(Default::default())
+            .expect("ed25519 public keys always encode")
+    }
+}
+
+/// Derives a device id from caller-supplied `(name, value)` components the
+/// way `default_device_id` does, so a failing source such as `hostname` can
+/// be simulated.
+pub fn device_id_from_components(
+    namespace: &uuid::Uuid,
+    components: Vec<(&'static str, Result<String, String>)>,
+) -> Result<String, AlureError> {
+    crate::device::derive_from_components(namespace, components)
+}
@@ -0,0 +1,329 @@
+use std::error::Error as StdError;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use ::http::header::HeaderMap;
+use ::http::Method;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tower_service::Service;
+
+use crate::errors::AlureError;
+
+/// Error bodies beyond this are truncated before they reach `AlureError`.
+pub(crate) const MAX_ERROR_BODY: usize = 64 * 1024;
+const ERROR_BODY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<TransportResponse, AlureError>> + Send + 'a>>;
+
+/// A fully-built API call: the URL already carries the query string and the
+/// body is already serialized.
+#[derive(Debug, Clone)]
+pub struct TransportRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    /// Overall budget for this call when it differs from the default, e.g.
+    /// downloads. Custom transports may enforce their own instead.
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+/// Sends the SDK's HTTP calls. The default goes through `reqwest`; see
+/// `TowerTransport` to plug in a tower `Service` instead.
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_>;
+}
+
+impl TryFrom<TransportRequest> for ::http::Request<Vec<u8>> {
+    type Error = ::http::Error;
+
+    fn try_from(request: TransportRequest) -> Result<Self, Self::Error> {
+        let mut builder = ::http::Request::builder()
+            .method(request.method)
+            .uri(request.url);
+        if let Some(headers) = builder.headers_mut() {
+            *headers = request.headers;
+        }
+        builder.body(request.body)
+    }
+}
+
+impl From<::http::Response<Vec<u8>>> for TransportResponse {
+    fn from(response: ::http::Response<Vec<u8>>) -> Self {
+        let (parts, body) = response.into_parts();
+        Self {
+            status: parts.status.as_u16(),
+            headers: parts.headers,
+            body,
+        }
+    }
+}
+
+/// Adapts a tower `Service` over `http` types into a `Transport`, so
+/// retry/timeout/tracing layers can wrap SDK calls. The service is cloned
+/// per call, as is usual for tower services.
+#[derive(Clone)]
+pub struct TowerTransport<S> {
+    service: S,
+}
+
+impl<S> TowerTransport<S> {
+    pub fn new(service: S) -> Self {
+        Self { service }
+    }
+}
+
+impl<S> std::fmt::Debug for TowerTransport<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TowerTransport").finish_non_exhaustive()
+    }
+}
+
+impl<S> Transport for TowerTransport<S>
+where
+    S: Service<::http::Request<Vec<u8>>, Response = ::http::Response<Vec<u8>>>
+        + Clone
+        + Send
+        + Sync,
+    S::Future: Send,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        let mut service = self.service.clone();
+        Box::pin(async move {
+            let request = ::http::Request::try_from(request)
+                .map_err(|err| AlureError::Transport(format!("invalid_request: {err}")))?;
+            std::future::poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(transport_error)?;
+            let response = service.call(request).await.map_err(transport_error)?;
+            Ok(response.into())
+        })
+    }
+}
+
+fn transport_error(err: impl Into<Box<dyn StdError + Send + Sync>>) -> AlureError {
+    AlureError::Transport(err.into().to_string())
+}
+
+pub(crate) async fn send_reqwest(
+    client: &reqwest::Client,
+    request: &TransportRequest,
+    body: hyper::body::Bytes,
+    read_timeout: Option<Duration>,
+    sink: BodySink<'_>,
+) -> Result<TransportResponse, AlureError> {
+    let mut req = client
+        .request(request.method.clone(), &request.url)
+        .headers(request.headers.clone());
+    if !body.is_empty() {
+        req = req.body(body);
+    }
+    if let Some(timeout) = request.timeout {
+        req = req.timeout(timeout);
+    }
+    let mut resp = req.send().await?;
+    let status = resp.status().as_u16();
+    let headers = resp.headers().clone();
+    let body = if resp.status().is_success() {
+        read_body(&mut resp, read_timeout, sink).await?
+    } else {
+        // Stop at one byte past the cap so the caller can tell it was cut,
+        // and give up on servers that trickle the body.
+        let mut body = Vec::new();
+        let read = async {
+            while let Some(chunk) = resp.chunk().await? {
+                body.extend_from_slice(&chunk);
+                if body.len() > MAX_ERROR_BODY {
+                    body.truncate(MAX_ERROR_BODY + 1);
+                    break;
+                }
+            }
+            Ok::<_, reqwest::Error>(())
+        };
+        let _ = tokio::time::timeout(ERROR_BODY_TIMEOUT, read).await;
+        body
+    };
+    Ok(TransportResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Streams a successful body into `sink` instead of buffering it.
+pub(crate) type BodySink<'a> = Option<&'a mut (dyn AsyncWrite + Unpin + Send)>;
+
+async fn read_body(
+    resp: &mut reqwest::Response,
+    read_timeout: Option<Duration>,
+    mut sink: BodySink<'_>,
+) -> Result<Vec<u8>, AlureError> {
+    let mut body = Vec::new();
+    loop {
+        let chunk = match read_timeout {
+            Some(idle) => tokio::time::timeout(idle, resp.chunk())
+                .await
+                .map_err(|_| {
+                    AlureError::Transport(format!("read_timeout: no data for {idle:?}"))
+                })?,
+            None => resp.chunk().await,
+        };
+        match (chunk?, sink.as_deref_mut()) {
+            (Some(chunk), Some(sink)) => sink.write_all(&chunk).await?,
+            (Some(chunk), None) => body.extend_from_slice(&chunk),
+            (None, _) => break,
+        }
+    }
+    if let Some(sink) = sink {
+        sink.flush().await?;
+    }
+    Ok(body)
+}
+
+/// In-memory sink that refuses to grow past `limit` bytes.
+pub(crate) struct CappedBuffer {
+    buffer: Vec<u8>,
+    limit: usize,
+}
+
+impl CappedBuffer {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            limit,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+impl AsyncWrite for CappedBuffer {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        if this.buffer.len() + buf.len() > this.limit {
+            return Poll::Ready(Err(std::io::Error::other(format!(
+                "asset_too_large: more than {} bytes",
+                this.limit
+            ))));
+        }
+        this.buffer.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Byte budget shared by every download of a client (and its clones), so
+/// concurrent downloads split the configured rate between them.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    bytes_per_second: u64,
+    /// When the bytes granted so far have been paid for.
+    next_free: std::sync::Mutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            next_free: std::sync::Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    /// Largest write granted at once, about a tenth of a second's worth.
+    fn slice(&self) -> usize {
+        (self.bytes_per_second / 10).clamp(1, 64 * 1024) as usize
+    }
+
+    /// Books `bytes` and returns when they may be handed on. Idle time is
+    /// not saved up, so a burst after a pause is still paced.
+    fn reserve(&self, bytes: usize) -> tokio::time::Instant {
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_second as f64);
+        let mut next_free = self.next_free.lock().unwrap_or_else(|err| err.into_inner());
+        let start = (*next_free).max(tokio::time::Instant::now());
+        *next_free = start + cost;
+        *next_free
+    }
+}
+
+/// Passes writes on to `inner` no faster than `limiter` allows; without a
+/// limiter it is a plain pass-through. Since the body is read only as fast
+/// as the sink accepts it, this also paces the download itself.
+pub(crate) struct Throttled<'a, W> {
+    inner: W,
+    limiter: Option<&'a RateLimiter>,
+    /// A granted slice and the time it may be written.
+    pending: Option<(usize, Pin<Box<tokio::time::Sleep>>)>,
+}
+
+impl<'a, W> Throttled<'a, W> {
+    pub(crate) fn new(inner: W, limiter: Option<&'a RateLimiter>) -> Self {
+        Self {
+            inner,
+            limiter,
+            pending: None,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for Throttled<'_, W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let Some(limiter) = this.limiter else {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        };
+        let (granted, sleep) = this.pending.get_or_insert_with(|| {
+            let granted = buf.len().min(limiter.slice());
+            let deadline = limiter.reserve(granted);
+            (granted, Box::pin(tokio::time::sleep_until(deadline)))
+        });
+        if sleep.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let granted = (*granted).min(buf.len());
+        let written = Pin::new(&mut this.inner).poll_write(cx, &buf[..granted]);
+        if written.is_ready() {
+            this.pending = None;
+        }
+        written
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
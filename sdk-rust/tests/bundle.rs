@@ -0,0 +1,102 @@
+mod common;
+
+use alure_sdk::{AlureError, FileStorage};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use common::{client, receipt_for, signing_key, DEVICE_ID};
+use ed25519_dalek::Signer;
+use sha2::{Digest, Sha256};
+
+fn key_fingerprint() -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(signing_key().verifying_key().as_bytes())
+    )
+}
+
+fn bundle(payload: &serde_json::Value, key_fingerprint: &str) -> serde_json::Value {
+    let payload = serde_json::to_vec(payload).unwrap();
+    serde_json::json!({
+        "format": "alure-license-bundle",
+        "version": 1,
+        "key_fingerprint": key_fingerprint,
+        "payload": STANDARD.encode(&payload),
+        "signature": STANDARD.encode(signing_key().sign(&payload).to_bytes()),
+    })
+}
+
+fn payload() -> serde_json::Value {
+    serde_json::json!({
+        "receipt": receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+        "device_id": DEVICE_ID,
+        "activation_id": "act-offline",
+    })
+}
+
+fn write(dir: &std::path::Path, bundle: &serde_json::Value) -> std::path::PathBuf {
+    let path = dir.join("license.bundle.json");
+    std::fs::write(&path, serde_json::to_vec(bundle).unwrap()).unwrap();
+    path
+}
+
+#[test]
+fn valid_bundle_is_imported_and_stored() {
+    let (client, dir) = client(None);
+    let path = write(dir.path(), &bundle(&payload(), &key_fingerprint()));
+
+    let record = client.import_bundle(&path).unwrap();
+
+    assert_eq!(record.activation_id.as_deref(), Some("act-offline"));
+    assert_eq!(record.project_id.as_deref(), Some("proj-1"));
+    let stored = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .load_receipt()
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.receipt, record.receipt);
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+}
+
+#[test]
+fn bundle_for_another_key_is_rejected() {
+    let (client, dir) = client(None);
+    let path = write(dir.path(), &bundle(&payload(), &"ab".repeat(32)));
+
+    let err = client.import_bundle(&path).unwrap_err();
+
+    assert!(
+        matches!(&err, AlureError::Receipt(e) if e.0 == "bundle_key_mismatch"),
+        "{err}"
+    );
+    assert!(client
+        .verify_offline(None, None, false)
+        .unwrap()
+        .reason
+        .is_some());
+}
+
+#[test]
+fn tampered_bundle_is_rejected() {
+    let (client, dir) = client(None);
+    let mut tampered = bundle(&payload(), &key_fingerprint());
+    let mut forged = payload();
+    forged["device_id"] = "someone-else".into();
+    tampered["payload"] = STANDARD.encode(serde_json::to_vec(&forged).unwrap()).into();
+    let path = write(dir.path(), &tampered);
+
+    let err = client.import_bundle(&path).unwrap_err();
+
+    assert!(
+        matches!(&err, AlureError::Receipt(e) if e.0 == "bundle_signature_mismatch"),
+        "{err}"
+    );
+}
+
+#[test]
+fn malformed_bundle_is_rejected() {
+    let (client, dir) = client(None);
+    let path = write(dir.path(), &serde_json::json!({"format": "zip"}));
+
+    let err = client.import_bundle(&path).unwrap_err();
+
+    assert!(err.to_string().contains("invalid_bundle"), "{err}");
+}
@@ -0,0 +1,1452 @@
+mod common;
+
+use alure_sdk::{FileStorage, ReceiptRecord};
+use common::{client, device_hash, receipt_for, DEVICE_ID};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn store(dir: &std::path::Path, receipt: String) {
+    let storage = FileStorage::new(Some(dir.to_path_buf())).unwrap();
+    storage
+        .save_receipt(&ReceiptRecord {
+            receipt,
+            device_id: DEVICE_ID.to_string(),
+            activation_id: Some("act-1".to_string()),
+            project_id: Some("proj-1".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+}
+
+async fn mount_activate(server: &MockServer, receipt: &str, expected_calls: u64) {
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt,
+            "activation_id": "act-2",
+            "expires_at": "2099-01-01T00:00:00Z",
+            "grace_period_days": 7,
+            "server_time": "2026-01-01T00:00:00Z",
+        })))
+        .expect(expected_calls)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn ensure_activated_uses_valid_stored_receipt() {
+    let server = MockServer::start().await;
+    mount_activate(&server, "unused", 0).await;
+    let (client, dir) = client(Some(server.uri()));
+    let receipt = receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7);
+    store(dir.path(), receipt.clone());
+
+    let resp = client
+        .ensure_activated("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    assert_eq!(resp.receipt, receipt);
+    assert_eq!(resp.activation_id, "act-1");
+    assert_eq!(resp.expires_at.as_deref(), Some("2099-01-01T00:00:00Z"));
+    assert_eq!(resp.grace_period_days, 7);
+    assert!(resp.from_cache);
+}
+
+#[tokio::test]
+async fn activate_if_needed_force_bypasses_stored_activation() {
+    let server = MockServer::start().await;
+    let fresh = receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7);
+    mount_activate(&server, &fresh, 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+
+    let resp = client
+        .activate_if_needed("KEY", Some(DEVICE_ID.to_string()), None, None, true)
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-2");
+    assert!(!resp.from_cache);
+}
+
+#[tokio::test]
+async fn activate_if_needed_ignores_activation_of_other_device() {
+    let server = MockServer::start().await;
+    let fresh = receipt_for("other-device", Some("2099-01-01T00:00:00Z"), 7);
+    mount_activate(&server, &fresh, 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+
+    let resp = client
+        .activate_if_needed("KEY", Some("other-device".to_string()), None, None, false)
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-2");
+}
+
+#[tokio::test]
+async fn ensure_activated_activates_without_stored_receipt() {
+    let server = MockServer::start().await;
+    let fresh = receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7);
+    mount_activate(&server, &fresh, 1).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let resp = client
+        .ensure_activated("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-2");
+}
+
+#[tokio::test]
+async fn ensure_activated_reactivates_expired_receipt() {
+    let server = MockServer::start().await;
+    let fresh = receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7);
+    mount_activate(&server, &fresh, 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(dir.path(), receipt_for(DEVICE_ID, Some("2020-01-01T00:00:00Z"), 0));
+
+    let resp = client
+        .ensure_activated("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    assert_eq!(resp.receipt, fresh);
+    assert_eq!(resp.activation_id, "act-2");
+}
+
+#[tokio::test]
+async fn activate_surfaces_license_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(409).set_body_json(serde_json::json!({
+            "statusCode": 409,
+            "message": "seat_limit_reached",
+        })))
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let err = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        alure_sdk::AlureError::License {
+            code: alure_sdk::LicenseErrorCode::SeatLimitReached,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn verify_online_sends_device_meta() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .and(wiremock::matchers::body_json(serde_json::json!({
+            "receipt": "v1.a.b",
+            "device_id": DEVICE_ID,
+            "device_meta": {"os_version": "14.2", "device_id": "spoofed"},
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "valid": true,
+            "server_time": "2026-01-01T00:00:00Z",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let data = client
+        .verify_online(
+            Some("v1.a.b".to_string()),
+            Some(DEVICE_ID.to_string()),
+            Some(serde_json::json!({"os_version": "14.2", "device_id": "spoofed"})),
+        )
+        .await
+        .unwrap();
+    assert!(data.valid);
+}
+
+#[test]
+fn verify_result_parses_each_status() {
+    use alure_sdk::{VerifyResult, VerifyStatus};
+    let cases = [
+        ("active", VerifyStatus::Active),
+        ("expired", VerifyStatus::Expired),
+        ("revoked", VerifyStatus::Revoked),
+        ("not_found", VerifyStatus::NotFound),
+        ("grace", VerifyStatus::Grace),
+        ("suspended", VerifyStatus::Unknown),
+    ];
+    for (status, expected) in cases {
+        let result = VerifyResult::from_value(serde_json::json!({
+            "valid": status == "active" || status == "grace",
+            "status": status,
+            "expires_at": "2099-01-01T00:00:00Z",
+            "reason": "because",
+        }));
+        assert_eq!(result.status, expected);
+        assert_eq!(result.revoked, expected == VerifyStatus::Revoked);
+        assert_eq!(result.expires_at.as_deref(), Some("2099-01-01T00:00:00Z"));
+        assert_eq!(result.reason.as_deref(), Some("because"));
+        assert_eq!(result.raw["status"], status);
+    }
+}
+
+#[test]
+fn verify_result_infers_status_when_missing() {
+    use alure_sdk::{VerifyResult, VerifyStatus};
+    let active = VerifyResult::from_value(serde_json::json!({"valid": true}));
+    assert_eq!(active.status, VerifyStatus::Active);
+    let revoked = VerifyResult::from_value(serde_json::json!({"valid": false, "revoked": true}));
+    assert_eq!(revoked.status, VerifyStatus::Revoked);
+    let unknown = VerifyResult::from_value(serde_json::json!({"valid": false, "status": 42}));
+    assert_eq!(unknown.status, VerifyStatus::Unknown);
+}
+
+#[test]
+fn verify_result_access_predicates() {
+    use alure_sdk::VerifyResult;
+    let active = VerifyResult::from_value(serde_json::json!({"valid": true, "status": "active"}));
+    assert!(active.is_active() && !active.is_in_grace() && active.allows_access());
+    let grace = VerifyResult::from_value(serde_json::json!({"valid": true, "status": "grace"}));
+    assert!(!grace.is_active() && grace.is_in_grace() && grace.allows_access());
+    let expired =
+        VerifyResult::from_value(serde_json::json!({"valid": false, "status": "expired"}));
+    assert!(!expired.is_active() && !expired.is_in_grace() && !expired.allows_access());
+    let revoked = VerifyResult::from_value(serde_json::json!({"valid": true, "revoked": true}));
+    assert!(!revoked.is_active() && !revoked.allows_access());
+}
+
+async fn mount_release(server: &MockServer, response: ResponseTemplate, expected_calls: u64) {
+    Mock::given(method("POST"))
+        .and(path("/licenses/deactivate"))
+        .and(wiremock::matchers::body_json(
+            serde_json::json!({"license_key": "KEY", "activation_id": "act-1"}),
+        ))
+        .respond_with(response)
+        .expect(expected_calls)
+        .mount(server)
+        .await;
+}
+
+fn released() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({"deactivated": true}))
+}
+
+#[tokio::test]
+async fn migrate_device_replaces_record_on_success() {
+    let server = MockServer::start().await;
+    mount_release(&server, released(), 1).await;
+    mount_activate(&server, &receipt_for("device-new", None, 0), 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(dir.path(), receipt_for(DEVICE_ID, None, 0));
+
+    let resp = client
+        .migrate_device("KEY", "device-new".to_string())
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-2");
+    let stored = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .load_receipt()
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.device_id, "device-new");
+    assert_eq!(stored.activation_id.as_deref(), Some("act-2"));
+}
+
+#[tokio::test]
+async fn migrate_device_keeps_old_record_on_failure() {
+    let server = MockServer::start().await;
+    mount_release(&server, released(), 0).await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+    let old_receipt = receipt_for(DEVICE_ID, None, 0);
+    store(dir.path(), old_receipt.clone());
+
+    assert!(client
+        .migrate_device("KEY", "device-new".to_string())
+        .await
+        .is_err());
+    let stored = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .load_receipt()
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.receipt, old_receipt);
+    assert_eq!(stored.device_id, DEVICE_ID);
+    assert_eq!(stored.activation_id.as_deref(), Some("act-1"));
+}
+
+#[tokio::test]
+async fn migrate_device_frees_the_only_seat_before_retrying() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(409).set_body_string("activation_limit_reached"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_activate(&server, &receipt_for("device-new", None, 0), 1).await;
+    mount_release(&server, released(), 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(dir.path(), receipt_for(DEVICE_ID, None, 0));
+
+    let resp = client
+        .migrate_device("KEY", "device-new".to_string())
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-2");
+    let calls: Vec<String> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.url.path().to_string())
+        .collect();
+    assert_eq!(
+        calls,
+        [
+            "/licenses/activate",
+            "/licenses/deactivate",
+            "/licenses/activate"
+        ]
+    );
+    let stored = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .load_receipt()
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.device_id, "device-new");
+}
+
+#[tokio::test]
+async fn migrate_device_succeeds_when_release_fails() {
+    let server = MockServer::start().await;
+    mount_release(
+        &server,
+        ResponseTemplate::new(404).set_body_string("activation_not_found"),
+        1,
+    )
+    .await;
+    mount_activate(&server, &receipt_for("device-new", None, 0), 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(dir.path(), receipt_for(DEVICE_ID, None, 0));
+
+    let resp = client
+        .migrate_device("KEY", "device-new".to_string())
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-2");
+    let stored = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .load_receipt()
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.device_id, "device-new");
+}
+
+#[tokio::test]
+async fn check_license_does_not_persist_anything() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .and(wiremock::matchers::body_json(
+            serde_json::json!({"license_key": "KEY"}),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "valid": true,
+            "seats_available": 2,
+            "receipt": "v1.should.not-be-stored",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+
+    let check = client.check_license("KEY").await.unwrap();
+    assert!(check.valid);
+    assert_eq!(check.seats_available, Some(2));
+    assert!(check.reason.is_none());
+    let storage = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+    assert!(storage.load_receipt().unwrap().is_none());
+}
+
+async fn mount_license_info(server: &MockServer, body: serde_json::Value) {
+    Mock::given(method("POST"))
+        .and(path("/licenses/info"))
+        .and(wiremock::matchers::body_json(body))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "license_id": "lic-1",
+            "plan": "pro",
+            "seats_total": 5,
+            "seats_used": 3,
+            "owner_email": "owner@example.com",
+            "expires_at": "2099-01-01T00:00:00Z",
+            "status": "active",
+            "features": ["export", "sync"],
+            "created_by": "ignored",
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn license_info_authenticates_with_license_key() {
+    let server = MockServer::start().await;
+    mount_license_info(&server, serde_json::json!({"license_key": "KEY"})).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let info = client.license_info(Some("KEY")).await.unwrap();
+
+    assert_eq!(
+        info,
+        alure_sdk::LicenseInfo {
+            license_id: Some("lic-1".to_string()),
+            plan: Some("pro".to_string()),
+            seats_total: Some(5),
+            seats_used: Some(3),
+            owner_email: Some("owner@example.com".to_string()),
+            expires_at: Some("2099-01-01T00:00:00Z".to_string()),
+            status: Some("active".to_string()),
+            features: vec!["export".to_string(), "sync".to_string()],
+        }
+    );
+    assert_eq!(info.seats_available(), Some(2));
+}
+
+#[tokio::test]
+async fn license_info_authenticates_with_stored_receipt() {
+    let server = MockServer::start().await;
+    let receipt = receipt_for(DEVICE_ID, None, 0);
+    mount_license_info(
+        &server,
+        serde_json::json!({"receipt": receipt, "device_id": DEVICE_ID}),
+    )
+    .await;
+    let (client, dir) = client(Some(server.uri()));
+    store(dir.path(), receipt);
+
+    let info = client.license_info(None).await.unwrap();
+
+    assert_eq!(info.plan.as_deref(), Some("pro"));
+}
+
+#[tokio::test]
+async fn license_info_without_key_or_receipt_is_an_error() {
+    let (client, _dir) = client(None);
+
+    let err = client.license_info(None).await.unwrap_err();
+
+    assert!(matches!(
+        err,
+        alure_sdk::AlureError::Http { status: 400, ref message, .. } if message == "missing_receipt"
+    ));
+}
+
+#[test]
+fn license_info_tolerates_missing_fields() {
+    let info: alure_sdk::LicenseInfo =
+        serde_json::from_value(serde_json::json!({"plan": "free"})).unwrap();
+
+    assert_eq!(info.plan.as_deref(), Some("free"));
+    assert!(info.features.is_empty());
+    assert_eq!(info.seats_available(), None);
+}
+
+#[tokio::test]
+async fn requests_carry_request_id_into_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .and(wiremock::matchers::header_exists("x-request-id"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let err = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap_err();
+    let sent = server.received_requests().await.unwrap()[0]
+        .headers
+        .get("x-request-id")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(uuid::Uuid::parse_str(&sent).is_ok());
+    assert_eq!(err.request_id(), Some(sent.as_str()));
+}
+
+#[tokio::test]
+async fn errors_prefer_server_echoed_request_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(
+            ResponseTemplate::new(500)
+                .insert_header("X-Request-Id", "srv-42")
+                .set_body_string("boom"),
+        )
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let err = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.request_id(), Some("srv-42"));
+}
+
+#[test]
+fn activate_response_expiry_helpers() {
+    use chrono::{Duration, TimeZone, Utc};
+    let response = |expires_at: Option<&str>| alure_sdk::ActivateResponse {
+        receipt: String::new(),
+        activation_id: String::new(),
+        expires_at: expires_at.map(str::to_string),
+        grace_period_days: 0,
+        server_time: String::new(),
+        from_cache: false,
+        claims: None,
+        project_id: None,
+        features: None,
+        tier: None,
+        idempotency_key: None,
+    };
+    let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+
+    let valid = response(Some("2026-01-11T00:00:00+00:00"));
+    assert_eq!(
+        valid.expires_at_datetime(),
+        Some(Utc.with_ymd_and_hms(2026, 1, 11, 0, 0, 0).unwrap())
+    );
+    assert_eq!(valid.time_remaining(now), Some(Duration::days(10)));
+
+    assert!(response(None).expires_at_datetime().is_none());
+    assert!(response(None).time_remaining(now).is_none());
+    assert!(response(Some("next tuesday"))
+        .expires_at_datetime()
+        .is_none());
+    assert!(response(Some("next tuesday")).time_remaining(now).is_none());
+}
+
+#[tokio::test]
+async fn verify_online_captures_configured_headers() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-License-Warning", "renew soon")
+                .insert_header("Set-Cookie", "session=secret")
+                .insert_header("X-Other", "ignored")
+                .set_body_json(serde_json::json!({"valid": true})),
+        )
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_captured_headers(["X-License-Warning", "Set-Cookie"]);
+
+    let result = client
+        .verify_online(
+            Some("v1.a.b".to_string()),
+            Some(DEVICE_ID.to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.headers.len(), 1);
+    assert_eq!(
+        result.headers.get("x-license-warning").map(String::as_str),
+        Some("renew soon")
+    );
+}
+
+#[tokio::test]
+async fn no_content_is_ok_for_unit_methods_and_an_error_for_structs() {
+    let server = MockServer::start().await;
+    for route in ["/licenses/revoke", "/licenses/check"] {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+    }
+    let (client, _dir) = client(Some(server.uri()));
+
+    client.deactivate("act-1").await.unwrap();
+    let err = client.check_license("KEY").await.unwrap_err();
+    assert!(
+        matches!(err, alure_sdk::AlureError::EmptyResponse(ref path) if path == "/licenses/check"),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn activate_accepts_device_meta_builder() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .and(wiremock::matchers::body_partial_json(serde_json::json!({
+            "device_meta": {"os": "linux", "app_version": "1.0.0"},
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt_for(DEVICE_ID, None, 0),
+            "activation_id": "act-9",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let meta = alure_sdk::DeviceMeta::new()
+        .os("linux")
+        .app_version("1.0.0");
+    let resp = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, Some(meta.into()))
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-9");
+}
+
+#[tokio::test]
+async fn pre_hashed_device_id_is_sent_and_validated_as_is() {
+    let hashed = device_hash(DEVICE_ID);
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .and(wiremock::matchers::body_partial_json(serde_json::json!({
+            "device_id": hashed,
+            "device_id_is_hash": true,
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt_for(DEVICE_ID, None, 0),
+            "activation_id": "act-9",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_device_id_is_hash(true);
+
+    client
+        .activate("KEY", Some(hashed.clone()), None, None)
+        .await
+        .unwrap();
+
+    let result = client.verify_offline(None, None, true).unwrap();
+    assert!(result.valid, "{result:?}");
+    let result = client
+        .verify_offline(
+            Some(receipt_for(DEVICE_ID, None, 0)),
+            Some(DEVICE_ID.to_string()),
+            true,
+        )
+        .unwrap();
+    assert_eq!(result.reason.as_deref(), Some("device_mismatch"));
+}
+
+async fn activate_with_body(
+    body: serde_json::Value,
+) -> (MockServer, alure_sdk::AlureClient, tempfile::TempDir) {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+    (server, client, dir)
+}
+
+fn activation_fields(receipt: &str) -> serde_json::Value {
+    serde_json::json!({
+        "receipt": receipt,
+        "activation_id": "act-3",
+        "expires_at": null,
+        "grace_period_days": 7,
+        "server_time": "2026-01-01T00:00:00Z",
+    })
+}
+
+#[tokio::test]
+async fn activate_reads_flat_and_enveloped_responses() {
+    let receipt = receipt_for(DEVICE_ID, None, 7);
+    let bodies = [
+        activation_fields(&receipt),
+        serde_json::json!({ "data": activation_fields(&receipt) }),
+        serde_json::json!({ "result": activation_fields(&receipt) }),
+    ];
+    for body in bodies {
+        let (_server, client, _dir) = activate_with_body(body).await;
+        let response = client
+            .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.receipt, receipt);
+        assert_eq!(response.activation_id, "act-3");
+        assert_eq!(response.grace_period_days, 7);
+    }
+}
+
+#[tokio::test]
+async fn activate_uses_configured_envelope_key() {
+    let receipt = receipt_for(DEVICE_ID, None, 7);
+    let (_server, client, _dir) =
+        activate_with_body(serde_json::json!({ "payload": activation_fields(&receipt) })).await;
+    let response = client
+        .with_envelope("payload")
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    assert_eq!(response.receipt, receipt);
+}
+
+#[tokio::test]
+async fn activate_without_receipt_is_an_error() {
+    let (_server, client, _dir) =
+        activate_with_body(serde_json::json!({ "payload": activation_fields("x") })).await;
+    let err = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing_receipt_in_response"));
+}
+
+#[tokio::test]
+async fn oversized_error_bodies_are_truncated() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("x".repeat(4 * 1024 * 1024)))
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let started = std::time::Instant::now();
+    let err = client.check_license("KEY").await.unwrap_err();
+
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    match err {
+        alure_sdk::AlureError::Http {
+            status, message, ..
+        } => {
+            assert_eq!(status, 500);
+            assert!(message.ends_with("...[truncated]"));
+            assert_eq!(message.len(), 64 * 1024 + "...[truncated]".len());
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+async fn mount_activations_page(
+    server: &MockServer,
+    page: Option<u32>,
+    ids: &[&str],
+    next: Option<u32>,
+) {
+    let items: Vec<_> = ids
+        .iter()
+        .map(|id| serde_json::json!({"activation_id": id, "app_version": "1.0.0"}))
+        .collect();
+    let mut body = serde_json::json!({"license_key": "KEY"});
+    if let Some(page) = page {
+        body["page"] = page.into();
+    }
+    Mock::given(method("POST"))
+        .and(path("/licenses/activations"))
+        .and(wiremock::matchers::body_json(body))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"items": items, "next_page": next})),
+        )
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn list_activations_returns_typed_page() {
+    let server = MockServer::start().await;
+    mount_activations_page(&server, Some(2), &["act-3"], None).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let page = client.list_activations("KEY", Some(2)).await.unwrap();
+
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].activation_id, "act-3");
+    assert_eq!(page.items[0].app_version.as_deref(), Some("1.0.0"));
+    assert!(!page.items[0].revoked);
+    assert_eq!(page.next_page, None);
+}
+
+#[tokio::test]
+async fn activations_stream_walks_every_page() {
+    use futures_util::TryStreamExt;
+    let server = MockServer::start().await;
+    mount_activations_page(&server, None, &["act-1", "act-2"], Some(2)).await;
+    mount_activations_page(&server, Some(2), &["act-3"], None).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let items: Vec<_> = client.activations("KEY").try_collect().await.unwrap();
+
+    let ids: Vec<_> = items
+        .iter()
+        .map(|item| item.activation_id.as_str())
+        .collect();
+    assert_eq!(ids, ["act-1", "act-2", "act-3"]);
+}
+
+async fn mount_verify(server: &MockServer, status: u16, expected_calls: u64) {
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(
+            ResponseTemplate::new(status)
+                .set_body_json(serde_json::json!({"valid": true, "status": "active"})),
+        )
+        .expect(expected_calls)
+        .mount(server)
+        .await;
+}
+
+fn soft_offline_client(
+    server: &MockServer,
+) -> (alure_sdk::AlureClient, common::TestClock, tempfile::TempDir) {
+    let clock = common::TestClock::at("2026-03-01T00:00:00Z");
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-06-01T00:00:00Z"), 0),
+    );
+    (client.with_clock(clock.clone()), clock, dir)
+}
+
+#[tokio::test]
+async fn verify_trusts_fresh_online_result() {
+    let server = MockServer::start().await;
+    mount_verify(&server, 200, 1).await;
+    let (client, clock, _dir) = soft_offline_client(&server);
+    let max_age = std::time::Duration::from_secs(3600);
+
+    assert!(client.verify(None, max_age).await.unwrap().is_active());
+    clock.set("2026-03-01T00:30:00Z");
+    assert!(client.verify(None, max_age).await.unwrap().is_active());
+}
+
+#[tokio::test]
+async fn verify_goes_online_when_cache_is_stale() {
+    let server = MockServer::start().await;
+    mount_verify(&server, 200, 2).await;
+    let (client, clock, _dir) = soft_offline_client(&server);
+    let max_age = std::time::Duration::from_secs(3600);
+
+    assert!(client.verify(None, max_age).await.unwrap().valid);
+    clock.set("2026-03-01T02:00:00Z");
+    assert!(client.verify(None, max_age).await.unwrap().valid);
+}
+
+#[tokio::test]
+async fn verify_falls_back_offline_when_server_is_down() {
+    let server = MockServer::start().await;
+    mount_verify(&server, 503, 2).await;
+    let (client, clock, _dir) = soft_offline_client(&server);
+    let max_age = std::time::Duration::from_secs(3600);
+
+    let result = client.verify(None, max_age).await.unwrap();
+    assert!(result.is_active());
+    assert_eq!(result.expires_at.as_deref(), Some("2026-06-01T00:00:00Z"));
+
+    clock.set("2026-07-01T00:00:00Z");
+    assert!(!client.verify(None, max_age).await.unwrap().valid);
+}
+
+#[tokio::test]
+async fn sync_time_corrects_offline_checks() {
+    let server = MockServer::start().await;
+    Mock::given(method("HEAD"))
+        .respond_with(
+            ResponseTemplate::new(200).insert_header("Date", "Wed, 11 Mar 2026 00:00:00 GMT"),
+        )
+        .mount(&server)
+        .await;
+    let clock = common::TestClock::at("2026-03-01T00:00:00Z");
+    let (client, dir) = client(Some(server.uri()));
+    let client = client.with_clock(clock);
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-03-05T00:00:00Z"), 0),
+    );
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+
+    let drift = client.sync_time().await.unwrap();
+
+    assert_eq!(drift, chrono::Duration::days(10));
+    let result = client.verify_offline(None, None, true).unwrap();
+    assert_eq!(result.reason.as_deref(), Some("expired"));
+
+    let reopened = alure_sdk::AlureClient::new(
+        Some(server.uri()),
+        Some(dir.path().to_path_buf()),
+        Some(common::public_key_pem()),
+        None,
+    )
+    .unwrap()
+    .with_clock(common::TestClock::at("2026-03-01T00:00:00Z"));
+    assert!(!reopened.verify_offline(None, None, true).unwrap().valid);
+}
+
+#[tokio::test]
+async fn activate_exposes_receipt_claims() {
+    let server = MockServer::start().await;
+    let receipt = common::sign_receipt(&serde_json::json!({
+        "v": 1,
+        "project_id": "proj-9",
+        "activation_id": "act-2",
+        "device_id_hash": common::device_hash(DEVICE_ID),
+        "plan": "pro",
+        "tier": "gold",
+        "features": ["export", "sync"],
+        "expires_at": null,
+        "grace_period_days": 7,
+    }));
+    mount_activate(&server, &receipt, 1).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let resp = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(resp.project_id.as_deref(), Some("proj-9"));
+    assert_eq!(resp.tier.as_deref(), Some("gold"));
+    assert_eq!(
+        resp.features,
+        Some(vec!["export".to_string(), "sync".to_string()])
+    );
+    assert_eq!(resp.claims.unwrap()["plan"], "pro");
+}
+
+#[tokio::test]
+async fn activate_falls_back_to_modules_and_plan() {
+    let server = MockServer::start().await;
+    let receipt = common::sign_receipt(&serde_json::json!({
+        "v": 1,
+        "device_id_hash": common::device_hash(DEVICE_ID),
+        "plan": "pro",
+        "modules": [{"key": "reports"}, {"key": "api", "params": {"rate": "10"}}],
+    }));
+    mount_activate(&server, &receipt, 1).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let resp = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(resp.project_id, None);
+    assert_eq!(resp.tier.as_deref(), Some("pro"));
+    assert_eq!(
+        resp.features,
+        Some(vec!["reports".to_string(), "api".to_string()])
+    );
+}
+
+#[tokio::test]
+async fn custom_media_types_reach_the_server() {
+    use wiremock::matchers::header;
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .and(header("accept", "application/vnd.alure.v1+json"))
+        .and(header("content-type", "application/json; charset=utf-8"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .and(header("accept", "application/json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": false})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_content_type("application/json; charset=utf-8");
+
+    let scoped = client.clone().with_accept("application/vnd.alure.v1+json");
+    assert!(scoped.check_license("KEY").await.unwrap().valid);
+    assert!(!client.check_license("KEY").await.unwrap().valid);
+}
+
+#[tokio::test]
+async fn per_call_media_types_win_over_the_configured_ones() {
+    use wiremock::matchers::header;
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .and(header("accept", "application/vnd.alure.v2+json"))
+        .and(header("content-type", "application/vnd.alure.v2+json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .and(header("accept", "application/vnd.alure.v1+json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": false})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client
+        .with_accept("application/vnd.alure.v1+json")
+        .with_content_type("application/json; charset=utf-8");
+
+    let v2 = "application/vnd.alure.v2+json".to_string();
+    let check: alure_sdk::LicenseCheck = client
+        .call(
+            reqwest::Method::POST,
+            "/licenses/check",
+            Some(serde_json::json!({"license_key": "KEY"})),
+            vec![
+                ("Accept".to_string(), v2.clone()),
+                ("content-type".to_string(), v2),
+            ],
+        )
+        .await
+        .unwrap();
+    assert!(check.valid);
+    assert!(!client.check_license("KEY").await.unwrap().valid);
+}
+
+async fn mount_verify_body(server: &MockServer, body: serde_json::Value, expected_calls: u64) {
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .expect(expected_calls)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn verify_strict_passes_when_both_sides_agree() {
+    let server = MockServer::start().await;
+    mount_verify_body(&server, serde_json::json!({"valid": true}), 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+
+    let result = client.verify_strict(None, None, None).await.unwrap();
+
+    assert!(result.is_active());
+    assert_eq!(result.expires_at.as_deref(), Some("2099-01-01T00:00:00Z"));
+}
+
+#[tokio::test]
+async fn verify_strict_stops_when_offline_check_fails() {
+    let server = MockServer::start().await;
+    mount_verify_body(&server, serde_json::json!({"valid": true}), 0).await;
+    let (client, dir) = client(Some(server.uri()));
+    let mut forged = receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7);
+    forged.push('x');
+    store(dir.path(), forged);
+
+    let result = client.verify_strict(None, None, None).await.unwrap();
+
+    assert!(!result.valid);
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("offline_failed: invalid_signature")
+    );
+    assert_eq!(result.exit_code(), alure_sdk::exit_code::INVALID_RECEIPT);
+}
+
+#[tokio::test]
+async fn verify_strict_rejects_revocation_of_offline_valid_receipt() {
+    let server = MockServer::start().await;
+    mount_verify_body(
+        &server,
+        serde_json::json!({"valid": true, "revoked": true}),
+        1,
+    )
+    .await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+
+    let result = client.verify_strict(None, None, None).await.unwrap();
+
+    assert!(!result.valid);
+    assert_eq!(result.reason.as_deref(), Some("online_failed: revoked"));
+    assert_eq!(result.exit_code(), alure_sdk::exit_code::REVOKED);
+}
+
+#[tokio::test]
+async fn verify_strict_keeps_the_exit_code_of_an_expired_receipt() {
+    let server = MockServer::start().await;
+    mount_verify_body(&server, serde_json::json!({"valid": true}), 0).await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2020-01-01T00:00:00Z"), 0),
+    );
+
+    let result = client.verify_strict(None, None, None).await.unwrap();
+
+    assert_eq!(result.reason.as_deref(), Some("offline_failed: expired"));
+    assert_eq!(result.exit_code(), alure_sdk::exit_code::EXPIRED);
+}
+
+#[tokio::test]
+async fn verify_online_server_time_updates_drift_when_enabled() {
+    let server = MockServer::start().await;
+    mount_verify_body(
+        &server,
+        serde_json::json!({"valid": true, "server_time": "2026-03-11T00:00:00Z"}),
+        2,
+    )
+    .await;
+    let (client, dir) = client(Some(server.uri()));
+    let client = client.with_clock(common::TestClock::at("2026-03-01T00:00:00Z"));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-03-05T00:00:00Z"), 0),
+    );
+    let storage = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+
+    client.verify_online(None, None, None).await.unwrap();
+    assert_eq!(storage.load_clock_drift().unwrap(), None);
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+
+    let client = client.with_server_time_sync(true);
+    client.verify_online(None, None, None).await.unwrap();
+
+    assert_eq!(storage.load_clock_drift().unwrap(), Some(10 * 86_400));
+    let result = client.verify_offline(None, None, true).unwrap();
+    assert_eq!(result.reason.as_deref(), Some("expired"));
+}
+
+fn signed_verify_body(valid: bool) -> serde_json::Value {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use ed25519_dalek::Signer;
+    let canonical =
+        format!(r#"{{"expires_at":"2099-01-01T00:00:00Z","status":"active","valid":{valid}}}"#);
+    let signature = common::signing_key().sign(canonical.as_bytes());
+    serde_json::json!({
+        "valid": valid,
+        "status": "active",
+        "expires_at": "2099-01-01T00:00:00Z",
+        "signature": STANDARD.encode(signature.to_bytes()),
+    })
+}
+
+#[tokio::test]
+async fn signed_verify_response_is_accepted() {
+    let server = MockServer::start().await;
+    mount_verify_body(&server, signed_verify_body(true), 1).await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_signed_verify_responses(true);
+
+    let result = client
+        .verify_online(Some("receipt".into()), Some(DEVICE_ID.into()), None)
+        .await
+        .unwrap();
+
+    assert!(result.is_active());
+}
+
+#[tokio::test]
+async fn tampered_verify_response_is_rejected() {
+    let server = MockServer::start().await;
+    let mut body = signed_verify_body(false);
+    body["valid"] = true.into();
+    mount_verify_body(&server, body, 1).await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_signed_verify_responses(true);
+
+    let err = client
+        .verify_online(Some("receipt".into()), Some(DEVICE_ID.into()), None)
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(
+            &err,
+            alure_sdk::AlureError::ResponseSignature { reason, .. } if reason == "signature_mismatch"
+        ),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn unsigned_verify_response_is_rejected_only_when_required() {
+    let server = MockServer::start().await;
+    mount_verify_body(&server, serde_json::json!({"valid": true}), 2).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    assert!(client
+        .verify_online(Some("receipt".into()), Some(DEVICE_ID.into()), None)
+        .await
+        .is_ok());
+    let err = client
+        .with_signed_verify_responses(true)
+        .verify_online(Some("receipt".into()), Some(DEVICE_ID.into()), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing_signature"), "{err}");
+}
+
+fn clocked_client() -> (alure_sdk::AlureClient, common::TestClock, tempfile::TempDir) {
+    let clock = common::TestClock::at("2026-03-01T00:00:00Z");
+    let (client, dir) = client(None);
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-06-01T00:00:00Z"), 0),
+    );
+    let client = client
+        .with_clock(clock.clone())
+        .with_clock_rollback_tolerance(Some(chrono::Duration::hours(24)));
+    (client, clock, dir)
+}
+
+#[test]
+fn rolled_back_clock_is_rejected_offline() {
+    let (client, clock, dir) = clocked_client();
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+    let seen = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .load_max_seen_time()
+        .unwrap();
+    assert_eq!(seen, Some("2026-03-01T00:00:00Z".parse().unwrap()));
+
+    clock.set("2026-05-01T00:00:00Z");
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+    clock.set("2026-03-01T00:00:00Z");
+    let result = client.verify_offline(None, None, true).unwrap();
+
+    assert_eq!(result.reason.as_deref(), Some("clock_rollback"));
+}
+
+#[test]
+fn forward_clock_and_small_skew_are_accepted() {
+    let (client, clock, _dir) = clocked_client();
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+
+    clock.set("2026-02-28T12:00:00Z");
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+    clock.set("2026-04-01T00:00:00Z");
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+}
+
+#[test]
+fn rollback_check_is_off_by_default() {
+    let clock = common::TestClock::at("2026-05-01T00:00:00Z");
+    let (client, dir) = client(None);
+    store(dir.path(), receipt_for(DEVICE_ID, Some("2026-06-01T00:00:00Z"), 0));
+    let client = client.with_clock(clock.clone());
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+
+    clock.set("2026-03-01T00:00:00Z");
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+    assert!(!dir.path().join("max_seen_time").exists());
+}
+
+#[test]
+fn last_seen_time_is_only_written_when_it_moves_forward() {
+    let (client, clock, dir) = clocked_client();
+    let path = dir.path().join("max_seen_time");
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+    let written = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    clock.set("2026-02-28T12:00:00Z");
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+    assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), written);
+    assert!(!dir.path().join("max_seen_time.tmp").exists());
+}
+
+#[tokio::test]
+async fn online_contact_records_the_last_seen_time() {
+    let server = MockServer::start().await;
+    mount_verify_body(&server, serde_json::json!({"valid": true}), 1).await;
+    let (client, dir) = client(Some(server.uri()));
+    let client = client
+        .with_clock(common::TestClock::at("2026-04-01T00:00:00Z"))
+        .with_clock_rollback_tolerance(Some(chrono::Duration::hours(24)));
+
+    client
+        .verify_online(Some("receipt".into()), Some(DEVICE_ID.into()), None)
+        .await
+        .unwrap();
+
+    let seen = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .load_max_seen_time()
+        .unwrap();
+    assert_eq!(seen, Some("2026-04-01T00:00:00Z".parse().unwrap()));
+}
+
+#[tokio::test]
+async fn server_grace_override_extends_offline_grace() {
+    let server = MockServer::start().await;
+    mount_verify_body(
+        &server,
+        serde_json::json!({"valid": true, "grace_period_days": 10}),
+        1,
+    )
+    .await;
+    let clock = common::TestClock::at("2026-03-11T00:00:00Z");
+    let (client, dir) = client(Some(server.uri()));
+    let client = client.with_clock(clock.clone());
+    let receipt = receipt_for(DEVICE_ID, Some("2026-03-10T00:00:00Z"), 2);
+    store(dir.path(), receipt.clone());
+
+    clock.set("2026-03-15T00:00:00Z");
+    let before = client.verify_offline(None, None, true).unwrap();
+    assert_eq!(before.reason.as_deref(), Some("expired"));
+
+    let online = client.verify_online(None, None, None).await.unwrap();
+    assert_eq!(online.grace_period_days, Some(10));
+    let after = client.verify_offline(None, None, true).unwrap();
+    assert!(after.is_in_grace(), "{after:?}");
+    assert_eq!(after.grace_period_days, Some(10));
+    assert_eq!(after.grace_until.as_deref(), Some("2026-03-20T00:00:00Z"));
+
+    clock.set("2026-03-21T00:00:00Z");
+    let ended = client.verify_offline(None, None, true).unwrap();
+    assert_eq!(ended.reason.as_deref(), Some("expired"));
+}
+
+#[tokio::test]
+async fn server_grace_override_is_ignored_for_another_receipt() {
+    let server = MockServer::start().await;
+    mount_verify_body(
+        &server,
+        serde_json::json!({"valid": true, "grace_period_days": 10}),
+        1,
+    )
+    .await;
+    let (client, dir) = client(Some(server.uri()));
+    let client = client.with_clock(common::TestClock::at("2026-03-15T00:00:00Z"));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-03-10T00:00:00Z"), 2),
+    );
+    client.verify_online(None, None, None).await.unwrap();
+
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-03-09T00:00:00Z"), 2),
+    );
+    let result = client.verify_offline(None, None, true).unwrap();
+    assert_eq!(result.reason.as_deref(), Some("expired"));
+}
+
+#[test]
+fn verify_offline_at_checks_validity_on_a_given_date() {
+    use chrono::{TimeZone, Utc};
+    let (client, dir) = client(None);
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-06-01T00:00:00Z"), 0),
+    );
+    let at = |year, month| Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).unwrap();
+
+    let early = client
+        .verify_offline_at(None, None, at(2026, 3), true)
+        .unwrap();
+    assert!(early.valid, "{early:?}");
+    let late = client
+        .verify_offline_at(None, None, at(2026, 9), true)
+        .unwrap();
+    assert_eq!(late.reason.as_deref(), Some("expired"));
+
+    let explicit = client
+        .verify_offline_at(
+            Some(receipt_for(DEVICE_ID, Some("2026-06-01T00:00:00Z"), 0)),
+            Some(DEVICE_ID.to_string()),
+            at(2026, 3),
+            true,
+        )
+        .unwrap();
+    assert!(explicit.valid);
+}
+
+fn receipt_with_features(device_id: &str, expires_at: &str) -> String {
+    common::sign_receipt(&serde_json::json!({
+        "v": 1,
+        "license_id": "lic-1",
+        "project_id": "proj-1",
+        "device_id_hash": device_hash(device_id),
+        "issued_at": "2026-01-01T00:00:00Z",
+        "expires_at": expires_at,
+        "grace_period_days": 0,
+        "features": ["export", "sync"],
+    }))
+}
+
+#[test]
+fn authorized_features_returns_features_of_valid_receipt() {
+    let (client, dir) = client(None);
+    store(
+        dir.path(),
+        receipt_with_features(DEVICE_ID, "2099-01-01T00:00:00Z"),
+    );
+
+    assert_eq!(client.authorized_features().unwrap(), ["export", "sync"]);
+}
+
+#[test]
+fn authorized_features_rejects_invalid_receipts() {
+    let cases = [
+        (
+            receipt_with_features(DEVICE_ID, "2020-01-01T00:00:00Z"),
+            "expired",
+        ),
+        (
+            receipt_with_features("other-device", "2099-01-01T00:00:00Z"),
+            "device_mismatch",
+        ),
+    ];
+    for (receipt, reason) in cases {
+        let (client, dir) = client(None);
+        store(dir.path(), receipt);
+
+        let err = client.authorized_features().unwrap_err();
+        assert!(
+            matches!(&err, alure_sdk::AlureError::Receipt(err) if err.0 == reason),
+            "{err}"
+        );
+    }
+
+    let (client, _dir) = client(None);
+    assert!(matches!(
+        client.authorized_features().unwrap_err(),
+        alure_sdk::AlureError::Http { status: 400, ref message, .. } if message == "missing_receipt"
+    ));
+}
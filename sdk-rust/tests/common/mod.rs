@@ -0,0 +1,80 @@
+#![allow(dead_code)]
+
+use alure_sdk::AlureClient;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use ed25519_dalek::pkcs8::EncodePublicKey;
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+
+pub const DEVICE_ID: &str = "device-123";
+
+pub fn signing_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+}
+
+pub fn public_key_pem() -> String {
+    signing_key()
+        .verifying_key()
+        .to_public_key_pem(Default::default())
+        .expect("encode public key")
+}
+
+pub fn device_hash(device_id: &str) -> String {
+    format!("{:x}", Sha256::digest(device_id.as_bytes()))
+}
+
+pub fn sign_receipt(payload: &serde_json::Value) -> String {
+    let payload_b64 = URL_SAFE_NO_PAD.encode(serde_json::to_vec(payload).unwrap());
+    let signature = signing_key().sign(payload_b64.as_bytes());
+    format!(
+        "v1.{payload_b64}.{}",
+        URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    )
+}
+
+pub fn receipt_for(device_id: &str, expires_at: Option<&str>, grace_days: i64) -> String {
+    sign_receipt(&serde_json::json!({
+        "v": 1,
+        "license_id": "lic-1",
+        "project_id": "proj-1",
+        "activation_id": "act-1",
+        "device_id_hash": device_hash(device_id),
+        "plan": "pro",
+        "issued_at": "2026-01-01T00:00:00Z",
+        "expires_at": expires_at,
+        "grace_period_days": grace_days,
+    }))
+}
+
+pub fn client(base_url: Option<String>) -> (AlureClient, TempDir) {
+    let dir = TempDir::new().expect("temp dir");
+    let client = AlureClient::new(
+        base_url,
+        Some(dir.path().to_path_buf()),
+        Some(public_key_pem()),
+        Some(5),
+    )
+    .expect("client");
+    (client, dir)
+}
+
+/// `alure_sdk::TestClock` taking RFC 3339 strings.
+#[derive(Debug, Clone)]
+pub struct TestClock(alure_sdk::TestClock);
+
+impl TestClock {
+    pub fn at(now: &str) -> Self {
+        Self(alure_sdk::TestClock::new(now.parse().unwrap()))
+    }
+
+    pub fn set(&self, now: &str) {
+        self.0.set(now.parse().unwrap());
+    }
+}
+
+impl alure_sdk::Clock for TestClock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.0.now()
+    }
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use alure_sdk::{AlureClient, AlureError, DeviceIdSources, FileStorage, ReceiptRecord};
+use common::{device_hash, receipt_for};
+use tempfile::TempDir;
+
+fn client_in(dir: &TempDir, sources: DeviceIdSources) -> AlureClient {
+    AlureClient::new(None, Some(dir.path().to_path_buf()), None, None)
+        .unwrap()
+        .with_device_id_sources(sources)
+}
+
+#[test]
+fn device_id_without_mac_uses_persisted_install_id() {
+    let dir = TempDir::new().unwrap();
+    let sources = DeviceIdSources {
+        mac_address: false,
+        ..DeviceIdSources::default()
+    };
+    let first = client_in(&dir, sources).default_device_id().unwrap();
+    let second = client_in(&dir, sources).default_device_id().unwrap();
+    assert_eq!(first, second);
+
+    let install_id = std::fs::read_to_string(dir.path().join("install_id")).unwrap();
+    let host = hostname::get().unwrap().to_string_lossy().to_string();
+    let raw = format!("{host}-{install_id}-{}", whoami::username());
+    let expected = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_DNS, raw.as_bytes()).to_string();
+    assert_eq!(first, expected);
+}
+
+#[test]
+fn private_device_id_differs_per_install() {
+    let first = client_in(&TempDir::new().unwrap(), DeviceIdSources::private())
+        .default_device_id()
+        .unwrap();
+    let second = client_in(&TempDir::new().unwrap(), DeviceIdSources::private())
+        .default_device_id()
+        .unwrap();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn device_id_namespace_scopes_the_id_per_product() {
+    let dir = TempDir::new().unwrap();
+    let product = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, b"https://example.com/app");
+    let scoped = || {
+        client_in(&dir, DeviceIdSources::private())
+            .with_device_id_namespace(product)
+            .default_device_id()
+            .unwrap()
+    };
+    let default = client_in(&dir, DeviceIdSources::private())
+        .default_device_id()
+        .unwrap();
+
+    assert_eq!(scoped(), scoped());
+    assert_ne!(scoped(), default);
+    let install_id = std::fs::read_to_string(dir.path().join("install_id")).unwrap();
+    let expected = uuid::Uuid::new_v5(&product, install_id.as_bytes()).to_string();
+    assert_eq!(scoped(), expected);
+}
+
+#[test]
+fn device_meta_serializes_canonical_shape() {
+    let meta = alure_sdk::DeviceMeta::new()
+        .os("linux")
+        .os_version("Ubuntu 24.04")
+        .arch("x86_64")
+        .app_version("1.2.3");
+    assert_eq!(
+        serde_json::Value::from(meta),
+        serde_json::json!({
+            "os": "linux",
+            "os_version": "Ubuntu 24.04",
+            "arch": "x86_64",
+            "app_version": "1.2.3",
+        })
+    );
+}
+
+#[test]
+fn device_meta_detects_platform_fields() {
+    let meta = alure_sdk::DeviceMeta::detect().app_version("2.0.0");
+    assert_eq!(meta.os.as_deref(), Some(std::env::consts::OS));
+    assert_eq!(meta.arch.as_deref(), Some(std::env::consts::ARCH));
+    let value = serde_json::Value::from(meta);
+    assert_eq!(value["app_version"], "2.0.0");
+    assert_eq!(value["os"], std::env::consts::OS);
+}
+
+fn store_receipt_for(dir: &TempDir, receipt_device: &str, stored_device: &str) {
+    FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .save_receipt(&ReceiptRecord {
+            receipt: receipt_for(receipt_device, Some("2000-01-01T00:00:00Z"), 0),
+            device_id: stored_device.to_string(),
+            activation_id: Some("act-1".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+}
+
+#[test]
+fn stored_receipt_for_this_device_matches_even_when_expired() {
+    let dir = TempDir::new().unwrap();
+    let client = client_in(&dir, DeviceIdSources::private());
+    let device_id = client.default_device_id().unwrap();
+    store_receipt_for(&dir, &device_id, &device_id);
+
+    assert!(client.device_matches_stored_receipt().unwrap());
+    let binding = client.device_binding().unwrap();
+    assert_eq!(binding.current_hash, device_hash(&device_id));
+    assert_eq!(binding.receipt_hash, Some(device_hash(&device_id)));
+}
+
+#[test]
+fn stored_receipt_for_another_device_does_not_match() {
+    let dir = TempDir::new().unwrap();
+    let client = client_in(&dir, DeviceIdSources::private());
+    let device_id = client.default_device_id().unwrap();
+    store_receipt_for(&dir, "other-device", "other-device");
+
+    assert!(!client.device_matches_stored_receipt().unwrap());
+    let binding = client.device_binding().unwrap();
+    assert_eq!(binding.stored_device_id, "other-device");
+    assert_eq!(binding.current_device_id, device_id);
+    assert_eq!(binding.receipt_hash, Some(device_hash("other-device")));
+}
+
+#[test]
+fn device_match_without_stored_receipt_fails() {
+    let client = client_in(&TempDir::new().unwrap(), DeviceIdSources::private());
+    let err = client.device_matches_stored_receipt().unwrap_err();
+    assert!(
+        matches!(err, AlureError::Http { status: 400, ref message, .. } if message == "missing_receipt"),
+        "{err:?}"
+    );
+}
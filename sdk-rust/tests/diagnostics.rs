@@ -0,0 +1,136 @@
+mod common;
+
+use alure_sdk::{AlureClient, AlureError, Diagnostics, Endpoints, MAX_DIAGNOSTICS_BYTES};
+use common::{client, receipt_for, DEVICE_ID};
+use wiremock::matchers::{body_bytes, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn diagnostics_client(server: &MockServer) -> (AlureClient, tempfile::TempDir) {
+    let (client, dir) = client(Some(server.uri()));
+    let client = client.with_endpoints(Endpoints {
+        diagnostics: Some("/licenses/diagnostics".to_string()),
+        ..Default::default()
+    });
+    (client, dir)
+}
+
+async fn mount_activate(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt_for(DEVICE_ID, None, 0),
+            "activation_id": "act-7",
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn diagnostics_are_uploaded_after_activation() {
+    let server = MockServer::start().await;
+    mount_activate(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/diagnostics"))
+        .and(query_param("activation_id", "act-7"))
+        .and(header("Content-Type", "text/plain"))
+        .and(body_bytes(b"crash at 0xdead".to_vec()))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = diagnostics_client(&server);
+
+    let diagnostics = Diagnostics::new(b"crash at 0xdead".to_vec()).with_content_type("text/plain");
+    let resp = client
+        .activate_with_diagnostics("KEY", Some(DEVICE_ID.to_string()), None, None, diagnostics)
+        .await
+        .unwrap();
+    assert_eq!(resp.activation_id, "act-7");
+}
+
+#[tokio::test]
+async fn activate_without_diagnostics_sends_a_single_request() {
+    let server = MockServer::start().await;
+    mount_activate(&server).await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert!(body.get("diagnostics").is_none(), "{body}");
+}
+
+#[tokio::test]
+async fn failed_diagnostics_upload_is_reported_but_keeps_the_activation() {
+    let server = MockServer::start().await;
+    mount_activate(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/diagnostics"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = diagnostics_client(&server);
+
+    let err = client
+        .activate_with_diagnostics(
+            "KEY",
+            Some(DEVICE_ID.to_string()),
+            None,
+            None,
+            Diagnostics::new(b"env".to_vec()),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AlureError::Http { status: 500, .. }), "{err:?}");
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+}
+
+#[tokio::test]
+async fn oversized_diagnostics_are_rejected_before_activating() {
+    let server = MockServer::start().await;
+    let (client, _dir) = diagnostics_client(&server);
+
+    let err = client
+        .activate_with_diagnostics(
+            "KEY",
+            Some(DEVICE_ID.to_string()),
+            None,
+            None,
+            Diagnostics::new(vec![0u8; MAX_DIAGNOSTICS_BYTES + 1]),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, AlureError::Config(ref message) if message.starts_with("diagnostics_too_large")),
+        "{err:?}"
+    );
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn diagnostics_need_a_configured_endpoint() {
+    let server = MockServer::start().await;
+    let (client, _dir) = client(Some(server.uri()));
+
+    let err = client
+        .activate_with_diagnostics(
+            "KEY",
+            Some(DEVICE_ID.to_string()),
+            None,
+            None,
+            Diagnostics::new(b"env".to_vec()),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, AlureError::Config(ref message) if message == "diagnostics_endpoint_not_configured"),
+        "{err:?}"
+    );
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
@@ -0,0 +1,676 @@
+mod common;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ed25519_dalek::Signer;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ASSET: &[u8] = b"installer bytes";
+
+async fn asset_server(token_signature: Option<String>, sig_file: Option<String>) -> MockServer {
+    let server = MockServer::start().await;
+    let mut token = serde_json::json!({"token": "tok-1", "expires_at": "2099-01-01T00:00:00Z"});
+    if let Some(signature) = token_signature {
+        token["signature"] = signature.into();
+    }
+    Mock::given(method("POST"))
+        .and(path("/updates/download-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(token))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .and(query_param("token", "tok-1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Disposition", "attachment; filename=\"app.bin\"")
+                .set_body_bytes(ASSET),
+        )
+        .mount(&server)
+        .await;
+    if let Some(signature) = sig_file {
+        Mock::given(method("GET"))
+            .and(path("/updates/download/asset-1.sig"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(signature))
+            .mount(&server)
+            .await;
+    }
+    server
+}
+
+fn signature_of(data: &[u8]) -> String {
+    STANDARD.encode(common::signing_key().sign(data).to_bytes())
+}
+
+async fn download(
+    server: &MockServer,
+) -> (
+    Result<std::path::PathBuf, alure_sdk::AlureError>,
+    tempfile::TempDir,
+) {
+    let (client, dir) = common::client(Some(server.uri()));
+    let result = client
+        .with_asset_signatures(true)
+        .download_asset(
+            "asset-1",
+            Some(common::receipt_for(common::DEVICE_ID, None, 0)),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            None,
+        )
+        .await;
+    (result, dir)
+}
+
+#[tokio::test]
+async fn accepts_asset_signed_in_token_response() {
+    let server = asset_server(Some(signature_of(ASSET)), None).await;
+    let (result, _dir) = download(&server).await;
+    let path = result.unwrap();
+    assert_eq!(std::fs::read(path).unwrap(), ASSET);
+}
+
+#[tokio::test]
+async fn accepts_asset_signed_in_companion_sig_file() {
+    let server = asset_server(None, Some(signature_of(ASSET))).await;
+    let (result, _dir) = download(&server).await;
+    assert!(result.unwrap().exists());
+}
+
+#[tokio::test]
+async fn rejects_tampered_asset_and_removes_file() {
+    let server = asset_server(Some(signature_of(b"other bytes")), None).await;
+    let (result, dir) = download(&server).await;
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("asset_signature_mismatch"));
+    assert!(!dir.path().join("downloads").join("app.bin").exists());
+}
+
+#[tokio::test]
+async fn tampered_asset_leaves_an_existing_target_alone() {
+    let server = asset_server(Some(signature_of(b"other bytes")), None).await;
+    let (client, dir) = common::client(Some(server.uri()));
+    let dest = dir.path().join("app.bin");
+    std::fs::write(&dest, b"previous install").unwrap();
+
+    let err = client
+        .with_asset_signatures(true)
+        .download_asset(
+            "asset-1",
+            Some(common::receipt_for(common::DEVICE_ID, None, 0)),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            Some(dest.clone()),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("asset_signature_mismatch"));
+    assert_eq!(std::fs::read(&dest).unwrap(), b"previous install");
+    assert!(!dir.path().join("app.bin.part").exists());
+}
+
+#[tokio::test]
+async fn content_disposition_cannot_escape_the_downloads_dir() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header(
+                    "Content-Disposition",
+                    "attachment; filename=\"../receipt.json\"",
+                )
+                .set_body_bytes(ASSET),
+        )
+        .mount(&server)
+        .await;
+    let (client, dir) = common::client(Some(server.uri()));
+    let receipt = dir.path().join("receipt.json");
+    std::fs::write(&receipt, b"stored receipt").unwrap();
+
+    let path = client
+        .download_asset("asset-1", None, None, Some("tok-1".to_string()), None)
+        .await
+        .unwrap();
+
+    assert_eq!(path, dir.path().join("downloads").join("asset-1.bin"));
+    assert_eq!(std::fs::read(&receipt).unwrap(), b"stored receipt");
+}
+
+#[tokio::test]
+async fn explicit_dest_path_creates_missing_parents() {
+    let server = asset_server(None, None).await;
+    let (client, dir) = common::client(Some(server.uri()));
+    let dest = dir.path().join("nested").join("deeper").join("app.bin");
+
+    let path = client
+        .download_asset(
+            "asset-1",
+            None,
+            None,
+            Some("tok-1".to_string()),
+            Some(dest.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(path, dest);
+    assert_eq!(std::fs::read(dest).unwrap(), ASSET);
+}
+
+#[tokio::test]
+async fn download_result_reports_size_and_overwrite() {
+    let server = asset_server(None, None).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+    let fetch =
+        || client.download_asset_file("asset-1", None, None, Some("tok-1".to_string()), None);
+
+    let first = fetch().await.unwrap();
+    assert_eq!(first.bytes_written, ASSET.len() as u64);
+    assert!(!first.replaced_existing);
+    assert_eq!(std::fs::read(&first.path).unwrap(), ASSET);
+
+    let second = fetch().await.unwrap();
+    assert_eq!(second.path, first.path);
+    assert!(second.replaced_existing);
+    assert_eq!(second.sha256, first.sha256);
+}
+
+async fn token_server(body: serde_json::Value) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/updates/download-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn parses_complete_download_token() {
+    let server = token_server(serde_json::json!({
+        "token": "tok-1",
+        "expires_at": "2099-01-01T00:00:00Z",
+        "download_url": "https://cdn.example.com/app.bin?sig=abc",
+        "sha256": "deadbeef",
+    }))
+    .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let token = client
+        .request_download_token("receipt", common::DEVICE_ID, "asset-1")
+        .await
+        .unwrap();
+
+    assert_eq!(token.token, "tok-1");
+    assert_eq!(token.expires_at.as_deref(), Some("2099-01-01T00:00:00Z"));
+    assert_eq!(
+        token.download_url.as_deref(),
+        Some("https://cdn.example.com/app.bin?sig=abc")
+    );
+    assert_eq!(token.sha256.as_deref(), Some("deadbeef"));
+}
+
+#[tokio::test]
+async fn parses_minimal_download_token() {
+    let server = token_server(serde_json::json!({"token": "tok-1"})).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let token = client
+        .request_download_token("receipt", common::DEVICE_ID, "asset-1")
+        .await
+        .unwrap();
+
+    assert_eq!(token.token, "tok-1");
+    assert!(token.expires_at.is_none() && token.download_url.is_none() && token.sha256.is_none());
+}
+
+#[tokio::test]
+async fn empty_download_token_is_an_error() {
+    let server = token_server(serde_json::json!({"expires_at": null})).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let err = client
+        .download_asset(
+            "asset-1",
+            Some("receipt".to_string()),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, alure_sdk::AlureError::EmptyResponse(_)));
+}
+
+#[tokio::test]
+async fn relative_download_url_is_resolved_against_base_url() {
+    let server = token_server(serde_json::json!({
+        "token": "tok-1",
+        "download_url": "/files/app.bin",
+    }))
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/files/app.bin"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(ASSET))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let path = client
+        .download_asset(
+            "asset-1",
+            Some("receipt".to_string()),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(path).unwrap(), ASSET);
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Serves `ASSET` in full, or from byte 4 on for `Range: bytes=4-`.
+async fn resumable_server(full_body: &[u8], full_calls: u64) -> MockServer {
+    let server = token_server(serde_json::json!({
+        "token": "tok-1",
+        "sha256": sha256_hex(ASSET),
+    }))
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .and(wiremock::matchers::header("Range", "bytes=4-"))
+        .respond_with(ResponseTemplate::new(206).set_body_bytes(&ASSET[4..]))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(full_body))
+        .expect(full_calls)
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn download_with_partial(
+    server: &MockServer,
+    partial: &[u8],
+) -> (
+    Result<std::path::PathBuf, alure_sdk::AlureError>,
+    tempfile::TempDir,
+) {
+    let (client, dir) = common::client(Some(server.uri()));
+    let dest = dir.path().join("app.bin");
+    std::fs::write(dir.path().join("app.bin.part"), partial).unwrap();
+    let result = client
+        .download_asset(
+            "asset-1",
+            Some("receipt".to_string()),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            Some(dest),
+        )
+        .await;
+    (result, dir)
+}
+
+#[tokio::test]
+async fn resumes_a_valid_partial() {
+    let server = resumable_server(ASSET, 0).await;
+    let (result, _dir) = download_with_partial(&server, &ASSET[..4]).await;
+    assert_eq!(std::fs::read(result.unwrap()).unwrap(), ASSET);
+}
+
+#[tokio::test]
+async fn corrupt_partial_is_discarded_and_downloaded_again() {
+    let server = resumable_server(ASSET, 1).await;
+    let (result, dir) = download_with_partial(&server, b"XXXX").await;
+    assert_eq!(std::fs::read(result.unwrap()).unwrap(), ASSET);
+    assert!(!dir.path().join("app.bin.part").exists());
+}
+
+#[tokio::test]
+async fn checksum_mismatch_after_full_retry_is_an_error() {
+    let server = resumable_server(b"corrupted on the server", 1).await;
+    let (result, dir) = download_with_partial(&server, b"XXXX").await;
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("asset_checksum_mismatch"), "{err}");
+    assert!(!dir.path().join("app.bin").exists());
+    assert!(!dir.path().join("app.bin.part").exists());
+}
+
+#[tokio::test]
+async fn small_asset_is_returned_in_memory() {
+    let server = asset_server(None, None).await;
+    let (client, dir) = common::client(Some(server.uri()));
+
+    let bytes = client
+        .download_asset_bytes("asset-1", None, None, Some("tok-1".to_string()), 1024)
+        .await
+        .unwrap();
+
+    assert_eq!(bytes, ASSET);
+    assert!(std::fs::read_dir(dir.path().join("downloads")).is_err());
+}
+
+#[tokio::test]
+async fn in_memory_download_enforces_size_limit() {
+    let server = asset_server(None, None).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let err = client
+        .download_asset_bytes("asset-1", None, None, Some("tok-1".to_string()), 4)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("asset_too_large"), "{err}");
+}
+
+#[tokio::test]
+async fn in_memory_download_checks_sha256() {
+    let server =
+        token_server(serde_json::json!({"token": "tok-1", "sha256": sha256_hex(b"other")})).await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(ASSET))
+        .mount(&server)
+        .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let err = client
+        .download_asset_bytes(
+            "asset-1",
+            Some("receipt".to_string()),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            1024,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("asset_checksum_mismatch"), "{err}");
+}
+
+#[tokio::test]
+async fn downloads_several_assets_concurrently_in_order() {
+    let server = MockServer::start().await;
+    let ids = ["asset-a", "asset-b", "asset-c", "asset-d", "asset-e"];
+    for id in ids {
+        let body = format!("contents of {id}").into_bytes();
+        Mock::given(method("POST"))
+            .and(path("/updates/download-token"))
+            .and(wiremock::matchers::body_partial_json(
+                serde_json::json!({"asset_id": id}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "token": format!("tok-{id}"),
+                "sha256": sha256_hex(&body),
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/updates/download/{id}")))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_delay(std::time::Duration::from_millis(50))
+                    .set_body_bytes(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let results = client
+        .download_assets(
+            &ids,
+            2,
+            Some("receipt".to_string()),
+            Some(common::DEVICE_ID.to_string()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), ids.len());
+    for (id, result) in ids.iter().zip(results) {
+        let result = result.unwrap();
+        let expected = format!("contents of {id}").into_bytes();
+        assert_eq!(result.asset_id, *id);
+        assert_eq!(result.sha256, sha256_hex(&expected));
+        assert_eq!(std::fs::read(&result.path).unwrap(), expected);
+    }
+}
+
+#[tokio::test]
+async fn failed_asset_does_not_stop_the_others() {
+    let server = asset_server(None, None).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let results = client
+        .download_assets(
+            &["missing", "asset-1"],
+            4,
+            Some("receipt".to_string()),
+            Some(common::DEVICE_ID.to_string()),
+        )
+        .await
+        .unwrap();
+
+    assert!(results[0].is_err());
+    let downloaded = results[1].as_ref().unwrap();
+    assert_eq!(downloaded.asset_id, "asset-1");
+    assert_eq!(std::fs::read(&downloaded.path).unwrap(), ASSET);
+}
+
+#[tokio::test]
+async fn download_assets_without_receipt_fails_up_front() {
+    let (client, _dir) = common::client(Some("http://127.0.0.1:1".to_string()));
+
+    let err = client
+        .download_assets(&["asset-1"], 2, None, None)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("missing_receipt"), "{err}");
+}
+
+async fn large_asset_server(body: Vec<u8>) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/big"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn rate_limited_download_takes_at_least_size_over_rate() {
+    let body = vec![7u8; 40 * 1024];
+    let server = large_asset_server(body.clone()).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+    let client = client.with_download_rate_limit(Some(64 * 1024));
+
+    let started = std::time::Instant::now();
+    let path = client
+        .download_asset("big", None, None, Some("tok-1".to_string()), None)
+        .await
+        .unwrap();
+    let elapsed = started.elapsed();
+
+    assert_eq!(std::fs::read(path).unwrap(), body);
+    assert!(
+        elapsed >= std::time::Duration::from_millis(600),
+        "{elapsed:?}"
+    );
+    assert!(elapsed < std::time::Duration::from_secs(5), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn rate_limit_applies_to_in_memory_downloads() {
+    let body = vec![7u8; 20 * 1024];
+    let server = large_asset_server(body.clone()).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+    let client = client.with_download_rate_limit(Some(40 * 1024));
+
+    let started = std::time::Instant::now();
+    let bytes = client
+        .download_asset_bytes("big", None, None, Some("tok-1".to_string()), 1 << 20)
+        .await
+        .unwrap();
+
+    assert_eq!(bytes, body);
+    assert!(started.elapsed() >= std::time::Duration::from_millis(500));
+}
+
+/// Hands out `tok-old` and then `tok-new`; only `tok-new` is accepted by the
+/// download route.
+async fn rotating_token_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/updates/download-token"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"token": "tok-old"})),
+        )
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/updates/download-token"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"token": "tok-new"})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .and(query_param("token", "tok-old"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("token expired"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .and(query_param("token", "tok-new"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(ASSET))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn token_requests(server: &MockServer) -> usize {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.url.path() == "/updates/download-token")
+        .count()
+}
+
+#[tokio::test]
+async fn expired_download_token_is_refreshed_once() {
+    let server = rotating_token_server().await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let path = client
+        .download_asset(
+            "asset-1",
+            Some(common::receipt_for(common::DEVICE_ID, None, 0)),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(path).unwrap(), ASSET);
+    assert_eq!(token_requests(&server).await, 2);
+}
+
+#[tokio::test]
+async fn expired_download_token_is_refreshed_for_in_memory_downloads() {
+    let server = rotating_token_server().await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let bytes = client
+        .download_asset_bytes(
+            "asset-1",
+            Some(common::receipt_for(common::DEVICE_ID, None, 0)),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            1024,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(bytes, ASSET);
+    assert_eq!(token_requests(&server).await, 2);
+}
+
+#[tokio::test]
+async fn explicit_download_token_is_not_refreshed() {
+    let server = rotating_token_server().await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let err = client
+        .download_asset(
+            "asset-1",
+            Some(common::receipt_for(common::DEVICE_ID, None, 0)),
+            Some(common::DEVICE_ID.to_string()),
+            Some("tok-old".to_string()),
+            None,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, alure_sdk::AlureError::Http { status: 401, .. }),
+        "{err}"
+    );
+    assert_eq!(token_requests(&server).await, 0);
+}
+
+#[tokio::test]
+async fn download_fails_when_the_fresh_token_is_rejected_too() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/updates/download-token"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"token": "tok-1"})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/updates/download/asset-1"))
+        .respond_with(ResponseTemplate::new(403).set_body_string("forbidden"))
+        .expect(2)
+        .mount(&server)
+        .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let err = client
+        .download_asset(
+            "asset-1",
+            Some(common::receipt_for(common::DEVICE_ID, None, 0)),
+            Some(common::DEVICE_ID.to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, alure_sdk::AlureError::Http { status: 403, .. }),
+        "{err}"
+    );
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use alure_sdk::Endpoints;
+use common::{client, receipt_for, DEVICE_ID};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn custom_endpoints() -> Endpoints {
+    Endpoints {
+        activate: "/v2/license/activate".to_string(),
+        verify: "/v2/license/verify".to_string(),
+        update_latest: "/v2/releases/latest".to_string(),
+        download_token: "/v2/releases/token".to_string(),
+        download: "/v2/files".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn custom_license_endpoints_are_used() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/license/activate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt_for(DEVICE_ID, None, 0),
+            "activation_id": "act-1",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v2/license/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_endpoints(custom_endpoints());
+
+    client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    let result = client.verify_online(None, None, None).await.unwrap();
+    assert!(result.is_active());
+}
+
+#[tokio::test]
+async fn custom_update_endpoints_are_used() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v2/releases/latest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "update_available": true,
+            "latest_version": "2.0.0",
+            "asset": {"asset_id": "asset-1"},
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v2/releases/token"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"token": "tok-1"})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v2/files/asset-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"new build".to_vec()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_endpoints(custom_endpoints());
+
+    let check = client
+        .check_update("proj-1", "stable", Some("1.0.0".to_string()))
+        .await
+        .unwrap();
+    let asset = check.update().unwrap().asset.clone().unwrap();
+    let bytes = client
+        .download_asset_bytes(
+            &asset.asset_id,
+            Some("receipt".to_string()),
+            Some(DEVICE_ID.to_string()),
+            None,
+            1024,
+        )
+        .await
+        .unwrap();
+    assert_eq!(bytes, b"new build");
+}
+
+#[test]
+fn default_endpoints_match_the_server_routes() {
+    let endpoints = Endpoints::default();
+    assert_eq!(endpoints.activate, "/licenses/activate");
+    assert_eq!(endpoints.verify, "/licenses/verify");
+    assert_eq!(endpoints.update_latest, "/updates/latest");
+    assert_eq!(endpoints.download_token, "/updates/download-token");
+    assert_eq!(endpoints.download, "/updates/download");
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use alure_sdk::{AlureClient, AlureError};
+
+const VARS: [&str; 4] = [
+    "ALURE_BASE_URL",
+    "ALURE_PUBLIC_KEY",
+    "ALURE_TIMEOUT",
+    "ALURE_HOME",
+];
+
+fn clear() {
+    for name in VARS {
+        std::env::remove_var(name);
+    }
+}
+
+fn config_error(result: Result<AlureClient, AlureError>) -> String {
+    match result {
+        Err(AlureError::Config(message)) => message,
+        other => panic!("expected a config error, got {other:?}"),
+    }
+}
+
+// One test: the process environment is shared by every test in this binary.
+#[test]
+fn from_env_reads_alure_variables() {
+    clear();
+    let home = tempfile::TempDir::new().unwrap();
+    let storage_dir = home.path().join("alure");
+    std::env::set_var("ALURE_BASE_URL", "https://licenses.example.com/api/v1/");
+    std::env::set_var("ALURE_TIMEOUT", "30");
+    std::env::set_var("ALURE_HOME", &storage_dir);
+    std::env::set_var(
+        "ALURE_PUBLIC_KEY",
+        common::public_key_pem().trim().replace('\n', "\\n"),
+    );
+
+    let client = AlureClient::from_env().unwrap();
+    assert_eq!(client.base_url(), "https://licenses.example.com/api/v1");
+    assert_eq!(client.timeout_seconds(), 30);
+    assert!(storage_dir.is_dir());
+    let receipt = common::receipt_for(common::DEVICE_ID, None, 0);
+    let result = client
+        .verify_offline(Some(receipt), Some(common::DEVICE_ID.to_string()), true)
+        .unwrap();
+    assert!(result.valid, "{result:?}");
+
+    std::env::set_var("ALURE_TIMEOUT", "ten");
+    let message = config_error(AlureClient::from_env());
+    assert!(
+        message.contains("ALURE_TIMEOUT") && message.contains("ten"),
+        "{message}"
+    );
+    std::env::set_var("ALURE_TIMEOUT", "0");
+    assert!(config_error(AlureClient::from_env()).contains("ALURE_TIMEOUT"));
+    std::env::remove_var("ALURE_TIMEOUT");
+
+    std::env::set_var("ALURE_PUBLIC_KEY", "not a key");
+    let message = config_error(AlureClient::from_env());
+    assert!(
+        message.starts_with("ALURE_PUBLIC_KEY: invalid_public_key"),
+        "{message}"
+    );
+
+    clear();
+    std::env::set_var("ALURE_HOME", &storage_dir);
+    std::env::set_var("ALURE_BASE_URL", "");
+    let client = AlureClient::from_env().unwrap();
+    assert_eq!(client.base_url(), "http://localhost:3000/api/v1");
+    assert_eq!(client.timeout_seconds(), 10);
+    clear();
+}
@@ -0,0 +1,174 @@
+use alure_sdk::{AlureError, LicenseErrorCode};
+
+fn code_of(body: &str) -> Option<LicenseErrorCode> {
+    match AlureError::from_response(403, body.to_string(), None) {
+        AlureError::License { code, detail, .. } => {
+            assert_eq!(detail, body);
+            Some(code)
+        }
+        AlureError::Http {
+            status, message, ..
+        } => {
+            assert_eq!(status, 403);
+            assert_eq!(message, body);
+            None
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn maps_known_license_error_codes() {
+    let cases = [
+        ("seat_limit_reached", LicenseErrorCode::SeatLimitReached),
+        ("license_expired", LicenseErrorCode::LicenseExpired),
+        ("license_revoked", LicenseErrorCode::LicenseRevoked),
+        ("invalid_license_key", LicenseErrorCode::InvalidLicenseKey),
+    ];
+    for (code, expected) in cases {
+        let nest_body = format!(r#"{{"statusCode":403,"message":"{code}"}}"#);
+        assert_eq!(code_of(&nest_body), Some(expected));
+        assert_eq!(code_of(&format!(r#"{{"error":"{code}"}}"#)), Some(expected));
+        assert_eq!(code_of(code), Some(expected));
+        assert_eq!(expected.as_str(), code);
+    }
+}
+
+#[test]
+fn leaves_unknown_codes_as_http() {
+    assert_eq!(code_of(r#"{"statusCode":403,"message":"forbidden"}"#), None);
+    assert_eq!(code_of("upstream exploded"), None);
+}
+
+#[test]
+fn exit_codes_are_stable() {
+    use alure_sdk::exit_code;
+    assert_eq!(
+        [
+            exit_code::OK,
+            exit_code::ERROR,
+            exit_code::EXPIRED,
+            exit_code::DEVICE_MISMATCH,
+            exit_code::NETWORK,
+            exit_code::CONFIG,
+            exit_code::INVALID_RECEIPT,
+            exit_code::REVOKED,
+            exit_code::NOT_ACTIVATED,
+            exit_code::LICENSE_REJECTED,
+        ],
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]
+    );
+}
+
+#[test]
+fn errors_map_to_exit_codes() {
+    use alure_sdk::{ReceiptError, StorageError};
+    let license = |code| AlureError::License {
+        code,
+        detail: String::new(),
+        request_id: None,
+    };
+    let http = |status| AlureError::Http {
+        status,
+        message: String::new(),
+        request_id: None,
+    };
+    let receipt = |reason: &str| AlureError::Receipt(ReceiptError(reason.to_string()));
+    let cases = [
+        (license(LicenseErrorCode::LicenseExpired), 2),
+        (license(LicenseErrorCode::LicenseRevoked), 7),
+        (license(LicenseErrorCode::SeatLimitReached), 9),
+        (license(LicenseErrorCode::InvalidLicenseKey), 9),
+        (http(500), 4),
+        (http(503), 4),
+        (http(429), 4),
+        (http(408), 4),
+        (http(400), 1),
+        (http(404), 1),
+        (
+            AlureError::Http {
+                status: 400,
+                message: "missing_receipt".into(),
+                request_id: None,
+            },
+            8,
+        ),
+        (AlureError::EmptyResponse("/licenses/check".into()), 4),
+        (AlureError::Transport("connection reset".into()), 4),
+        (AlureError::Transport("invalid_certificate: bad".into()), 5),
+        (AlureError::Transport("unsupported_operation: /x".into()), 5),
+        (
+            AlureError::UpdateNotFound {
+                project_id: "p".into(),
+                channel: "c".into(),
+                request_id: None,
+            },
+            5,
+        ),
+        (receipt("invalid_signature"), 6),
+        (receipt("invalid_receipt_format"), 6),
+        (receipt("invalid_bundle: payload: bad"), 6),
+        (receipt("device_mismatch"), 3),
+        (receipt("invalid_public_key"), 5),
+        (receipt("public_key_required"), 5),
+        (AlureError::Storage(StorageError("locked".into())), 1),
+        (AlureError::Io(std::io::Error::other("disk")), 1),
+    ];
+    for (err, code) in cases {
+        assert_eq!(err.exit_code(), code, "{err:?}");
+    }
+}
+
+#[tokio::test]
+async fn reqwest_errors_map_to_network_or_config() {
+    let client = reqwest::Client::new();
+    let builder = client.get("not a url").send().await.unwrap_err();
+    assert_eq!(AlureError::Reqwest(builder).exit_code(), 5);
+    let connect = client.get("http://127.0.0.1:1/").send().await.unwrap_err();
+    assert_eq!(AlureError::Reqwest(connect).exit_code(), 4);
+}
+
+#[test]
+fn validation_reasons_map_to_exit_codes() {
+    use alure_sdk::ReceiptValidationResult;
+    let cases = [
+        ("expired", 2),
+        ("device_mismatch", 3),
+        ("revoked", 7),
+        ("missing_receipt", 8),
+        ("invalid_signature", 6),
+        ("not_yet_valid", 6),
+        ("stale", 6),
+        ("invalid_public_key", 5),
+    ];
+    for (reason, code) in cases {
+        assert_eq!(
+            ReceiptValidationResult::invalid(reason).exit_code(),
+            code,
+            "{reason}"
+        );
+    }
+    let mut grace = ReceiptValidationResult::invalid("grace_period");
+    grace.valid = true;
+    assert_eq!(grace.exit_code(), 0);
+    assert!(grace.user_message().contains("grace period"));
+}
+
+#[test]
+fn user_messages_follow_exit_codes() {
+    let seat = AlureError::License {
+        code: LicenseErrorCode::SeatLimitReached,
+        detail: "seat_limit_reached".into(),
+        request_id: None,
+    };
+    assert!(seat.user_message().contains("maximum number of devices"));
+    let network = AlureError::Transport("connection reset".into());
+    assert_eq!(
+        network.user_message(),
+        alure_sdk::exit_code::message(alure_sdk::exit_code::NETWORK)
+    );
+    assert_eq!(
+        alure_sdk::ReceiptValidationResult::invalid("device_mismatch").user_message(),
+        "The license is activated on a different device."
+    );
+}
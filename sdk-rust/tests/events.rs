@@ -0,0 +1,158 @@
+mod common;
+
+use alure_sdk::{EventRecord, FileStorage};
+use common::{client, receipt_for, DEVICE_ID};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn licensing_server(receipt: &str) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt,
+            "activation_id": "act-1",
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "valid": false,
+            "status": "revoked",
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/revoke"))
+        .respond_with(
+            ResponseTemplate::new(500)
+                .insert_header("X-Request-Id", "srv-req-1")
+                .set_body_string("boom"),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+fn read_events(dir: &tempfile::TempDir) -> Vec<EventRecord> {
+    FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .read_events()
+        .unwrap()
+}
+
+fn summary(events: &[EventRecord]) -> Vec<(&str, bool, &str)> {
+    events
+        .iter()
+        .map(|event| (event.action.as_str(), event.success, event.result.as_str()))
+        .collect()
+}
+
+#[tokio::test]
+async fn operations_are_logged_without_secrets() {
+    let receipt = receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 0);
+    let server = licensing_server(&receipt).await;
+    let (client, dir) = client(Some(server.uri()));
+    let client = client.with_event_log(10);
+
+    client
+        .activate("ALR-SECRET-KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    client.verify_online(None, None, None).await.unwrap();
+    assert!(client.deactivate("act-1").await.is_err());
+
+    let events = read_events(&dir);
+    assert_eq!(
+        summary(&events),
+        [
+            ("activate", true, "ok"),
+            ("verify", true, "revoked"),
+            ("deactivate", false, "http_500"),
+        ]
+    );
+    assert_eq!(events[2].request_id.as_deref(), Some("srv-req-1"));
+
+    // The id logged for a success is the one sent to the server.
+    let requests = server.received_requests().await.unwrap();
+    let sent = requests[0].headers.get("X-Request-Id").unwrap();
+    assert_eq!(events[0].request_id.as_deref(), sent.to_str().ok());
+
+    let log = std::fs::read_to_string(dir.path().join("events.jsonl")).unwrap();
+    assert_eq!(log.lines().count(), 3);
+    assert!(!log.contains("ALR-SECRET-KEY"));
+    assert!(!log.contains(&receipt));
+    assert!(!log.contains(DEVICE_ID));
+}
+
+#[tokio::test]
+async fn event_log_is_off_by_default() {
+    let receipt = receipt_for(DEVICE_ID, None, 0);
+    let server = licensing_server(&receipt).await;
+    let (client, dir) = client(Some(server.uri()));
+
+    client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+
+    assert!(!dir.path().join("events.jsonl").exists());
+}
+
+#[tokio::test]
+async fn event_log_keeps_only_the_latest_entries() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/revoke"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+    let client = client.with_event_log(2);
+
+    for _ in 0..3 {
+        client.deactivate("act-1").await.unwrap();
+    }
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: Vec<_> = requests
+        .iter()
+        .map(|request| {
+            request
+                .headers
+                .get("X-Request-Id")
+                .unwrap()
+                .to_str()
+                .unwrap()
+        })
+        .collect();
+    let events = read_events(&dir);
+    let logged: Vec<_> = events
+        .iter()
+        .map(|event| event.request_id.as_deref().unwrap())
+        .collect();
+    assert_eq!(logged, sent[1..]);
+}
+
+#[test]
+fn append_event_trims_the_oldest_lines() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let storage = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+    let event = |result: &str| EventRecord {
+        at: "2026-01-01T00:00:00Z".parse().unwrap(),
+        action: "verify".to_string(),
+        success: true,
+        result: result.to_string(),
+        request_id: None,
+    };
+
+    for result in ["active", "grace", "expired"] {
+        storage.append_event(&event(result), 2).unwrap();
+    }
+
+    assert_eq!(
+        storage.read_events().unwrap(),
+        [event("grace"), event("expired")]
+    );
+}
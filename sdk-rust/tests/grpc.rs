@@ -0,0 +1,210 @@
+#![cfg(feature = "grpc")]
+
+mod common;
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use alure_sdk::grpc::{ActivateReply, ActivateRequest, VerifyReply, VerifyRequest};
+use alure_sdk::{AlureError, GrpcTransport, LicenseErrorCode};
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::transport::{Body, Channel, Server};
+use tonic::{Request, Response, Status};
+use tower_service::Service;
+
+type Seen = Arc<Mutex<Vec<(ActivateRequest, Option<String>)>>>;
+
+/// In-process `alure.licensing.v1.Licensing` server; the license key
+/// "FULL" answers `seat_limit_reached`.
+#[derive(Clone, Default)]
+struct MockLicensing {
+    activations: Seen,
+}
+
+struct Activate(MockLicensing);
+
+impl Service<Request<ActivateRequest>> for Activate {
+    type Response = Response<ActivateReply>;
+    type Error = Status;
+    type Future = std::future::Ready<Result<Self::Response, Status>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<ActivateRequest>) -> Self::Future {
+        let request_id = request
+            .metadata()
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let message = request.into_inner();
+        let full = message.license_key == "FULL";
+        let device_id = message.device_id.clone();
+        self.0
+            .activations
+            .lock()
+            .unwrap()
+            .push((message, request_id));
+        if full {
+            return std::future::ready(Err(Status::resource_exhausted("seat_limit_reached")));
+        }
+        std::future::ready(Ok(Response::new(ActivateReply {
+            receipt: common::receipt_for(&device_id, Some("2099-01-01T00:00:00Z"), 7),
+            activation_id: "act-grpc".to_string(),
+            expires_at: Some("2099-01-01T00:00:00Z".to_string()),
+            grace_period_days: 7,
+            server_time: "2026-01-01T00:00:00Z".to_string(),
+        })))
+    }
+}
+
+struct Verify;
+
+impl Service<Request<VerifyRequest>> for Verify {
+    type Response = Response<VerifyReply>;
+    type Error = Status;
+    type Future = std::future::Ready<Result<Self::Response, Status>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _request: Request<VerifyRequest>) -> Self::Future {
+        std::future::ready(Ok(Response::new(VerifyReply {
+            valid: true,
+            expires_at: Some("2099-01-01T00:00:00Z".to_string()),
+            ..VerifyReply::default()
+        })))
+    }
+}
+
+impl Service<http::Request<Body>> for MockLicensing {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let this = self.clone();
+        Box::pin(async move {
+            let response = match request.uri().path() {
+                alure_sdk::grpc::ACTIVATE_PATH => {
+                    tonic::server::Grpc::new(ProstCodec::default())
+                        .unary(Activate(this), request)
+                        .await
+                }
+                alure_sdk::grpc::VERIFY_PATH => {
+                    tonic::server::Grpc::new(ProstCodec::default())
+                        .unary(Verify, request)
+                        .await
+                }
+                _ => Status::unimplemented("unknown method").to_http(),
+            };
+            Ok(response)
+        })
+    }
+}
+
+impl tonic::server::NamedService for MockLicensing {
+    const NAME: &'static str = alure_sdk::grpc::SERVICE_NAME;
+}
+
+async fn grpc_client(service: MockLicensing) -> (alure_sdk::AlureClient, tempfile::TempDir) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming =
+        tonic::transport::server::TcpIncoming::from_listener(listener, true, None).unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(incoming),
+    );
+    let channel = Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect_lazy();
+    let (client, dir) = common::client(Some("http://licensing.invalid/api/v1".to_string()));
+    (client.with_transport(GrpcTransport::new(channel)), dir)
+}
+
+#[tokio::test]
+async fn activate_round_trips_over_grpc() {
+    let service = MockLicensing::default();
+    let (client, _dir) = grpc_client(service.clone()).await;
+
+    let response = client
+        .activate(
+            "KEY",
+            Some(common::DEVICE_ID.to_string()),
+            Some("1.2.3".to_string()),
+            Some(serde_json::json!({"os": "linux"})),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.activation_id, "act-grpc");
+    assert_eq!(response.expires_at.as_deref(), Some("2099-01-01T00:00:00Z"));
+    assert_eq!(response.grace_period_days, 7);
+    assert_eq!(response.project_id.as_deref(), Some("proj-1"));
+    let stored = client.verify_offline(None, None, true).unwrap();
+    assert!(stored.valid);
+
+    let activations = service.activations.lock().unwrap();
+    let (request, request_id) = &activations[0];
+    assert_eq!(request.license_key, "KEY");
+    assert_eq!(request.device_id, common::DEVICE_ID);
+    assert_eq!(request.app_version.as_deref(), Some("1.2.3"));
+    assert_eq!(
+        request.device_meta_json.as_deref(),
+        Some(r#"{"os":"linux"}"#)
+    );
+    assert!(request_id.is_some());
+}
+
+#[tokio::test]
+async fn grpc_statuses_map_to_license_errors() {
+    let (client, _dir) = grpc_client(MockLicensing::default()).await;
+
+    let err = client
+        .activate("FULL", Some(common::DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        AlureError::License {
+            code: LicenseErrorCode::SeatLimitReached,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn verify_online_over_grpc() {
+    let (client, _dir) = grpc_client(MockLicensing::default()).await;
+    let receipt = common::receipt_for(common::DEVICE_ID, None, 7);
+
+    let result = client
+        .verify_online(Some(receipt), Some(common::DEVICE_ID.to_string()), None)
+        .await
+        .unwrap();
+
+    assert!(result.valid);
+    assert_eq!(result.status, alure_sdk::VerifyStatus::Active);
+}
+
+#[tokio::test]
+async fn unsupported_operations_fail_over_grpc() {
+    let (client, _dir) = grpc_client(MockLicensing::default()).await;
+
+    let err = client.check_license("KEY").await.unwrap_err();
+
+    assert!(matches!(&err, AlureError::Transport(msg) if msg.starts_with("unsupported_operation")));
+}
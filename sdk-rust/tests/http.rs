@@ -0,0 +1,304 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alure_sdk::{AlureError, HttpOptions};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Resolve, Resolving};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn license_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": true})))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn resolve_override_pins_host_to_address() {
+    let server = license_server().await;
+    let addr = *server.address();
+    let (client, _dir) = common::client(Some(format!("http://licensing.invalid:{}", addr.port())));
+    let client = client
+        .with_http_options(HttpOptions::default().resolve("licensing.invalid", addr))
+        .unwrap();
+
+    assert!(client.check_license("KEY").await.unwrap().valid);
+}
+
+struct Loopback(SocketAddr);
+
+impl Resolve for Loopback {
+    fn resolve(&self, _name: Name) -> Resolving {
+        let addr = self.0;
+        Box::pin(async move {
+            let addrs: Box<dyn Iterator<Item = SocketAddr> + Send> =
+                Box::new(std::iter::once(addr));
+            Ok(addrs)
+        })
+    }
+}
+
+#[tokio::test]
+async fn custom_resolver_is_used() {
+    let server = license_server().await;
+    let addr = *server.address();
+    let (client, _dir) = common::client(Some(format!("http://anything.invalid:{}", addr.port())));
+    let client = client
+        .with_http_options(HttpOptions {
+            local_address: Some("127.0.0.1".parse().unwrap()),
+            resolver: Some(Arc::new(Loopback(addr))),
+            ..HttpOptions::default()
+        })
+        .unwrap();
+
+    assert!(client.check_license("KEY").await.unwrap().valid);
+}
+
+#[derive(Debug)]
+struct SlowResolver;
+
+impl Resolve for SlowResolver {
+    fn resolve(&self, _name: Name) -> Resolving {
+        Box::pin(async {
+            tokio::time::sleep(Duration::from_secs(3)).await;
+            let addrs: Box<dyn Iterator<Item = SocketAddr> + Send> =
+                Box::new(std::iter::once(([127, 0, 0, 1], 9).into()));
+            Ok(addrs)
+        })
+    }
+}
+
+#[tokio::test]
+async fn slow_connect_trips_connect_timeout() {
+    let (client, _dir) = common::client(Some("http://slow.invalid".to_string()));
+    let client = client
+        .with_http_options(HttpOptions {
+            resolver: Some(Arc::new(SlowResolver)),
+            connect_timeout: Some(Duration::from_millis(200)),
+            ..HttpOptions::default()
+        })
+        .unwrap();
+
+    let started = Instant::now();
+    let err = client.check_license("KEY").await.unwrap_err();
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    match err {
+        AlureError::Reqwest(err) => assert!(err.is_connect() || err.is_timeout()),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+/// Sends headers and a few bytes of a longer body, then stalls.
+async fn stalling_server() -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\nabc")
+                    .await;
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            });
+        }
+    });
+    addr
+}
+
+#[tokio::test]
+async fn slow_body_trips_read_timeout() {
+    let addr = stalling_server().await;
+    let (client, _dir) = common::client(Some(format!("http://{addr}")));
+    let client = client
+        .with_http_options(HttpOptions {
+            read_timeout: Some(Duration::from_millis(200)),
+            download_timeout: Some(Duration::from_secs(30)),
+            ..HttpOptions::default()
+        })
+        .unwrap();
+
+    let started = Instant::now();
+    let err = client
+        .download_asset("asset-1", None, None, Some("tok".to_string()), None)
+        .await
+        .unwrap_err();
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(err.to_string().contains("read_timeout"), "{err}");
+}
+
+const TEST_CA: &[u8] = include_bytes!("fixtures/test_ca.pem");
+
+/// Answers every request with `{"valid":true}` over TLS, using a server
+/// certificate for `localhost` signed by the test CA.
+async fn tls_server() -> SocketAddr {
+    serve_tls(None).await
+}
+
+/// Like `tls_server`, but drops clients not presenting a certificate signed
+/// by `client_ca`.
+async fn serve_tls(client_ca: Option<&[u8]>) -> SocketAddr {
+    let certs = rustls_pemfile::certs(&mut &include_bytes!("fixtures/tls_server.pem")[..])
+        .unwrap()
+        .into_iter()
+        .map(tokio_rustls::rustls::Certificate)
+        .collect();
+    let key =
+        rustls_pemfile::pkcs8_private_keys(&mut &include_bytes!("fixtures/tls_server.key")[..])
+            .unwrap()
+            .remove(0);
+    let config = tokio_rustls::rustls::ServerConfig::builder().with_safe_defaults();
+    let config = match client_ca {
+        Some(pem) => {
+            let mut roots = tokio_rustls::rustls::RootCertStore::empty();
+            for cert in rustls_pemfile::certs(&mut &pem[..]).unwrap() {
+                roots.add(&tokio_rustls::rustls::Certificate(cert)).unwrap();
+            }
+            config.with_client_cert_verifier(
+                tokio_rustls::rustls::server::AllowAnyAuthenticatedClient::new(roots).boxed(),
+            )
+        }
+        None => config.with_no_client_auth(),
+    };
+    let config = config
+        .with_single_cert(certs, tokio_rustls::rustls::PrivateKey(key))
+        .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(socket).await else {
+                    return;
+                };
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let body = br#"{"valid":true}"#;
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(body).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    addr
+}
+
+#[tokio::test]
+async fn custom_root_certificate_is_trusted() {
+    let addr = tls_server().await;
+    let base_url = format!("https://localhost:{}", addr.port());
+
+    let (untrusted, _dir) = common::client(Some(base_url.clone()));
+    let untrusted = untrusted
+        .with_http_options(HttpOptions::default().resolve("localhost", addr))
+        .unwrap();
+    assert!(untrusted.check_license("KEY").await.is_err());
+
+    let (client, _dir) = common::client(Some(base_url));
+    let client = client
+        .with_http_options(
+            HttpOptions::default()
+                .resolve("localhost", addr)
+                .root_certificate(TEST_CA),
+        )
+        .unwrap();
+    assert!(client.check_license("KEY").await.unwrap().valid);
+}
+
+#[tokio::test]
+async fn malformed_root_certificate_is_rejected() {
+    let (client, _dir) = common::client(None);
+
+    let err = client
+        .with_http_options(HttpOptions::default().root_certificate(
+            b"-----BEGIN CERTIFICATE-----\nnot base64\n-----END CERTIFICATE-----\n".to_vec(),
+        ))
+        .unwrap_err();
+
+    assert!(
+        matches!(&err, AlureError::Transport(msg) if msg.starts_with("invalid_certificate")),
+        "{err}"
+    );
+}
+
+#[cfg(feature = "mtls")]
+mod mtls {
+    use super::*;
+    use alure_sdk::ClientIdentity;
+
+    const CLIENT_CA: &[u8] = include_bytes!("fixtures/tls_client_ca.pem");
+    const IDENTITY_PEM: &[u8] = include_bytes!("fixtures/tls_client_identity.pem");
+    const IDENTITY_P12: &[u8] = include_bytes!("fixtures/tls_client_identity.p12");
+
+    fn options(addr: SocketAddr) -> HttpOptions {
+        HttpOptions::default()
+            .resolve("localhost", addr)
+            .root_certificate(TEST_CA)
+    }
+
+    #[tokio::test]
+    async fn server_requiring_client_certificate_accepts_identity() {
+        let addr = serve_tls(Some(CLIENT_CA)).await;
+        let base_url = format!("https://localhost:{}", addr.port());
+
+        let (anonymous, _dir) = common::client(Some(base_url.clone()));
+        let anonymous = anonymous.with_http_options(options(addr)).unwrap();
+        assert!(anonymous.check_license("KEY").await.is_err());
+
+        let (client, _dir) = common::client(Some(base_url.clone()));
+        let client = client
+            .with_http_options(
+                options(addr).client_identity(ClientIdentity::Pem(IDENTITY_PEM.to_vec())),
+            )
+            .unwrap();
+        assert!(client.check_license("KEY").await.unwrap().valid);
+
+        let (client, _dir) = common::client(Some(base_url));
+        let client = client
+            .with_http_options(options(addr).client_identity(ClientIdentity::Pkcs12 {
+                der: IDENTITY_P12.to_vec(),
+                password: "alure".to_string(),
+            }))
+            .unwrap();
+        assert!(client.check_license("KEY").await.unwrap().valid);
+    }
+
+    #[tokio::test]
+    async fn unusable_client_identity_is_rejected() {
+        let identities = [
+            ClientIdentity::Pem(b"not a pem".to_vec()),
+            ClientIdentity::Pkcs12 {
+                der: IDENTITY_P12.to_vec(),
+                password: "wrong".to_string(),
+            },
+        ];
+        for identity in identities {
+            let (client, _dir) = common::client(None);
+            let err = client
+                .with_http_options(HttpOptions::default().client_identity(identity))
+                .unwrap_err();
+            assert!(
+                matches!(&err, AlureError::Transport(msg) if msg.starts_with("invalid_client_identity")),
+                "{err}"
+            );
+            assert_eq!(err.exit_code(), alure_sdk::exit_code::CONFIG);
+        }
+    }
+}
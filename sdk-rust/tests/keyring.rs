@@ -0,0 +1,26 @@
+#![cfg(feature = "keyring")]
+
+use alure_sdk::{KeyringStorage, ReceiptRecord, ReceiptStorage};
+
+#[test]
+fn round_trips_record_through_mock_keyring() {
+    keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+    let storage = KeyringStorage::new("proj-1").unwrap();
+    assert!(storage.load_receipt().unwrap().is_none());
+
+    let record = ReceiptRecord {
+        receipt: "v1.payload.sig".to_string(),
+        device_id: "device-123".to_string(),
+        activation_id: Some("act-1".to_string()),
+        project_id: Some("proj-1".to_string()),
+        ..Default::default()
+    };
+    storage.save_receipt(&record).unwrap();
+    let loaded = storage.load_receipt().unwrap().unwrap();
+    assert_eq!(loaded.receipt, record.receipt);
+    assert_eq!(loaded.activation_id, record.activation_id);
+
+    storage.delete_receipt().unwrap();
+    assert!(storage.load_receipt().unwrap().is_none());
+    storage.delete_receipt().unwrap();
+}
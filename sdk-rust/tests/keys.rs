@@ -0,0 +1,206 @@
+mod common;
+
+use alure_sdk::{AlureClient, AlureError, KeySource};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use common::{public_key_pem, receipt_for, signing_key, DEVICE_ID};
+use tempfile::TempDir;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn jwks() -> serde_json::Value {
+    serde_json::json!({
+        "keys": [
+            {"kty": "RSA", "n": "ignored", "e": "AQAB"},
+            {
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "kid": "k1",
+                "x": URL_SAFE_NO_PAD.encode(signing_key().verifying_key().as_bytes()),
+            },
+        ]
+    })
+}
+
+fn keyless_client(server: &MockServer, dir: &TempDir) -> AlureClient {
+    AlureClient::new(
+        Some(server.uri()),
+        Some(dir.path().to_path_buf()),
+        None,
+        Some(5),
+    )
+    .unwrap()
+}
+
+fn verifies(client: &AlureClient) -> bool {
+    let receipt = receipt_for(DEVICE_ID, None, 0);
+    client
+        .verify_offline(Some(receipt), Some(DEVICE_ID.to_string()), true)
+        .unwrap()
+        .valid
+}
+
+#[tokio::test]
+async fn revalidates_cached_keys_with_etag() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/licenses/keys"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304).insert_header("ETag", "\"v1\""))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/licenses/keys"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"v1\"")
+                .set_body_json(jwks()),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+
+    let first = keyless_client(&server, &dir);
+    assert!(!verifies(&first));
+    let first = first.fetch_verifying_keys().await.unwrap();
+    assert!(verifies(&first));
+
+    let relaunched = keyless_client(&server, &dir)
+        .fetch_verifying_keys()
+        .await
+        .unwrap();
+    assert!(verifies(&relaunched));
+}
+
+#[tokio::test]
+async fn fresh_cache_skips_the_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/licenses/keys"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Cache-Control", "public, max-age=3600")
+                .set_body_json(jwks()),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+
+    keyless_client(&server, &dir)
+        .fetch_verifying_keys()
+        .await
+        .unwrap();
+    let relaunched = keyless_client(&server, &dir)
+        .fetch_verifying_keys()
+        .await
+        .unwrap();
+
+    assert!(verifies(&relaunched));
+}
+
+#[tokio::test]
+async fn jwks_without_ed25519_keys_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/licenses/keys"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"keys": []})))
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+
+    let err = keyless_client(&server, &dir)
+        .fetch_verifying_keys()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, alure_sdk::AlureError::EmptyResponse(_)));
+}
+
+fn other_key_pem() -> String {
+    use ed25519_dalek::pkcs8::EncodePublicKey;
+    ed25519_dalek::SigningKey::from_bytes(&[9u8; 32])
+        .verifying_key()
+        .to_public_key_pem(Default::default())
+        .unwrap()
+}
+
+fn embedded_key() -> &'static str {
+    Box::leak(public_key_pem().into_boxed_str())
+}
+
+#[tokio::test]
+async fn unreachable_key_url_falls_back_to_embedded_key() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/public_key.pem"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+
+    let client = keyless_client(&server, &dir)
+        .with_key_sources(&[
+            KeySource::Url(format!("{}/public_key.pem", server.uri())),
+            KeySource::Embedded(embedded_key()),
+        ])
+        .await
+        .unwrap();
+    assert!(verifies(&client));
+}
+
+#[tokio::test]
+async fn key_url_wins_and_is_cached_for_offline_starts() {
+    let server = MockServer::start().await;
+    let url = format!("{}/public_key.pem", server.uri());
+    Mock::given(method("GET"))
+        .and(path("/public_key.pem"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(public_key_pem()))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/public_key.pem"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let sources = [KeySource::Url(url), KeySource::Pem(other_key_pem())];
+
+    let online = keyless_client(&server, &dir)
+        .with_key_sources(&sources)
+        .await
+        .unwrap();
+    assert!(verifies(&online));
+    let offline = keyless_client(&server, &dir)
+        .with_key_sources(&sources)
+        .await
+        .unwrap();
+    assert!(verifies(&offline));
+}
+
+#[tokio::test]
+async fn key_sources_fail_when_none_resolves() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+
+    let err = keyless_client(&server, &dir)
+        .with_key_sources(&[KeySource::Url(format!("{}/missing.pem", server.uri()))])
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, AlureError::Receipt(ref err) if err.0 == "public_key_required"),
+        "{err:?}"
+    );
+    let err = keyless_client(&server, &dir)
+        .with_key_sources(&[KeySource::File(dir.path().join("missing.pem"))])
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, AlureError::Receipt(ref err) if err.0.starts_with("public_key_unreadable")),
+        "{err:?}"
+    );
+}
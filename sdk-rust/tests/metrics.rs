@@ -0,0 +1,152 @@
+mod common;
+
+use std::sync::Arc;
+
+use alure_sdk::{AtomicMetrics, ErrorKind, MetricsSink};
+use common::{client, receipt_for, DEVICE_ID};
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn counts_mixed_successes_and_failures() {
+    let server = MockServer::start().await;
+    let receipt = receipt_for(DEVICE_ID, None, 0);
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .and(body_partial_json(
+            serde_json::json!({"license_key": "GOOD"}),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt,
+            "activation_id": "act-1",
+            "grace_period_days": 0,
+            "server_time": "2026-01-01T00:00:00Z",
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .set_body_string(r#"{"statusCode":403,"message":"license_revoked"}"#),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": true})))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(502))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+
+    let metrics = Arc::new(AtomicMetrics::new());
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client.with_metrics(metrics.clone());
+    let device = || Some(DEVICE_ID.to_string());
+
+    client.activate("GOOD", device(), None, None).await.unwrap();
+    client
+        .activate("BAD", device(), None, None)
+        .await
+        .unwrap_err();
+    client.verify_online(None, None, None).await.unwrap();
+    client.verify_online(None, None, None).await.unwrap_err();
+    client.check_license("KEY").await.unwrap_err();
+
+    assert_eq!(metrics.requests(), 5);
+    assert_eq!(metrics.activations_ok(), 1);
+    assert_eq!(metrics.activations_failed(), 1);
+    assert_eq!(metrics.verifications_ok(), 1);
+    assert_eq!(metrics.verifications_failed(), 1);
+    assert_eq!(metrics.license_errors(), 1);
+    assert_eq!(metrics.server_errors(), 1);
+    assert_eq!(metrics.client_errors(), 1);
+    assert_eq!(metrics.network_errors(), 0);
+}
+
+#[tokio::test]
+async fn counts_calls_to_custom_endpoints() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/license/activate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt_for(DEVICE_ID, None, 0),
+            "activation_id": "act-1",
+            "grace_period_days": 0,
+            "server_time": "2026-01-01T00:00:00Z",
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v2/license/verify"))
+        .respond_with(ResponseTemplate::new(502))
+        .mount(&server)
+        .await;
+
+    let metrics = Arc::new(AtomicMetrics::new());
+    let (client, _dir) = client(Some(server.uri()));
+    let client = client
+        .with_metrics(metrics.clone())
+        .with_endpoints(alure_sdk::Endpoints {
+            activate: "/v2/license/activate".to_string(),
+            verify: "/v2/license/verify".to_string(),
+            ..Default::default()
+        });
+
+    client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    client.verify_online(None, None, None).await.unwrap_err();
+
+    assert_eq!(metrics.requests(), 2);
+    assert_eq!(metrics.activations_ok(), 1);
+    assert_eq!(metrics.verifications_failed(), 1);
+}
+
+#[tokio::test]
+async fn counts_network_errors() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let metrics = Arc::new(AtomicMetrics::new());
+    let (client, _dir) = client(Some(format!("http://{addr}")));
+    let client = client.with_metrics(metrics.clone());
+
+    client.check_license("KEY").await.unwrap_err();
+
+    assert_eq!(metrics.requests(), 0);
+    assert_eq!(metrics.network_errors(), 1);
+}
+
+#[test]
+fn on_error_routes_each_kind() {
+    let metrics = AtomicMetrics::new();
+    for kind in [
+        ErrorKind::Network,
+        ErrorKind::Server,
+        ErrorKind::License,
+        ErrorKind::Client,
+    ] {
+        metrics.on_error(kind);
+    }
+    assert_eq!(
+        [
+            metrics.network_errors(),
+            metrics.server_errors(),
+            metrics.license_errors(),
+            metrics.client_errors()
+        ],
+        [1, 1, 1, 1]
+    );
+}
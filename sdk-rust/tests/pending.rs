@@ -0,0 +1,138 @@
+mod common;
+
+use alure_sdk::{ActivationOutcome, AlureClient, FileStorage};
+use common::{public_key_pem, receipt_for, DEVICE_ID};
+use tempfile::TempDir;
+use wiremock::matchers::{body_partial_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn client_at(base_url: String, dir: &TempDir) -> AlureClient {
+    AlureClient::new(
+        Some(base_url),
+        Some(dir.path().to_path_buf()),
+        Some(public_key_pem()),
+        Some(5),
+    )
+    .unwrap()
+}
+
+/// A base URL nothing listens on.
+fn unreachable_url() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+    format!("http://127.0.0.1:{port}")
+}
+
+async fn queue_offline(dir: &TempDir) -> alure_sdk::PendingActivation {
+    let offline = client_at(unreachable_url(), dir);
+    let outcome = offline
+        .activate_or_queue(
+            "KEY",
+            Some(DEVICE_ID.to_string()),
+            Some("1.0.0".to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+    match outcome {
+        ActivationOutcome::Pending(pending) => pending,
+        other => panic!("expected a pending activation, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn unreachable_server_queues_the_activation_without_a_receipt() {
+    let dir = TempDir::new().unwrap();
+    let pending = queue_offline(&dir).await;
+    assert_eq!(pending.license_key, "KEY");
+    assert_eq!(pending.device_id, DEVICE_ID);
+
+    // Queuing again replaces the entry instead of adding a second one.
+    queue_offline(&dir).await;
+    let client = client_at(unreachable_url(), &dir);
+    assert_eq!(client.pending_activations().unwrap().len(), 1);
+    let storage = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+    assert!(storage.load_receipt().unwrap().is_none());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn queued_license_key_is_readable_only_by_the_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    queue_offline(&dir).await;
+    let path = dir.path().join("pending_activations.json");
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(!dir.path().join("pending_activations.json.tmp").exists());
+}
+
+#[tokio::test]
+async fn flush_completes_queued_activation_once_online() {
+    let dir = TempDir::new().unwrap();
+    let pending = queue_offline(&dir).await;
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .and(header("Idempotency-Key", pending.idempotency_key.as_str()))
+        .and(body_partial_json(serde_json::json!({
+            "license_key": "KEY",
+            "device_id": DEVICE_ID,
+            "app_version": "1.0.0",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt_for(DEVICE_ID, None, 0),
+            "activation_id": "act-1",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client_at(server.uri(), &dir);
+
+    let results = client.flush_pending_activations().await.unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].as_ref().unwrap().activation_id, "act-1");
+    assert!(client.pending_activations().unwrap().is_empty());
+    assert!(client.verify_offline(None, None, true).unwrap().valid);
+    assert!(client.flush_pending_activations().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn flush_keeps_the_queue_while_still_offline() {
+    let dir = TempDir::new().unwrap();
+    queue_offline(&dir).await;
+    let client = client_at(unreachable_url(), &dir);
+
+    let results = client.flush_pending_activations().await.unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+    assert_eq!(client.pending_activations().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn rejected_activation_leaves_the_queue() {
+    let dir = TempDir::new().unwrap();
+    queue_offline(&dir).await;
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/activate"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+            "statusCode": 403,
+            "message": "seat_limit_reached",
+        })))
+        .mount(&server)
+        .await;
+    let client = client_at(server.uri(), &dir);
+
+    let results = client.flush_pending_activations().await.unwrap();
+
+    assert!(matches!(
+        results[0],
+        Err(alure_sdk::AlureError::License { .. })
+    ));
+    assert!(client.pending_activations().unwrap().is_empty());
+}
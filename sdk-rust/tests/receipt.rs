@@ -0,0 +1,1031 @@
+mod common;
+
+use alure_sdk::{AlureClient, ReceiptVerifier};
+use common::public_key_pem;
+
+#[test]
+fn try_new_accepts_valid_pem() {
+    let verifier = ReceiptVerifier::try_new(Some(public_key_pem())).unwrap();
+    assert!(verifier.is_configured_for_signatures());
+    assert!(!ReceiptVerifier::new(None).is_configured_for_signatures());
+}
+
+#[test]
+fn try_new_rejects_missing_or_malformed_pem() {
+    let err = ReceiptVerifier::try_new(None).unwrap_err();
+    assert_eq!(err.0, "public_key_required");
+    let err = ReceiptVerifier::try_new(Some("not a pem".to_string())).unwrap_err();
+    assert_eq!(err.0, "invalid_public_key");
+}
+
+#[test]
+fn parsed_key_verifies_repeatedly() {
+    let verifier = ReceiptVerifier::try_new(Some(public_key_pem())).unwrap();
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    for _ in 0..100 {
+        assert!(verifier.verify_signature(&token).unwrap());
+    }
+}
+
+#[test]
+fn malformed_pem_passed_to_new_fails_at_first_use() {
+    let verifier = ReceiptVerifier::new(Some("not a pem".to_string()));
+    assert!(verifier.is_configured_for_signatures());
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    let err = verifier.verify_signature(&token).unwrap_err();
+    assert_eq!(err.0, "invalid_public_key");
+}
+
+#[test]
+fn strict_client_fails_at_construction_on_malformed_pem() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let result = AlureClient::new_strict(
+        None,
+        Some(dir.path().to_path_buf()),
+        Some("-----BEGIN PUBLIC KEY-----\nAAAA\n-----END PUBLIC KEY-----".to_string()),
+        None,
+    );
+    assert!(matches!(
+        result,
+        Err(alure_sdk::AlureError::Receipt(ref err)) if err.0 == "invalid_public_key"
+    ));
+}
+
+fn receipt_with_alg(alg: Option<&str>) -> String {
+    let mut payload = serde_json::json!({
+        "device_id_hash": common::device_hash(common::DEVICE_ID),
+        "expires_at": null,
+        "grace_period_days": 0,
+    });
+    if let Some(alg) = alg {
+        payload["alg"] = serde_json::Value::String(alg.to_string());
+    }
+    common::sign_receipt(&payload)
+}
+
+#[test]
+fn signature_accepts_ed25519_alg_or_implied_v1() {
+    let verifier = ReceiptVerifier::new(Some(public_key_pem()));
+    for alg in [Some("EdDSA"), Some("ed25519"), None] {
+        let token = receipt_with_alg(alg);
+        assert!(verifier.verify_signature(&token).unwrap());
+        let result = verifier.validate_offline(&token, common::DEVICE_ID, true);
+        assert!(result.valid, "alg {alg:?}: {:?}", result.reason);
+    }
+}
+
+#[test]
+fn signature_rejects_none_and_foreign_alg() {
+    let verifier = ReceiptVerifier::new(Some(public_key_pem()));
+    for alg in ["none", "RS256"] {
+        let token = receipt_with_alg(Some(alg));
+        assert_eq!(verifier.verify_signature(&token).unwrap_err().0, "unsupported_alg");
+        let result = verifier.validate_offline(&token, common::DEVICE_ID, true);
+        assert!(!result.valid);
+        assert_eq!(result.reason.as_deref(), Some("unsupported_alg"));
+    }
+}
+
+#[test]
+fn raw_and_pem_keys_verify_the_same_receipt() {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    let raw = common::signing_key().verifying_key().to_bytes();
+    let hex: String = raw.iter().map(|byte| format!("{byte:02x}")).collect();
+    let verifiers = [
+        ReceiptVerifier::new(Some(public_key_pem())),
+        ReceiptVerifier::from_raw_key(&raw).unwrap(),
+        ReceiptVerifier::from_hex_key(&hex).unwrap(),
+        ReceiptVerifier::from_base64_key(&STANDARD.encode(raw)).unwrap(),
+    ];
+    for verifier in verifiers {
+        assert!(verifier.verify_signature(&token).unwrap());
+    }
+}
+
+#[test]
+fn raw_key_rejects_wrong_length() {
+    assert_eq!(
+        ReceiptVerifier::from_raw_key(&[1u8; 31]).unwrap_err().0,
+        "invalid_public_key"
+    );
+    assert!(ReceiptVerifier::from_hex_key("zz").is_err());
+}
+
+#[test]
+fn validates_receipt_bytes() {
+    let verifier = ReceiptVerifier::new(Some(public_key_pem()));
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    let result = verifier.validate_offline_bytes(token.as_bytes(), common::DEVICE_ID, true);
+    assert!(result.valid);
+    let payload = verifier.parse_bytes(token.as_bytes()).unwrap();
+    assert_eq!(payload["plan"], "pro");
+}
+
+#[test]
+fn rejects_non_utf8_receipt_bytes() {
+    let verifier = ReceiptVerifier::new(None);
+    let bytes = [b'v', b'1', b'.', 0xff, 0xfe, b'.', b'x'];
+    let result = verifier.validate_offline_bytes(&bytes, common::DEVICE_ID, false);
+    assert!(!result.valid);
+    assert_eq!(result.reason.as_deref(), Some("invalid_receipt_format"));
+    assert_eq!(
+        verifier.parse_bytes(&bytes).unwrap_err().0,
+        "invalid_receipt_format"
+    );
+}
+
+#[test]
+fn free_verify_offline_matches_client_path() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let client = AlureClient::new(
+        None,
+        Some(dir.path().to_path_buf()),
+        Some(public_key_pem()),
+        None,
+    )
+    .unwrap();
+    let tokens = [
+        common::receipt_for(common::DEVICE_ID, None, 0),
+        common::receipt_for(common::DEVICE_ID, Some("2020-01-01T00:00:00Z"), 0),
+        common::receipt_for("other-device", None, 0),
+        "garbage".to_string(),
+    ];
+    for token in tokens {
+        for verify_signature in [false, true] {
+            let free = alure_sdk::verify_offline(
+                &token,
+                common::DEVICE_ID,
+                Some(public_key_pem()),
+                verify_signature,
+            );
+            let via_client = client
+                .verify_offline(
+                    Some(token.clone()),
+                    Some(common::DEVICE_ID.to_string()),
+                    verify_signature,
+                )
+                .unwrap();
+            assert_eq!(free.valid, via_client.valid);
+            assert_eq!(free.reason, via_client.reason);
+            assert_eq!(free.expires_at, via_client.expires_at);
+        }
+    }
+}
+
+#[test]
+fn fingerprint_is_stable_and_sensitive_to_changes() {
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    let fingerprint = ReceiptVerifier::fingerprint(&token);
+    assert_eq!(fingerprint.len(), 12);
+    assert!(fingerprint.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(fingerprint, ReceiptVerifier::fingerprint(&token));
+    assert_ne!(
+        fingerprint,
+        ReceiptVerifier::fingerprint(&format!("{token}x"))
+    );
+    let record = alure_sdk::ReceiptRecord {
+        receipt: token,
+        device_id: common::DEVICE_ID.to_string(),
+        activation_id: None,
+        project_id: None,
+        ..Default::default()
+    };
+    assert_eq!(record.fingerprint(), fingerprint);
+}
+
+#[test]
+fn default_and_salted_device_hashers() {
+    use alure_sdk::SaltedSha256DeviceHasher;
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    let verifier = ReceiptVerifier::new(None);
+    assert_eq!(
+        verifier.hash_device_id(common::DEVICE_ID),
+        common::device_hash(common::DEVICE_ID)
+    );
+    assert!(
+        verifier
+            .validate_offline(&token, common::DEVICE_ID, false)
+            .valid
+    );
+
+    // sha256("pepper" + "device-123"), as computed by a salting server.
+    let salted_hash = "8b860fc6e23ac97a09f645963502f5de40a52b016a0302bdbbf484da5411c8ba";
+    let salted = ReceiptVerifier::new(None).with_device_hasher(SaltedSha256DeviceHasher {
+        salt: "pepper".to_string(),
+    });
+    assert_eq!(salted.hash_device_id(common::DEVICE_ID), salted_hash);
+    let salted_token = common::sign_receipt(&serde_json::json!({
+        "device_id_hash": salted_hash,
+        "expires_at": null,
+    }));
+    assert!(
+        salted
+            .validate_offline(&salted_token, common::DEVICE_ID, false)
+            .valid
+    );
+    let result = verifier.validate_offline(&salted_token, common::DEVICE_ID, false);
+    assert_eq!(result.reason.as_deref(), Some("device_mismatch"));
+}
+
+#[test]
+fn pre_hashed_device_id_is_compared_as_is() {
+    use alure_sdk::PreHashedDeviceHasher;
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    let hashed = common::device_hash(common::DEVICE_ID);
+    let verifier = ReceiptVerifier::new(None).with_device_hasher(PreHashedDeviceHasher);
+
+    assert!(verifier.validate_offline(&token, &hashed, false).valid);
+    let upper = hashed.to_ascii_uppercase();
+    assert!(verifier.validate_offline(&token, &upper, false).valid);
+    let result = verifier.validate_offline(&token, common::DEVICE_ID, false);
+    assert_eq!(result.reason.as_deref(), Some("device_mismatch"));
+}
+
+#[test]
+fn validate_public_key_reports_descriptive_errors() {
+    ReceiptVerifier::validate_public_key(&public_key_pem()).unwrap();
+    AlureClient::validate_public_key(&public_key_pem()).unwrap();
+
+    let pem = public_key_pem();
+    let truncated = format!("{}\n-----END PUBLIC KEY-----", &pem[..40]);
+    let err = ReceiptVerifier::validate_public_key(&truncated).unwrap_err();
+    assert!(
+        err.0.starts_with("invalid_public_key: malformed"),
+        "{}",
+        err.0
+    );
+
+    let p256 = "-----BEGIN PUBLIC KEY-----\n\
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEu8s6rmhXn3RORaUjfpAc/iGM4J39\n\
+6qs/pk/CXU8yrlKv96mPCCIRVLO5St29dPlwebaR7QtJLAkutEu2pdVGzg==\n\
+-----END PUBLIC KEY-----";
+    let err = ReceiptVerifier::validate_public_key(p256).unwrap_err();
+    assert_eq!(
+        err.0,
+        "invalid_public_key: expected an Ed25519 key, found algorithm 1.2.840.10045.2.1"
+    );
+}
+
+#[test]
+fn grace_until_is_expiry_plus_grace_days() {
+    let verifier = ReceiptVerifier::new(None);
+    let token = common::receipt_for(common::DEVICE_ID, Some("2026-03-10T00:00:00Z"), 5);
+    for now in [
+        "2026-03-01T00:00:00Z",
+        "2026-03-12T00:00:00Z",
+        "2026-03-20T00:00:00Z",
+    ] {
+        let result =
+            verifier.validate_offline_at(&token, common::DEVICE_ID, now.parse().unwrap(), false);
+        assert_eq!(result.grace_until.as_deref(), Some("2026-03-15T00:00:00Z"));
+    }
+
+    let perpetual = common::receipt_for(common::DEVICE_ID, None, 5);
+    let result = verifier.validate_offline(&perpetual, common::DEVICE_ID, false);
+    assert!(result.valid);
+    assert_eq!(result.grace_until, None);
+}
+
+#[test]
+fn access_predicates_across_states() {
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    let token = common::receipt_for(common::DEVICE_ID, Some("2026-03-10T00:00:00Z"), 5);
+    let at = |now: &str| {
+        verifier.validate_offline_at(&token, common::DEVICE_ID, now.parse().unwrap(), true)
+    };
+
+    let active = at("2026-03-01T00:00:00Z");
+    assert!(active.is_active() && !active.is_in_grace() && active.allows_access());
+
+    let grace = at("2026-03-12T00:00:00Z");
+    assert!(!grace.is_active() && grace.is_in_grace() && grace.allows_access());
+
+    let expired = at("2026-03-20T00:00:00Z");
+    assert!(!expired.is_active() && !expired.is_in_grace() && !expired.allows_access());
+
+    let invalid = verifier.validate_offline(&token, "other-device", true);
+    assert!(!invalid.is_active() && !invalid.is_in_grace() && !invalid.allows_access());
+}
+
+const EMBEDDED_KEY: &str = include_str!("fixtures/public_key.pem");
+
+#[test]
+fn embedded_key_parses_and_verifies_receipts() {
+    let verifier = ReceiptVerifier::from_embedded_key(EMBEDDED_KEY).unwrap();
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    assert!(verifier.verify_signature(&token).unwrap());
+    assert!(
+        verifier
+            .validate_offline(&token, common::DEVICE_ID, true)
+            .valid
+    );
+    assert!(ReceiptVerifier::from_embedded_key("not a key").is_err());
+}
+
+#[test]
+fn verify_raw_checks_arbitrary_messages() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    use ed25519_dalek::Signer;
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    let message = br#"{"beta_features":true}"#;
+    let signature = common::signing_key().sign(message).to_bytes();
+
+    assert!(verifier.verify_raw(message, &signature).unwrap());
+    assert!(!verifier
+        .verify_raw(br#"{"beta_features":false}"#, &signature)
+        .unwrap());
+    assert!(verifier
+        .verify_raw_base64(message, &URL_SAFE_NO_PAD.encode(signature))
+        .unwrap());
+    assert_eq!(
+        verifier
+            .verify_raw(message, &signature[..10])
+            .unwrap_err()
+            .0,
+        "invalid_signature"
+    );
+    assert_eq!(
+        ReceiptVerifier::new(None)
+            .verify_raw(message, &signature)
+            .unwrap_err()
+            .0,
+        "public_key_required"
+    );
+}
+
+#[test]
+fn key_dir_loads_valid_keys_and_reports_junk() {
+    use ed25519_dalek::pkcs8::EncodePublicKey;
+    let dir = tempfile::TempDir::new().unwrap();
+    let rotated = ed25519_dalek::SigningKey::from_bytes(&[9u8; 32])
+        .verifying_key()
+        .to_public_key_pem(Default::default())
+        .unwrap();
+    std::fs::write(dir.path().join("current.pem"), common::public_key_pem()).unwrap();
+    std::fs::write(dir.path().join("previous.pem"), rotated).unwrap();
+    std::fs::write(dir.path().join("junk.pem"), "not a key").unwrap();
+    std::fs::write(dir.path().join("README.txt"), "ignored").unwrap();
+
+    let (verifier, warnings) = ReceiptVerifier::from_key_dir(dir.path()).unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("junk.pem"));
+    let token = common::receipt_for(common::DEVICE_ID, None, 0);
+    assert!(verifier.verify_signature(&token).unwrap());
+    assert!(
+        verifier
+            .validate_offline(&token, common::DEVICE_ID, true)
+            .valid
+    );
+}
+
+#[test]
+fn key_dir_without_usable_keys_fails() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("junk.pem"), "not a key").unwrap();
+    let err = ReceiptVerifier::from_key_dir(dir.path()).unwrap_err();
+    assert!(err.0.starts_with("no_public_keys"));
+}
+
+fn jwt(claims: serde_json::Value) -> String {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    use ed25519_dalek::Signer;
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"EdDSA","typ":"JWT"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+    let signing_input = format!("{header}.{payload}");
+    let signature = common::signing_key().sign(signing_input.as_bytes());
+    format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    )
+}
+
+#[test]
+fn jwt_and_v1_receipts_both_validate_offline() {
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    let now = "2026-03-01T00:00:00Z".parse().unwrap();
+    let jwt = jwt(serde_json::json!({
+        "sub": common::device_hash(common::DEVICE_ID),
+        "project_id": "proj-1",
+        "nbf": 1767225600, // 2026-01-01
+        "exp": 1775001600, // 2026-04-01
+    }));
+    let v1 = common::receipt_for(common::DEVICE_ID, Some("2026-04-01T00:00:00Z"), 0);
+
+    for token in [&jwt, &v1] {
+        let result = verifier.validate_offline_at(token, common::DEVICE_ID, now, true);
+        assert!(result.is_active(), "{result:?}");
+        assert_eq!(result.expires_at.as_deref(), Some("2026-04-01T00:00:00Z"));
+    }
+    assert_eq!(verifier.parse(&jwt).unwrap()["project_id"], "proj-1");
+}
+
+#[test]
+fn jwt_claims_are_enforced() {
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    let token = jwt(serde_json::json!({
+        "sub": common::device_hash(common::DEVICE_ID),
+        "nbf": 1767225600, // 2026-01-01
+        "exp": 1775001600, // 2026-04-01
+    }));
+    let at = |now: &str| {
+        verifier
+            .validate_offline_at(&token, common::DEVICE_ID, now.parse().unwrap(), true)
+            .reason
+    };
+    assert_eq!(at("2025-12-01T00:00:00Z").as_deref(), Some("not_yet_valid"));
+    assert_eq!(at("2026-05-01T00:00:00Z").as_deref(), Some("expired"));
+
+    let mut tampered = token.clone();
+    tampered.replace_range(token.len() - 4.., "AAAA");
+    assert_eq!(
+        verifier
+            .validate_offline(&tampered, common::DEVICE_ID, true)
+            .reason
+            .as_deref(),
+        Some("invalid_signature")
+    );
+}
+
+#[test]
+fn jwt_with_foreign_alg_is_rejected() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(b"{}");
+    let token = format!("{header}.{payload}.c2ln");
+    assert_eq!(
+        verifier.verify_signature(&token).unwrap_err().0,
+        "unsupported_alg"
+    );
+}
+
+#[test]
+fn expires_within_window() {
+    use chrono::Duration;
+    let verifier = ReceiptVerifier::new(None);
+    let token = common::receipt_for(common::DEVICE_ID, Some("2026-03-31T00:00:00Z"), 7);
+    let check = |now: &str, window: Duration| {
+        let now = now.parse().unwrap();
+        verifier
+            .validate_offline_at(&token, common::DEVICE_ID, now, false)
+            .expires_within(window, now)
+    };
+
+    assert!(!check("2026-03-01T00:00:00Z", Duration::days(14)));
+    assert!(check("2026-03-01T00:00:00Z", Duration::days(30)));
+    assert!(check("2026-03-20T00:00:00Z", Duration::days(14)));
+    assert!(!check("2026-03-20T00:00:00Z", Duration::days(7)));
+    assert!(check("2026-03-30T23:00:00Z", Duration::hours(1)));
+    // Past expiry the receipt is in grace, not "expiring soon".
+    assert!(!check("2026-04-02T00:00:00Z", Duration::days(14)));
+
+    let perpetual = common::receipt_for(common::DEVICE_ID, None, 0);
+    let now = "2026-03-01T00:00:00Z".parse().unwrap();
+    assert!(!verifier
+        .validate_offline_at(&perpetual, common::DEVICE_ID, now, false)
+        .expires_within(Duration::days(36500), now));
+}
+
+fn sign_with(
+    version: &str,
+    codec: &dyn alure_sdk::TokenCodec,
+    payload: serde_json::Value,
+) -> String {
+    use ed25519_dalek::Signer;
+    let payload = codec.encode(&serde_json::to_vec(&payload).unwrap());
+    let signature = common::signing_key().sign(payload.as_bytes());
+    format!(
+        "{version}.{payload}.{}",
+        codec.encode(&signature.to_bytes())
+    )
+}
+
+#[test]
+fn receipt_versions_route_to_their_codec() {
+    use alure_sdk::{Base64StdCodec, Base64UrlCodec};
+    // `>>>` encodes to `Pj4+` in standard base64, which URL-safe rejects.
+    let claims = serde_json::json!({
+        "device_id_hash": common::device_hash(common::DEVICE_ID),
+        "note": ">>>",
+    });
+    let v1 = sign_with("v1", &Base64UrlCodec, claims.clone());
+    let v2 = sign_with("v2", &Base64StdCodec, claims.clone());
+    let v1_with_std = sign_with("v1", &Base64StdCodec, claims);
+
+    let default = ReceiptVerifier::new(Some(common::public_key_pem()));
+    assert!(default.validate_offline(&v1, common::DEVICE_ID, true).valid);
+    assert_eq!(
+        default
+            .validate_offline(&v2, common::DEVICE_ID, true)
+            .reason
+            .as_deref(),
+        Some("invalid_receipt_format")
+    );
+    assert_eq!(
+        default
+            .validate_offline(&v1_with_std, common::DEVICE_ID, true)
+            .reason
+            .as_deref(),
+        Some("invalid_receipt_payload")
+    );
+
+    let with_v2 = default.with_codec("v2", Base64StdCodec);
+    assert!(with_v2.validate_offline(&v2, common::DEVICE_ID, true).valid);
+    assert!(with_v2.validate_offline(&v1, common::DEVICE_ID, true).valid);
+    assert_eq!(with_v2.parse(&v2).unwrap()["note"], ">>>");
+}
+
+#[test]
+fn verbose_trace_records_the_failing_step() {
+    use alure_sdk::CheckOutcome;
+    let clock = alure_sdk::TestClock::new("2026-06-01T00:00:00Z".parse().unwrap());
+    let verifier = ReceiptVerifier::new(Some(public_key_pem())).with_clock(clock);
+    let signed = |payload: serde_json::Value| common::sign_receipt(&payload);
+    let device_hash = common::device_hash(common::DEVICE_ID);
+    let mut forged = common::receipt_for(common::DEVICE_ID, None, 0);
+    forged.push('x');
+
+    let cases = [
+        ("garbage".to_string(), "format", "invalid_receipt_format"),
+        (forged, "signature", "invalid_signature"),
+        (
+            signed(serde_json::json!({
+                "device_id_hash": device_hash,
+                "not_before": "2027-01-01T00:00:00Z",
+            })),
+            "not_before",
+            "not_yet_valid",
+        ),
+        (
+            common::receipt_for("other-device", None, 0),
+            "device",
+            "device_mismatch",
+        ),
+        (
+            common::receipt_for(common::DEVICE_ID, Some("2026-01-01T00:00:00Z"), 7),
+            "grace",
+            "expired",
+        ),
+    ];
+    for (receipt, step, reason) in cases {
+        let (result, trace) = verifier.validate_offline_verbose(&receipt, common::DEVICE_ID, true);
+        assert!(!result.valid);
+        assert_eq!(result.reason.as_deref(), Some(reason));
+        let last = trace.checks.last().unwrap();
+        assert_eq!(
+            (last.name.as_str(), last.outcome),
+            (step, CheckOutcome::Failed)
+        );
+        assert!(trace.check("revocation").is_none());
+    }
+}
+
+#[test]
+fn verbose_trace_reports_compared_values() {
+    use alure_sdk::CheckOutcome;
+    let now: chrono::DateTime<chrono::Utc> = "2026-06-01T00:00:00Z".parse().unwrap();
+    let verifier = ReceiptVerifier::new(None).with_clock(alure_sdk::TestClock::new(now));
+    let receipt = common::receipt_for(common::DEVICE_ID, Some("2026-05-30T00:00:00Z"), 7);
+
+    let (result, trace) = verifier.validate_offline_verbose(&receipt, common::DEVICE_ID, false);
+
+    assert_eq!(
+        result,
+        verifier.validate_offline(&receipt, common::DEVICE_ID, false)
+    );
+    assert!(result.is_in_grace());
+    let names: Vec<_> = trace
+        .checks
+        .iter()
+        .map(|check| check.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "format",
+            "signature",
+            "not_before",
+            "device",
+            "max_age",
+            "expiry",
+            "grace",
+            "revocation"
+        ]
+    );
+    assert_eq!(
+        trace.check("signature").unwrap().outcome,
+        CheckOutcome::Skipped
+    );
+    assert_eq!(
+        trace.check("revocation").unwrap().outcome,
+        CheckOutcome::Skipped
+    );
+    assert_eq!(trace.first_failure().unwrap().name, "expiry");
+    let prefix = &common::device_hash(common::DEVICE_ID)[..8];
+    let device = trace.check("device").unwrap().detail.clone().unwrap();
+    assert_eq!(device, format!("expected={prefix}, receipt={prefix}"));
+    let expiry = trace.check("expiry").unwrap().detail.clone().unwrap();
+    assert!(
+        expiry.contains("2026-05-30") && expiry.contains("2026-06-01"),
+        "{expiry}"
+    );
+    assert_eq!(trace.now, now);
+}
+
+#[test]
+fn max_receipt_age_rejects_stale_receipts() {
+    let receipt = |issued_at: Option<&str>| {
+        common::sign_receipt(&serde_json::json!({
+            "device_id_hash": common::device_hash(common::DEVICE_ID),
+            "issued_at": issued_at,
+            "expires_at": null,
+        }))
+    };
+    let clock = alure_sdk::TestClock::new("2026-06-01T00:00:00Z".parse().unwrap());
+    let verifier = ReceiptVerifier::new(Some(public_key_pem()))
+        .with_clock(clock.clone())
+        .with_max_receipt_age(Some(chrono::Duration::days(30)));
+    let validate = |token: &str| verifier.validate_offline(token, common::DEVICE_ID, true);
+
+    assert!(validate(&receipt(Some("2026-05-20T00:00:00Z"))).is_active());
+    let old = validate(&receipt(Some("2025-01-01T00:00:00Z")));
+    assert!(!old.valid);
+    assert_eq!(old.reason.as_deref(), Some("stale"));
+    assert_eq!(validate(&receipt(None)).reason.as_deref(), Some("stale"));
+
+    let unrestricted = ReceiptVerifier::new(Some(public_key_pem())).with_clock(clock);
+    let old = receipt(Some("2020-01-01T00:00:00Z"));
+    assert!(unrestricted
+        .validate_offline(&old, common::DEVICE_ID, true)
+        .is_active());
+}
+
+#[test]
+fn client_applies_max_receipt_age() {
+    let (client, _dir) = common::client(None);
+    let client = client.with_max_receipt_age(Some(chrono::Duration::days(1)));
+    let receipt = common::receipt_for(common::DEVICE_ID, None, 0);
+
+    let result = client
+        .verify_offline(Some(receipt), Some(common::DEVICE_ID.to_string()), true)
+        .unwrap();
+
+    assert_eq!(result.reason.as_deref(), Some("stale"));
+}
+
+#[test]
+fn plan_reads_configurable_claim() {
+    let verifier = ReceiptVerifier::new(None);
+    let with_plan = common::receipt_for(common::DEVICE_ID, None, 0);
+    let without_plan = common::sign_receipt(&serde_json::json!({"device_id_hash": "x"}));
+    let with_tier = common::sign_receipt(&serde_json::json!({"tier": "enterprise"}));
+
+    assert_eq!(verifier.plan(&with_plan).unwrap().as_deref(), Some("pro"));
+    assert_eq!(verifier.plan(&without_plan).unwrap(), None);
+    assert_eq!(verifier.plan(&with_tier).unwrap(), None);
+    let tiered = ReceiptVerifier::new(None).with_plan_claim("tier");
+    assert_eq!(
+        tiered.plan(&with_tier).unwrap().as_deref(),
+        Some("enterprise")
+    );
+    assert_eq!(
+        verifier.plan("garbage").unwrap_err().0,
+        "invalid_receipt_format"
+    );
+}
+
+#[test]
+fn current_plan_uses_stored_receipt() {
+    let (client, dir) = common::client(None);
+    assert_eq!(client.current_plan().unwrap(), None);
+    alure_sdk::FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .save_receipt(&alure_sdk::ReceiptRecord {
+            receipt: common::sign_receipt(&serde_json::json!({"plan": "free", "tier": "t2"})),
+            device_id: common::DEVICE_ID.to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(client.current_plan().unwrap().as_deref(), Some("free"));
+    let client = client.with_plan_claim("tier");
+    assert_eq!(client.current_plan().unwrap().as_deref(), Some("t2"));
+}
+
+/// Base64 of the claims JSON XORed with a one-byte key: enough to prove the
+/// hook runs, not a real cipher.
+#[derive(Debug)]
+struct XorDecryptor(u8);
+
+impl XorDecryptor {
+    fn seal(&self, claims: &serde_json::Value) -> String {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        let bytes: Vec<u8> = serde_json::to_vec(claims)
+            .unwrap()
+            .into_iter()
+            .map(|byte| byte ^ self.0)
+            .collect();
+        STANDARD.encode(bytes)
+    }
+}
+
+impl alure_sdk::ClaimDecryptor for XorDecryptor {
+    fn decrypt(
+        &self,
+        enc: &serde_json::Value,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, alure_sdk::ReceiptError> {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        let failed = || alure_sdk::ReceiptError("claim_decryption_failed".to_string());
+        let bytes: Vec<u8> = STANDARD
+            .decode(enc.as_str().ok_or_else(failed)?)
+            .map_err(|_| failed())?
+            .into_iter()
+            .map(|byte| byte ^ self.0)
+            .collect();
+        serde_json::from_slice(&bytes).map_err(|_| failed())
+    }
+}
+
+fn encrypted_claims_receipt() -> String {
+    common::sign_receipt(&serde_json::json!({
+        "device_id_hash": common::device_hash(common::DEVICE_ID),
+        "plan": "pro",
+        "expires_at": null,
+        "enc": XorDecryptor(0x5a).seal(&serde_json::json!({
+            "customer_id": "cust-42",
+            "plan": "free",
+        })),
+    }))
+}
+
+#[test]
+fn decryptor_reveals_encrypted_claims() {
+    let token = encrypted_claims_receipt();
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()))
+        .with_claim_decryptor(XorDecryptor(0x5a));
+
+    let claims = verifier.parse(&token).unwrap();
+    assert_eq!(claims["customer_id"], "cust-42");
+    assert_eq!(claims["plan"], "pro", "clear claims win");
+    assert!(claims.get("enc").is_none());
+    assert!(
+        verifier
+            .validate_offline(&token, common::DEVICE_ID, true)
+            .valid
+    );
+}
+
+#[test]
+fn encrypted_section_is_untouched_without_decryptor() {
+    let token = encrypted_claims_receipt();
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+
+    let claims = verifier.parse(&token).unwrap();
+    assert!(claims["enc"].is_string());
+    assert!(claims.get("customer_id").is_none());
+    assert!(
+        verifier
+            .validate_offline(&token, common::DEVICE_ID, true)
+            .valid
+    );
+}
+
+#[test]
+fn failing_decryptor_invalidates_the_receipt() {
+    let token = common::sign_receipt(&serde_json::json!({
+        "device_id_hash": common::device_hash(common::DEVICE_ID),
+        "enc": {"unexpected": "shape"},
+    }));
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()))
+        .with_claim_decryptor(XorDecryptor(0x5a));
+
+    let result = verifier.validate_offline(&token, common::DEVICE_ID, true);
+    assert_eq!(result.reason.as_deref(), Some("claim_decryption_failed"));
+}
+
+#[test]
+fn policy_can_skip_expiry_but_enforce_device() {
+    use alure_sdk::TrustPolicy;
+    let clock = alure_sdk::TestClock::new("2026-06-01T00:00:00Z".parse().unwrap());
+    let verifier = ReceiptVerifier::new(Some(public_key_pem())).with_clock(clock);
+    let expired = common::receipt_for(common::DEVICE_ID, Some("2026-01-10T00:00:00Z"), 0);
+    let policy = TrustPolicy {
+        expiry: false,
+        ..TrustPolicy::default()
+    };
+
+    let result = verifier.validate_offline_with_policy(&expired, common::DEVICE_ID, policy);
+    assert!(result.is_active(), "{result:?}");
+    let result = verifier.validate_offline_with_policy(&expired, "other-device", policy);
+    assert_eq!(result.reason.as_deref(), Some("device_mismatch"));
+    let result = verifier.validate_offline(&expired, common::DEVICE_ID, true);
+    assert_eq!(result.reason.as_deref(), Some("expired"));
+}
+
+#[test]
+fn policy_can_skip_device_but_enforce_expiry() {
+    use alure_sdk::TrustPolicy;
+    let clock = alure_sdk::TestClock::new("2026-01-05T00:00:00Z".parse().unwrap());
+    let verifier = ReceiptVerifier::new(Some(public_key_pem())).with_clock(clock.clone());
+    let token = common::receipt_for(common::DEVICE_ID, Some("2026-01-10T00:00:00Z"), 0);
+    let policy = TrustPolicy {
+        device_match: false,
+        ..TrustPolicy::default()
+    };
+
+    let result = verifier.validate_offline_with_policy(&token, "other-device", policy);
+    assert!(result.valid, "{result:?}");
+    clock.set("2026-02-01T00:00:00Z".parse().unwrap());
+    let result = verifier.validate_offline_with_policy(&token, "other-device", policy);
+    assert_eq!(result.reason.as_deref(), Some("expired"));
+}
+
+#[test]
+fn policy_revocation_check_reads_revoked_claim() {
+    use alure_sdk::TrustPolicy;
+    let verifier = ReceiptVerifier::new(Some(public_key_pem()));
+    let token = common::sign_receipt(&serde_json::json!({
+        "device_id_hash": common::device_hash(common::DEVICE_ID),
+        "expires_at": null,
+        "revoked": true,
+    }));
+    let policy = TrustPolicy {
+        revocation: true,
+        ..TrustPolicy::default()
+    };
+
+    assert!(
+        verifier
+            .validate_offline(&token, common::DEVICE_ID, true)
+            .valid
+    );
+    let result = verifier.validate_offline_with_policy(&token, common::DEVICE_ID, policy);
+    assert_eq!(result.reason.as_deref(), Some("revoked"));
+}
+
+#[test]
+fn inspect_decodes_a_well_formed_receipt() {
+    let token = common::receipt_for(common::DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7);
+    // No key needed: nothing is verified.
+    let report = ReceiptVerifier::new(None).inspect(&token).unwrap();
+
+    assert_eq!(report["format"], "v1");
+    assert_eq!(report["header"], serde_json::json!({"version": "v1"}));
+    assert_eq!(report["claims"]["plan"], "pro");
+    assert_eq!(report["claims"]["expires_at"], "2099-01-01T00:00:00Z");
+    assert_eq!(report["fingerprint"], ReceiptVerifier::fingerprint(&token));
+    assert_eq!(report["signature_present"], true);
+    assert_eq!(report["signature_note"], "present, not verified");
+}
+
+#[test]
+fn inspect_reports_malformed_receipts() {
+    let verifier = ReceiptVerifier::new(None);
+
+    let err = verifier.inspect("not-a-receipt").unwrap_err();
+    assert_eq!(err.0, "invalid_receipt_format");
+    let err = verifier.inspect("v1.%%%.sig").unwrap_err();
+    assert_eq!(err.0, "invalid_receipt_payload");
+    let unsigned = verifier.inspect("v1.e30.").unwrap();
+    assert_eq!(unsigned["signature_present"], false);
+    assert_eq!(unsigned["signature_note"], "missing");
+}
+
+#[test]
+fn signing_input_is_the_payload_segment_for_versioned_receipts() {
+    use alure_sdk::{Base64StdCodec, Base64UrlCodec};
+    use ed25519_dalek::Signer;
+    let claims = serde_json::json!({
+        "device_id_hash": common::device_hash(common::DEVICE_ID),
+        "note": ">>>",
+    });
+    let verifier =
+        ReceiptVerifier::new(Some(common::public_key_pem())).with_codec("v2", Base64StdCodec);
+    for token in [
+        sign_with("v1", &Base64UrlCodec, claims.clone()),
+        sign_with("v2", &Base64StdCodec, claims.clone()),
+    ] {
+        let payload = token.split('.').nth(1).unwrap();
+        let input = verifier.signing_input(&token).unwrap();
+        assert_eq!(input, payload.as_bytes());
+
+        // Signing exactly these bytes yields the same receipt.
+        let version = token.split('.').next().unwrap();
+        let codec: &dyn alure_sdk::TokenCodec = match version {
+            "v1" => &Base64UrlCodec,
+            _ => &Base64StdCodec,
+        };
+        let signature = codec.encode(&common::signing_key().sign(&input).to_bytes());
+        assert_eq!(format!("{version}.{payload}.{signature}"), token);
+    }
+}
+
+#[test]
+fn signing_input_is_header_and_payload_for_jwts() {
+    let token = jwt(serde_json::json!({"sub": common::DEVICE_ID}));
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    let (signed, _signature) = token.rsplit_once('.').unwrap();
+    assert_eq!(verifier.signing_input(&token).unwrap(), signed.as_bytes());
+    assert_eq!(
+        verifier.signing_input("v9.abc.def").unwrap_err().0,
+        "invalid_receipt_format"
+    );
+}
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn sign_gzipped(header_segment: &str, payload: &[u8]) -> String {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    use ed25519_dalek::Signer;
+    let payload = URL_SAFE_NO_PAD.encode(gzip(payload));
+    let signed = match header_segment {
+        "v1+gzip" => payload.clone(),
+        header => format!("{header}.{payload}"),
+    };
+    let signature = common::signing_key().sign(signed.as_bytes());
+    format!(
+        "{header_segment}.{payload}.{}",
+        URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    )
+}
+
+#[test]
+fn gzipped_receipts_parse_to_the_same_claims() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    let claims = serde_json::json!({
+        "license_id": "lic-1",
+        "device_id_hash": common::device_hash(common::DEVICE_ID),
+        "features": (0..200).map(|idx| format!("feature-{idx}")).collect::<Vec<_>>(),
+    });
+    let plain = common::sign_receipt(&claims);
+    let compressed = sign_gzipped("v1+gzip", claims.to_string().as_bytes());
+    assert!(compressed.len() < plain.len());
+
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    assert_eq!(
+        verifier.parse(&compressed).unwrap(),
+        verifier.parse(&plain).unwrap()
+    );
+    assert!(
+        verifier
+            .validate_offline(&compressed, common::DEVICE_ID, true)
+            .valid
+    );
+
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"EdDSA","typ":"JWT","zip":"gzip"}"#);
+    let jwt = sign_gzipped(&header, claims.to_string().as_bytes());
+    assert_eq!(
+        verifier.parse(&jwt).unwrap()["features"],
+        claims["features"]
+    );
+    assert!(
+        verifier
+            .validate_offline(&jwt, common::DEVICE_ID, true)
+            .valid
+    );
+}
+
+#[test]
+fn oversized_gzipped_payload_is_rejected() {
+    let bomb = format!(r#"{{"pad":"{}"}}"#, "0".repeat(2 * 1024 * 1024));
+    let token = sign_gzipped("v1+gzip", bomb.as_bytes());
+    let verifier = ReceiptVerifier::new(Some(common::public_key_pem()));
+    assert_eq!(
+        verifier.parse(&token).unwrap_err().0,
+        "invalid_receipt_payload"
+    );
+}
+
+#[test]
+fn verifier_clock_drives_expiry_transitions() {
+    let clock = alure_sdk::TestClock::new("2026-03-09T00:00:00Z".parse().unwrap());
+    let verifier = ReceiptVerifier::new(Some(public_key_pem())).with_clock(clock.clone());
+    let receipt = common::receipt_for(common::DEVICE_ID, Some("2026-03-10T00:00:00Z"), 2);
+    let validate = || verifier.validate_offline(&receipt, common::DEVICE_ID, true);
+
+    let active = validate();
+    assert!(active.is_active(), "{active:?}");
+
+    clock.advance(chrono::Duration::days(2));
+    let grace = validate();
+    assert!(grace.valid);
+    assert_eq!(grace.reason.as_deref(), Some("grace_period"));
+
+    clock.advance(chrono::Duration::days(2));
+    let expired = validate();
+    assert!(!expired.valid);
+    assert_eq!(expired.reason.as_deref(), Some("expired"));
+
+    // `validate_offline_at` ignores the clock.
+    let at = "2026-03-01T00:00:00Z".parse().unwrap();
+    assert!(verifier
+        .validate_offline_at(&receipt, common::DEVICE_ID, at, true)
+        .is_active());
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use alure_sdk::{Transport, TransportFuture, TransportRequest, TransportResponse};
+use common::{receipt_for, DEVICE_ID};
+use wiremock::matchers::{body_partial_json, header_exists, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// The old host answers every call under `/api/v1` with a 308 to the same
+/// route under `/v2` on the new host.
+async fn moved_servers() -> (MockServer, MockServer) {
+    let old = MockServer::start().await;
+    let new = MockServer::start().await;
+    for route in ["/licenses/activate", "/licenses/check"] {
+        Mock::given(path(format!("/api/v1{route}")))
+            .respond_with(
+                ResponseTemplate::new(308)
+                    .insert_header("Location", format!("{}/v2{route}", new.uri()).as_str()),
+            )
+            .mount(&old)
+            .await;
+    }
+    Mock::given(method("POST"))
+        .and(path("/v2/licenses/activate"))
+        .and(body_partial_json(serde_json::json!({
+            "license_key": "KEY",
+            "device_id": DEVICE_ID,
+        })))
+        .and(header_exists("Idempotency-Key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "receipt": receipt_for(DEVICE_ID, None, 0),
+            "activation_id": "act-1",
+        })))
+        .mount(&new)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v2/licenses/check"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": true})))
+        .mount(&new)
+        .await;
+    (old, new)
+}
+
+#[tokio::test]
+async fn permanent_redirect_on_activate_keeps_method_and_body() {
+    let (old, new) = moved_servers().await;
+    let (client, _dir) = common::client(Some(format!("{}/api/v1", old.uri())));
+
+    let response = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    assert_eq!(response.activation_id, "act-1");
+    client.check_license("KEY").await.unwrap();
+
+    // Without relocation every call still starts at the old host.
+    assert_eq!(client.current_base_url(), format!("{}/api/v1", old.uri()));
+    assert_eq!(old.received_requests().await.unwrap().len(), 2);
+    let received = new.received_requests().await.unwrap();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].method.as_str(), "POST");
+}
+
+#[tokio::test]
+async fn relocation_moves_the_base_url_and_notifies() {
+    let (old, new) = moved_servers().await;
+    let (client, _dir) = common::client(Some(format!("{}/api/v1", old.uri())));
+    let moves = Arc::new(Mutex::new(Vec::new()));
+    let seen = moves.clone();
+    let client = client.with_base_url_relocation(move |from, to| {
+        seen.lock()
+            .unwrap()
+            .push((from.to_string(), to.to_string()));
+    });
+
+    client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    client.check_license("KEY").await.unwrap();
+
+    assert_eq!(client.current_base_url(), format!("{}/v2", new.uri()));
+    assert_eq!(
+        *moves.lock().unwrap(),
+        vec![(format!("{}/api/v1", old.uri()), format!("{}/v2", new.uri()))]
+    );
+    assert_eq!(old.received_requests().await.unwrap().len(), 1);
+    assert_eq!(new.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn see_other_turns_a_post_into_a_get() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/check"))
+        .respond_with(
+            ResponseTemplate::new(303).insert_header("Location", "/licenses/check/result"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/licenses/check/result"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"valid": true})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let check = client.check_license("KEY").await.unwrap();
+    assert!(check.valid);
+}
+
+/// Answers every https call with a 308 to the same URL over plain http and
+/// records what it was sent.
+#[derive(Debug, Clone, Default)]
+struct Downgrading {
+    seen: Arc<Mutex<Vec<String>>>,
+}
+
+impl Transport for Downgrading {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        self.seen.lock().unwrap().push(request.url.clone());
+        let mut headers = http::HeaderMap::new();
+        let location = request.url.replacen("https://", "http://", 1);
+        headers.insert("location", location.parse().unwrap());
+        Box::pin(async move {
+            Ok(TransportResponse {
+                status: 308,
+                headers,
+                body: Vec::new(),
+            })
+        })
+    }
+}
+
+#[tokio::test]
+async fn redirect_from_https_to_http_is_refused() {
+    let transport = Downgrading::default();
+    let (client, _dir) = common::client(Some("https://licenses.example.com/api/v1".to_string()));
+    let moves = Arc::new(Mutex::new(Vec::<String>::new()));
+    let seen = moves.clone();
+    let client = client
+        .with_transport(transport.clone())
+        .with_base_url_relocation(move |_, to| seen.lock().unwrap().push(to.to_string()));
+
+    let err = client
+        .activate("KEY", Some(DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("insecure_redirect"), "{err}");
+    assert_eq!(
+        *transport.seen.lock().unwrap(),
+        vec!["https://licenses.example.com/api/v1/licenses/activate".to_string()]
+    );
+    assert!(moves.lock().unwrap().is_empty());
+    assert_eq!(
+        client.current_base_url(),
+        "https://licenses.example.com/api/v1"
+    );
+}
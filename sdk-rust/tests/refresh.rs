@@ -0,0 +1,263 @@
+mod common;
+
+use std::time::Duration;
+
+use alure_sdk::{FileStorage, ReceiptRecord};
+use common::{client, receipt_for, DEVICE_ID};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn store(dir: &std::path::Path, receipt: String) -> FileStorage {
+    let storage = FileStorage::new(Some(dir.to_path_buf())).unwrap();
+    storage
+        .save_receipt(&ReceiptRecord {
+            receipt,
+            device_id: DEVICE_ID.to_string(),
+            activation_id: Some("act-1".to_string()),
+            project_id: None,
+            ..Default::default()
+        })
+        .unwrap();
+    storage
+}
+
+#[tokio::test]
+async fn refresh_task_publishes_updates_and_stores_new_receipt() {
+    let server = MockServer::start().await;
+    let renewed = receipt_for(DEVICE_ID, Some("2099-06-01T00:00:00Z"), 3);
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "valid": true,
+            "new_receipt": renewed,
+            "server_time": "2026-01-01T00:00:00Z",
+        })))
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+    let storage = store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+
+    let mut task = client.spawn_refresh_task(Duration::from_millis(20));
+    assert_eq!(
+        task.status.borrow().expires_at.as_deref(),
+        Some("2099-01-01T00:00:00Z")
+    );
+    tokio::time::timeout(Duration::from_secs(5), task.status.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    let status = task.status.borrow_and_update().clone();
+    assert!(status.valid);
+    assert_eq!(status.expires_at.as_deref(), Some("2099-06-01T00:00:00Z"));
+    assert_eq!(storage.load_receipt().unwrap().unwrap().receipt, renewed);
+    task.stop();
+}
+
+#[tokio::test]
+async fn refresh_rejects_a_revoked_status_even_when_valid_is_set() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "valid": true,
+            "status": "revoked",
+            "server_time": "2026-01-01T00:00:00Z",
+        })))
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+
+    let result = client.refresh(None).await.unwrap();
+    assert!(!result.valid);
+    assert_eq!(result.reason.as_deref(), Some("revoked"));
+}
+
+#[tokio::test]
+async fn refresh_task_publishes_server_rejection() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "valid": false,
+            "reason": "revoked",
+            "server_time": "2026-01-01T00:00:00Z",
+        })))
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+
+    let mut task = client.spawn_refresh_task(Duration::from_millis(20));
+    tokio::time::timeout(Duration::from_secs(5), task.status.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    let status = task.status.borrow().clone();
+    assert!(!status.valid);
+    assert_eq!(status.reason.as_deref(), Some("revoked"));
+    task.stop();
+}
+
+async fn advance(
+    clock: &common::TestClock,
+    status: &mut tokio::sync::watch::Receiver<alure_sdk::ReceiptValidationResult>,
+    to: &str,
+) -> alure_sdk::ReceiptValidationResult {
+    clock.set(to);
+    tokio::time::timeout(Duration::from_secs(5), status.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    status.borrow_and_update().clone()
+}
+
+#[tokio::test]
+async fn expiry_watch_reports_grace_and_expiry_transitions() {
+    let clock = common::TestClock::at("2026-03-01T00:00:00Z");
+    let (client, dir) = client(None);
+    let client = client
+        .with_clock(clock.clone())
+        .with_status_recheck_interval(Duration::from_millis(10));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-03-10T00:00:00Z"), 2),
+    );
+
+    let mut watch = client.expiry_watch();
+    assert!(watch.status.borrow().valid);
+    assert_eq!(watch.status.borrow().reason, None);
+
+    let grace = advance(&clock, &mut watch.status, "2026-03-11T00:00:00Z").await;
+    assert!(grace.valid);
+    assert_eq!(grace.reason.as_deref(), Some("grace_period"));
+    let expired = advance(&clock, &mut watch.status, "2026-03-13T00:00:00Z").await;
+    assert!(!expired.valid);
+    assert_eq!(expired.reason.as_deref(), Some("expired"));
+
+    watch.stop();
+}
+
+async fn next_status(
+    stream: &mut (impl futures_util::Stream<Item = alure_sdk::ReceiptValidationResult> + Unpin),
+) -> alure_sdk::ReceiptValidationResult {
+    use futures_util::StreamExt;
+    tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn status_stream_yields_each_transition_once() {
+    use futures_util::StreamExt;
+    let clock = common::TestClock::at("2026-03-01T00:00:00Z");
+    let (client, dir) = client(None);
+    let client = client
+        .with_clock(clock.clone())
+        .with_status_recheck_interval(Duration::from_millis(10));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2026-03-10T00:00:00Z"), 2),
+    );
+
+    let mut stream = Box::pin(client.status_stream());
+    assert!(next_status(&mut stream).await.is_active());
+
+    clock.set("2026-03-11T00:00:00Z");
+    let grace = next_status(&mut stream).await;
+    assert!(grace.is_in_grace());
+    // Unchanged state across several timer ticks is not yielded again.
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), stream.next())
+            .await
+            .is_err()
+    );
+
+    clock.set("2026-03-13T00:00:00Z");
+    let expired = next_status(&mut stream).await;
+    assert!(!expired.valid);
+    assert_eq!(expired.reason.as_deref(), Some("expired"));
+}
+
+#[tokio::test]
+async fn refresh_status_reverifies_online() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/licenses/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "valid": false,
+            "reason": "revoked",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let (client, dir) = client(Some(server.uri()));
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 7),
+    );
+
+    let mut stream = Box::pin(client.status_stream());
+    assert!(next_status(&mut stream).await.is_active());
+
+    client.clone().refresh_status();
+    let revoked = next_status(&mut stream).await;
+    assert!(!revoked.valid);
+    assert_eq!(revoked.reason.as_deref(), Some("revoked"));
+}
+
+async fn sender_closed(
+    status: &mut tokio::sync::watch::Receiver<alure_sdk::ReceiptValidationResult>,
+) -> bool {
+    // Only the closing of the channel can end this wait: the receipt never
+    // changes state in these tests.
+    matches!(
+        tokio::time::timeout(Duration::from_secs(5), status.changed()).await,
+        Ok(Err(_))
+    )
+}
+
+#[tokio::test]
+async fn dropping_the_client_stops_background_tasks() {
+    let (client, dir) = client(None);
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 0),
+    );
+    let mut watch = client.expiry_watch();
+    let mut task = client.spawn_refresh_task(Duration::from_secs(3600));
+
+    drop(client);
+
+    assert!(sender_closed(&mut watch.status).await);
+    assert!(sender_closed(&mut task.status).await);
+    assert!(task.handle.await.unwrap_err().is_cancelled());
+}
+
+#[tokio::test]
+async fn background_tasks_live_until_the_last_clone_is_dropped() {
+    let (client, dir) = client(None);
+    store(
+        dir.path(),
+        receipt_for(DEVICE_ID, Some("2099-01-01T00:00:00Z"), 0),
+    );
+    let mut watch = client.expiry_watch();
+    let clone = client.clone();
+
+    drop(client);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!watch.handle.is_finished());
+
+    drop(clone);
+    assert!(sender_closed(&mut watch.status).await);
+}
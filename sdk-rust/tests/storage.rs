@@ -0,0 +1,263 @@
+use std::time::{Duration, SystemTime};
+
+use alure_sdk::FileStorage;
+use tempfile::TempDir;
+
+fn write_aged(path: &std::path::Path, age: Duration) {
+    std::fs::write(path, b"data").unwrap();
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() - age).unwrap();
+}
+
+#[test]
+fn cleanup_downloads_removes_stale_entries_only() {
+    let dir = TempDir::new().unwrap();
+    let storage = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+    let downloads = storage.downloads_dir().unwrap();
+    let day = Duration::from_secs(86_400);
+    write_aged(&downloads.join("app-1.0.bin"), day * 30);
+    write_aged(&downloads.join("app-2.0.bin"), day);
+    write_aged(&downloads.join("app-2.1.bin.part"), day * 10);
+    write_aged(&downloads.join("app-2.2.bin.part"), Duration::ZERO);
+
+    let removed = storage
+        .cleanup_downloads(|entry| {
+            let age = SystemTime::now().duration_since(entry.modified).unwrap();
+            age < day * 7
+        })
+        .unwrap();
+    assert_eq!(removed.len(), 2);
+
+    let remaining: Vec<_> = storage
+        .list_downloads()
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.path.file_name().unwrap().to_owned(), entry.partial))
+        .collect();
+    assert_eq!(
+        remaining,
+        vec![
+            ("app-2.0.bin".into(), false),
+            ("app-2.2.bin.part".into(), true),
+        ]
+    );
+}
+
+#[test]
+fn named_receipt_files_do_not_clobber_each_other() {
+    use alure_sdk::ReceiptRecord;
+    let dir = TempDir::new().unwrap();
+    let record = |receipt: &str| ReceiptRecord {
+        receipt: receipt.to_string(),
+        device_id: "device-123".to_string(),
+        activation_id: None,
+        project_id: None,
+        ..Default::default()
+    };
+    let default = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+    let editor = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .with_filename("editor.json");
+    let viewer = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .with_filename("viewer.json");
+    assert_eq!(default.receipts_path(), dir.path().join("receipt.json"));
+    assert_eq!(editor.receipts_path(), dir.path().join("editor.json"));
+
+    editor.save_receipt(&record("v1.editor.sig")).unwrap();
+    viewer.save_receipt(&record("v1.viewer.sig")).unwrap();
+    assert_eq!(
+        editor.load_receipt().unwrap().unwrap().receipt,
+        "v1.editor.sig"
+    );
+    assert_eq!(
+        viewer.load_receipt().unwrap().unwrap().receipt,
+        "v1.viewer.sig"
+    );
+    assert!(default.load_receipt().unwrap().is_none());
+}
+
+#[test]
+fn open_existing_does_not_create_missing_dir() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("not-there");
+    let storage = FileStorage::open_existing(Some(missing.clone())).unwrap();
+
+    assert!(storage.load_receipt().unwrap().is_none());
+    assert!(storage.load_online_verification().unwrap().is_none());
+    assert!(storage.list_downloads().unwrap().is_empty());
+    assert_eq!(storage.downloads_dir().unwrap(), missing.join("downloads"));
+    assert!(storage.delete_receipt().is_ok());
+    assert!(!missing.exists());
+
+    let err = storage
+        .save_receipt(&alure_sdk::ReceiptRecord {
+            receipt: "r".to_string(),
+            device_id: "d".to_string(),
+            activation_id: None,
+            project_id: None,
+            ..Default::default()
+        })
+        .unwrap_err();
+    assert!(err.0.starts_with("write_failed"), "{err}");
+    assert!(!missing.exists());
+}
+
+#[test]
+fn extra_fields_survive_save_and_load() {
+    let dir = TempDir::new().unwrap();
+    let storage = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+    let mut record = alure_sdk::ReceiptRecord {
+        receipt: "v1.payload.sig".to_string(),
+        device_id: "device-123".to_string(),
+        ..Default::default()
+    };
+    record
+        .extra
+        .insert("tier".to_string(), serde_json::json!("pro"));
+    record
+        .extra
+        .insert("seats".to_string(), serde_json::json!({"used": 2}));
+
+    storage.save_receipt(&record).unwrap();
+    let loaded = storage.load_receipt().unwrap().unwrap();
+
+    assert_eq!(loaded.extra, record.extra);
+}
+
+#[test]
+fn hooks_transform_records_on_save_and_load() {
+    let dir = TempDir::new().unwrap();
+    let storage = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .with_before_save(|record| {
+            record
+                .extra
+                .insert("install".to_string(), serde_json::json!("local-1"));
+            record.device_id = record.device_id.chars().rev().collect();
+            Ok(())
+        })
+        .with_after_load(|record| {
+            record.device_id = record.device_id.chars().rev().collect();
+            Ok(())
+        });
+    let record = alure_sdk::ReceiptRecord {
+        receipt: "v1.payload.sig".to_string(),
+        device_id: "device-123".to_string(),
+        ..Default::default()
+    };
+
+    storage.save_receipt(&record).unwrap();
+
+    let on_disk = std::fs::read_to_string(storage.receipts_path()).unwrap();
+    assert!(on_disk.contains("321-ecived"), "{on_disk}");
+    let loaded = storage.load_receipt().unwrap().unwrap();
+    assert_eq!(loaded.device_id, "device-123");
+    assert_eq!(loaded.extra["install"], "local-1");
+
+    let failing = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .with_before_save(|_| Err(alure_sdk::StorageError("encrypt_failed".to_string())));
+    assert_eq!(
+        failing.save_receipt(&record).unwrap_err().0,
+        "encrypt_failed"
+    );
+}
+
+#[test]
+fn client_exposes_its_storage_and_downloads_dirs() {
+    let dir = TempDir::new().unwrap();
+    let client =
+        alure_sdk::AlureClient::new(None, Some(dir.path().to_path_buf()), None, None).unwrap();
+
+    assert_eq!(client.storage_dir(), dir.path());
+    let downloads = client.downloads_dir().unwrap();
+    assert_eq!(downloads, dir.path().join("downloads"));
+    assert!(downloads.is_dir());
+}
+
+#[test]
+fn client_cleanup_measures_age_with_the_client_clock() {
+    let dir = TempDir::new().unwrap();
+    let clock = alure_sdk::TestClock::new(chrono::Utc::now());
+    let client = alure_sdk::AlureClient::new(None, Some(dir.path().to_path_buf()), None, None)
+        .unwrap()
+        .with_clock(clock.clone());
+    let downloads = client.downloads_dir().unwrap();
+    write_aged(&downloads.join("app-1.0.bin"), Duration::ZERO);
+    let week = Duration::from_secs(7 * 86_400);
+
+    assert!(client.cleanup_downloads(week).unwrap().is_empty());
+    clock.advance(chrono::Duration::days(8));
+    assert_eq!(
+        client.cleanup_downloads(week).unwrap(),
+        vec![downloads.join("app-1.0.bin")]
+    );
+    let storage = FileStorage::new(Some(dir.path().to_path_buf())).unwrap();
+    assert!(storage.list_downloads().unwrap().is_empty());
+}
+
+fn formats() -> Vec<alure_sdk::StorageFormat> {
+    vec![
+        alure_sdk::StorageFormat::Json,
+        alure_sdk::StorageFormat::CompactJson,
+        #[cfg(feature = "msgpack")]
+        alure_sdk::StorageFormat::MessagePack,
+    ]
+}
+
+#[test]
+fn records_round_trip_in_every_format() {
+    let mut record = alure_sdk::ReceiptRecord {
+        receipt: "v1.payload.sig".to_string(),
+        device_id: "device-123".to_string(),
+        activation_id: Some("act-1".to_string()),
+        project_id: None,
+        ..Default::default()
+    };
+    record
+        .extra
+        .insert("seats".to_string(), serde_json::json!({"used": 2}));
+
+    for format in formats() {
+        let dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(Some(dir.path().to_path_buf()))
+            .unwrap()
+            .with_format(format);
+        storage.save_receipt(&record).unwrap();
+
+        // Loading detects the encoding, whatever format is configured.
+        for reader in formats() {
+            let loaded = FileStorage::new(Some(dir.path().to_path_buf()))
+                .unwrap()
+                .with_format(reader)
+                .load_receipt()
+                .unwrap()
+                .unwrap();
+            assert_eq!(loaded.receipt, record.receipt, "{format:?} -> {reader:?}");
+            assert_eq!(loaded.activation_id, record.activation_id);
+            assert_eq!(loaded.project_id, None);
+            assert_eq!(loaded.extra, record.extra);
+        }
+    }
+}
+
+#[test]
+fn compact_json_has_no_whitespace() {
+    let dir = TempDir::new().unwrap();
+    let storage = FileStorage::new(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .with_format(alure_sdk::StorageFormat::CompactJson);
+    storage
+        .save_receipt(&alure_sdk::ReceiptRecord {
+            receipt: "v1.payload.sig".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let content = std::fs::read_to_string(storage.receipts_path()).unwrap();
+    assert!(
+        !content.contains('\n') && !content.contains(": "),
+        "{content}"
+    );
+}
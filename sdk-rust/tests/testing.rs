@@ -0,0 +1,95 @@
+#![cfg(feature = "testing")]
+
+use alure_sdk::testing::device_id_from_components;
+use alure_sdk::{AlureClient, ReceiptVerifier, TestReceiptBuilder};
+use tempfile::TempDir;
+
+const DEVICE_ID: &str = "device-123";
+
+#[test]
+fn built_receipt_verifies_offline() {
+    let builder = TestReceiptBuilder::from_seed([3; 32])
+        .device_id(DEVICE_ID)
+        .expires_at("2099-01-01T00:00:00Z")
+        .grace_period_days(7)
+        .features(["export", "sync"]);
+    let receipt = builder.build();
+    assert!(receipt.starts_with("v1."));
+
+    let verifier = ReceiptVerifier::new(Some(builder.public_key_pem()));
+    let result = verifier.validate_offline(&receipt, DEVICE_ID, true);
+    assert!(result.valid, "{result:?}");
+    assert_eq!(result.grace_period_days, Some(7));
+    let claims = verifier.parse(&receipt).unwrap();
+    assert_eq!(claims["features"], serde_json::json!(["export", "sync"]));
+    assert_eq!(claims, builder.claims());
+}
+
+#[test]
+fn built_receipt_fails_the_checks_it_should() {
+    let builder = TestReceiptBuilder::from_seed([3; 32])
+        .device_id(DEVICE_ID)
+        .expires_at("2000-01-01T00:00:00Z");
+    let verifier = ReceiptVerifier::new(Some(builder.public_key_pem()));
+    let expired = verifier.validate_offline(&builder.build(), DEVICE_ID, true);
+    assert_eq!(expired.reason.as_deref(), Some("expired"));
+    let other_device = verifier.validate_offline(&builder.build(), "other", true);
+    assert_eq!(other_device.reason.as_deref(), Some("device_mismatch"));
+
+    let other_key = ReceiptVerifier::new(Some(
+        TestReceiptBuilder::from_seed([4; 32]).public_key_pem(),
+    ));
+    let forged = other_key.validate_offline(&builder.build(), DEVICE_ID, true);
+    assert!(!forged.valid);
+}
+
+#[test]
+fn client_accepts_built_receipt() {
+    let builder = TestReceiptBuilder::from_seed([3; 32]).device_id(DEVICE_ID);
+    let dir = TempDir::new().unwrap();
+    let client = AlureClient::new(
+        None,
+        Some(dir.path().to_path_buf()),
+        Some(builder.public_key_pem()),
+        None,
+    )
+    .unwrap();
+
+    let result = client
+        .verify_offline(Some(builder.build()), Some(DEVICE_ID.to_string()), true)
+        .unwrap();
+    assert!(result.valid, "{result:?}");
+}
+
+fn failing(name: &'static str) -> (&'static str, Result<String, String>) {
+    (name, Err("not permitted".to_string()))
+}
+
+#[test]
+fn failing_hostname_still_yields_deterministic_id() {
+    let components = || {
+        vec![
+            failing("hostname"),
+            ("mac_address", Ok("aa:bb:cc:dd:ee:ff".to_string())),
+            ("username", Ok("alice".to_string())),
+        ]
+    };
+    let first = device_id_from_components(&uuid::Uuid::NAMESPACE_DNS, components()).unwrap();
+    assert_eq!(
+        first,
+        device_id_from_components(&uuid::Uuid::NAMESPACE_DNS, components()).unwrap()
+    );
+    let raw = "unavailable-hostname-aa:bb:cc:dd:ee:ff-alice";
+    let expected = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_DNS, raw.as_bytes());
+    assert_eq!(first, expected.to_string());
+}
+
+#[test]
+fn device_id_fails_only_when_nothing_is_available() {
+    let err = device_id_from_components(
+        &uuid::Uuid::NAMESPACE_DNS,
+        vec![failing("hostname"), failing("username")],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("device_id_unavailable"));
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use std::future::{ready, Ready};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use alure_sdk::{AlureError, TowerTransport};
+use tower_service::Service;
+
+#[derive(Clone, Default)]
+struct FakeServer {
+    seen: Arc<Mutex<Vec<http::Request<Vec<u8>>>>>,
+}
+
+impl Service<http::Request<Vec<u8>>> for FakeServer {
+    type Response = http::Response<Vec<u8>>;
+    type Error = std::io::Error;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Vec<u8>>) -> Self::Future {
+        let response = match request.uri().path() {
+            "/api/v1/licenses/activate" => http::Response::builder().status(200).body(
+                serde_json::to_vec(&serde_json::json!({
+                    "receipt": common::receipt_for(common::DEVICE_ID, None, 7),
+                    "activation_id": "act-1",
+                    "expires_at": null,
+                    "grace_period_days": 7,
+                    "server_time": "2026-01-01T00:00:00Z",
+                }))
+                .unwrap(),
+            ),
+            _ => http::Response::builder()
+                .status(404)
+                .body(br#"{"statusCode":404,"message":"license_not_found"}"#.to_vec()),
+        };
+        self.seen.lock().unwrap().push(request);
+        ready(Ok(response.unwrap()))
+    }
+}
+
+#[tokio::test]
+async fn activate_runs_through_tower_service() {
+    let service = FakeServer::default();
+    let (client, _dir) = common::client(Some("http://licensing.invalid/api/v1".to_string()));
+    let client = client.with_transport(TowerTransport::new(service.clone()));
+
+    let response = client
+        .activate("KEY", Some(common::DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(response.activation_id, "act-1");
+    let seen = service.seen.lock().unwrap();
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen[0].method(), http::Method::POST);
+    assert_eq!(seen[0].headers()["content-type"], "application/json");
+    assert!(seen[0].headers().contains_key("x-request-id"));
+    let body: serde_json::Value = serde_json::from_slice(seen[0].body()).unwrap();
+    assert_eq!(body["license_key"], "KEY");
+}
+
+#[tokio::test]
+async fn tower_error_statuses_map_to_license_errors() {
+    let (client, _dir) = common::client(Some("http://licensing.invalid/api/v1".to_string()));
+    let client = client.with_transport(TowerTransport::new(FakeServer::default()));
+
+    let err = client.check_license("KEY").await.unwrap_err();
+
+    assert!(matches!(err, AlureError::License { .. }));
+    assert!(err.request_id().is_some());
+}
+
+/// Times out the first call after "processing" it, as a server whose reply
+/// got lost would, and resends the same request, as a retry layer does.
+#[derive(Clone, Default)]
+struct RetryOnce {
+    inner: FakeServer,
+}
+
+impl Service<http::Request<Vec<u8>>> for RetryOnce {
+    type Response = http::Response<Vec<u8>>;
+    type Error = std::io::Error;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<Vec<u8>>) -> Self::Future {
+        let mut retry = http::Request::builder()
+            .method(request.method().clone())
+            .uri(request.uri().clone())
+            .body(request.body().clone())
+            .unwrap();
+        *retry.headers_mut() = request.headers().clone();
+        let _lost = self.inner.call(request);
+        self.inner.call(retry)
+    }
+}
+
+#[tokio::test]
+async fn activation_retries_reuse_the_idempotency_key() {
+    let service = RetryOnce::default();
+    let (client, _dir) = common::client(Some("http://licensing.invalid/api/v1".to_string()));
+    let client = client.with_transport(TowerTransport::new(service.clone()));
+
+    let response = client
+        .activate("KEY", Some(common::DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+
+    let seen = service.inner.seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    let key = response.idempotency_key.expect("idempotency key");
+    assert!(uuid::Uuid::parse_str(&key).is_ok(), "{key}");
+    for request in seen.iter() {
+        assert_eq!(request.headers()["idempotency-key"], key.as_str());
+    }
+}
+
+#[tokio::test]
+async fn each_activation_call_gets_its_own_idempotency_key() {
+    let service = FakeServer::default();
+    let (client, _dir) = common::client(Some("http://licensing.invalid/api/v1".to_string()));
+    let client = client.with_transport(TowerTransport::new(service.clone()));
+
+    let first = client
+        .activate("KEY", Some(common::DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+    let second = client
+        .activate("KEY", Some(common::DEVICE_ID.to_string()), None, None)
+        .await
+        .unwrap();
+
+    assert_ne!(first.idempotency_key, second.idempotency_key);
+}
@@ -0,0 +1,263 @@
+mod common;
+
+use alure_sdk::{AlureError, UpdateCheck};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn latest_server(channel: &str, response: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/updates/latest"))
+        .and(query_param("channel", channel))
+        .respond_with(response)
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn up_to_date_when_no_update_is_available() {
+    let server = latest_server(
+        "stable",
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "update_available": false,
+            "server_time": "2026-01-01T00:00:00Z",
+        })),
+    )
+    .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let check = client
+        .check_update("proj-1", "stable", Some("1.2.0".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        check,
+        UpdateCheck::UpToDate {
+            current_version: Some("1.2.0".to_string()),
+            server_time: Some("2026-01-01T00:00:00Z".to_string()),
+        }
+    );
+}
+
+#[tokio::test]
+async fn up_to_date_when_latest_matches_current_version() {
+    let server = latest_server(
+        "stable",
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "update_available": true,
+            "latest_version": "1.2.0",
+        })),
+    )
+    .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let check = client
+        .check_update("proj-1", "stable", Some("1.2.0".to_string()))
+        .await
+        .unwrap();
+
+    assert!(check.is_up_to_date());
+}
+
+#[tokio::test]
+async fn newer_release_is_available() {
+    let server = latest_server(
+        "beta",
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "update_available": true,
+            "latest_version": "1.3.0",
+            "release_notes": "Bug fixes",
+            "release": {
+                "release_id": "rel-9",
+                "version": "1.3.0",
+                "channel": "beta",
+                "published_at": "2026-02-01T00:00:00Z",
+            },
+            "asset": {
+                "asset_id": "asset-1",
+                "filename": "app.bin",
+                "size_bytes": 15,
+                "sha256": "deadbeef",
+            },
+            "server_time": "2026-02-02T00:00:00Z",
+        })),
+    )
+    .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let check = client
+        .check_update("proj-1", "beta", Some("1.2.0".to_string()))
+        .await
+        .unwrap();
+
+    let update = check.update().expect("update available");
+    assert_eq!(update.latest_version, "1.3.0");
+    assert_eq!(update.release_notes.as_deref(), Some("Bug fixes"));
+    assert_eq!(update.release.as_ref().unwrap().release_id, "rel-9");
+    let asset = update.asset.as_ref().unwrap();
+    assert_eq!(asset.asset_id, "asset-1");
+    assert_eq!(asset.size_bytes, Some(15));
+    assert!(asset.download_url.is_none());
+}
+
+#[tokio::test]
+async fn unknown_channel_is_update_not_found() {
+    let server = latest_server(
+        "nightly",
+        ResponseTemplate::new(404)
+            .insert_header("x-request-id", "req-404")
+            .set_body_json(serde_json::json!({
+                "statusCode": 404,
+                "message": "Channel not found",
+            })),
+    )
+    .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let err = client
+        .check_update("proj-1", "nightly", None)
+        .await
+        .unwrap_err();
+
+    match &err {
+        AlureError::UpdateNotFound {
+            project_id,
+            channel,
+            ..
+        } => {
+            assert_eq!(project_id, "proj-1");
+            assert_eq!(channel, "nightly");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    assert_eq!(err.request_id(), Some("req-404"));
+}
+
+#[tokio::test]
+async fn check_updates_maps_each_channel() {
+    let server = MockServer::start().await;
+    for (channel, latest) in [("stable", "1.2.0"), ("beta", "1.3.0-beta.2")] {
+        Mock::given(method("GET"))
+            .and(path("/updates/latest"))
+            .and(query_param("channel", channel))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "update_available": true,
+                "latest_version": latest,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/updates/latest"))
+        .and(query_param("channel", "nightly"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+
+    let checks = client
+        .check_updates(
+            "proj-1",
+            &["stable", "beta", "nightly"],
+            Some("1.2.0".to_string()),
+        )
+        .await;
+
+    assert_eq!(checks.len(), 3);
+    assert!(checks["stable"].as_ref().unwrap().is_up_to_date());
+    let beta = checks["beta"].as_ref().unwrap().update().unwrap();
+    assert_eq!(beta.latest_version, "1.3.0-beta.2");
+    assert!(matches!(
+        checks["nightly"],
+        Err(AlureError::UpdateNotFound { ref channel, .. }) if channel == "nightly"
+    ));
+}
+
+/// Signs `manifest` the way the server does: over its compact JSON with
+/// sorted keys (`serde_json::Map` keeps keys sorted).
+fn signed_manifest(mut manifest: serde_json::Value) -> serde_json::Value {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use ed25519_dalek::Signer;
+    let signature = common::signing_key().sign(manifest.to_string().as_bytes());
+    manifest["signature"] = STANDARD.encode(signature.to_bytes()).into();
+    manifest
+}
+
+fn manifest() -> serde_json::Value {
+    serde_json::json!({
+        "update_available": true,
+        "latest_version": "2.0.0",
+        "asset": {
+            "asset_id": "asset-2",
+            "sha256": "aa".repeat(32),
+        },
+    })
+}
+
+#[tokio::test]
+async fn signed_update_manifest_is_accepted() {
+    let server = latest_server(
+        "stable",
+        ResponseTemplate::new(200).set_body_json(signed_manifest(manifest())),
+    )
+    .await;
+    let (client, _dir) = common::client(Some(server.uri()));
+    let client = client.with_signed_update_manifests(true);
+
+    let check = client
+        .check_update("proj-1", "stable", Some("1.0.0".to_string()))
+        .await
+        .unwrap();
+
+    let asset = check.update().unwrap().asset.as_ref().unwrap();
+    assert_eq!(asset.asset_id, "asset-2");
+    assert_eq!(asset.sha256, Some("aa".repeat(32)));
+}
+
+#[tokio::test]
+async fn update_manifest_with_swapped_asset_hash_is_rejected() {
+    let mut tampered = signed_manifest(manifest());
+    tampered["asset"]["sha256"] = "bb".repeat(32).into();
+    let server = latest_server("stable", ResponseTemplate::new(200).set_body_json(tampered)).await;
+    let (client, _dir) = common::client(Some(server.uri()));
+    let client = client.with_signed_update_manifests(true);
+
+    let err = client
+        .check_update("proj-1", "stable", Some("1.0.0".to_string()))
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(&err, AlureError::ResponseSignature { reason, .. } if reason == "signature_mismatch"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn unsigned_or_unbound_manifests_are_rejected_when_required() {
+    let mut unbound = manifest();
+    unbound["asset"].as_object_mut().unwrap().remove("sha256");
+    let cases = [
+        (manifest(), "missing_signature"),
+        (signed_manifest(unbound), "unbound_asset"),
+    ];
+    for (body, reason) in cases {
+        let server = latest_server("stable", ResponseTemplate::new(200).set_body_json(body)).await;
+        let (client, _dir) = common::client(Some(server.uri()));
+
+        let unchecked = client.clone().check_update("proj-1", "stable", None).await;
+        assert!(unchecked.is_ok());
+        let err = client
+            .with_signed_update_manifests(true)
+            .check_update("proj-1", "stable", None)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, AlureError::ResponseSignature { reason: got, .. } if got == reason),
+            "{err}"
+        );
+    }
+}
@@ -12,6 +12,8 @@ import {
   BulkCreateLicensesResponseDto,
   CreateLicenseRequestDto,
   CreateLicenseResponseDto,
+  DeactivateRequestDto,
+  DeactivateResponseDto,
   LicenseModulesResponseDto,
   LicenseListItemDto,
   RevokeRequestDto,
@@ -42,6 +44,11 @@ export class LicensingController {
     return this.licensingService.verify(body);
   }
 
+  @Post('deactivate')
+  async deactivate(@Body() body: DeactivateRequestDto): Promise<DeactivateResponseDto> {
+    return this.licensingService.deactivate(body);
+  }
+
   @Post('revoke')
   @UseGuards(AuthGuard)
   async revoke(@Req() req: { user?: { role: string } }, @Body() body: RevokeRequestDto): Promise<RevokeResponseDto> {
@@ -11,6 +11,8 @@ import {
   BulkCreateLicensesResponseDto,
   CreateLicenseRequestDto,
   CreateLicenseResponseDto,
+  DeactivateRequestDto,
+  DeactivateResponseDto,
   LicenseModulesResponseDto,
   LicenseListItemDto,
   RevokeRequestDto,
@@ -36,6 +38,11 @@ export class LicensingService {
     return createHash('sha256').update(value).digest('hex');
   }
 
+  /** Clients that hash device ids themselves send the hash as-is. */
+  private hashDeviceId(deviceId: string, isHash?: boolean): string {
+    return isHash ? deviceId.trim().toLowerCase() : this.hash(deviceId);
+  }
+
   private getDataKey(): string {
     return process.env.DATA_ENCRYPTION_KEY || process.env.JWT_SECRET || 'dev-secret';
   }
@@ -361,7 +368,7 @@ export class LicensingService {
   async activate(body: ActivateRequestDto): Promise<ActivateResponseDto> {
     const now = new Date();
     const licenseKeyHash = this.hash(body.license_key);
-    const deviceIdHash = this.hash(body.device_id);
+    const deviceIdHash = this.hashDeviceId(body.device_id, body.device_id_is_hash);
     const hostnameValue =
       typeof body.device_meta?.hostname === 'string' ? body.device_meta.hostname.trim() : null;
     const hostnameEnc = hostnameValue ? encryptData(hostnameValue, this.getDataKey()) : null;
@@ -472,7 +479,7 @@ export class LicensingService {
       if (
         activation.revoked ||
         activation.license.revoked ||
-        activation.deviceIdHash !== this.hash(body.device_id)
+        activation.deviceIdHash !== this.hashDeviceId(body.device_id, body.device_id_is_hash)
       ) {
         return {
           valid: false,
@@ -792,6 +799,38 @@ export class LicensingService {
     return { hostname };
   }
 
+  async deactivate(body: DeactivateRequestDto): Promise<DeactivateResponseDto> {
+    const now = new Date();
+    const licenseKeyHash = this.hash(body.license_key);
+
+    return this.prisma.$transaction(async (tx) => {
+      const license = await tx.license.findUnique({
+        where: { licenseKeyHash },
+      });
+
+      if (!license) {
+        throw new HttpException('license_not_found', HttpStatus.NOT_FOUND);
+      }
+
+      const activation = await tx.activation.findFirst({
+        where: { id: body.activation_id, licenseId: license.id },
+      });
+      if (!activation) {
+        throw new HttpException('activation_not_found', HttpStatus.NOT_FOUND);
+      }
+
+      await tx.activation.update({
+        where: { id: activation.id },
+        data: { revoked: true },
+      });
+
+      return {
+        deactivated: true,
+        server_time: now.toISOString(),
+      };
+    });
+  }
+
   async revoke(_body: RevokeRequestDto): Promise<RevokeResponseDto> {
     const now = new Date();
 
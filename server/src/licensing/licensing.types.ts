@@ -25,6 +25,13 @@ export class ActivateRequestDto {
   @IsNotEmpty()
   device_id: string;
 
+  @ApiPropertyOptional({
+    description: 'device_id is already the lowercase hex SHA-256 of the device id',
+  })
+  @IsBoolean()
+  @IsOptional()
+  device_id_is_hash?: boolean;
+
   @ApiPropertyOptional({ example: '1.0.0' })
   @IsString()
   @IsOptional()
@@ -69,6 +76,18 @@ export class VerifyRequestDto {
   @IsString()
   @IsNotEmpty()
   device_id: string;
+
+  @ApiPropertyOptional({
+    description: 'device_id is already the lowercase hex SHA-256 of the device id',
+  })
+  @IsBoolean()
+  @IsOptional()
+  device_id_is_hash?: boolean;
+
+  @ApiPropertyOptional({ type: Object })
+  @IsObject()
+  @IsOptional()
+  device_meta?: Record<string, unknown>;
 }
 
 export class VerifyResponseDto {
@@ -124,6 +143,29 @@ export class RevokeResponseDto {
   server_time?: string;
 }
 
+export class DeactivateRequestDto {
+  @ApiProperty({ example: 'ALR-AB12CD-34EF56-7890AB' })
+  @IsString()
+  @IsNotEmpty()
+  license_key: string;
+
+  @ApiProperty({ example: 'activation-id-456' })
+  @IsString()
+  @IsNotEmpty()
+  activation_id: string;
+}
+
+export class DeactivateResponseDto {
+  @ApiProperty()
+  @IsBoolean()
+  deactivated: boolean;
+
+  @ApiPropertyOptional()
+  @IsString()
+  @IsOptional()
+  server_time?: string;
+}
+
 export class CreateLicenseRequestDto {
   @ApiProperty({ example: 'project-demo' })
   @IsString()
@@ -38,6 +38,11 @@ export class UpdatesController {
   async createDownloadToken(
     @Body() body: DownloadTokenRequestDto,
   ): Promise<DownloadTokenResponseDto> {
-    return this.updatesService.createDownloadToken(body.asset_id, body.receipt, body.device_id);
+    return this.updatesService.createDownloadToken(
+      body.asset_id,
+      body.receipt,
+      body.device_id,
+      body.device_id_is_hash,
+    );
   }
 }
@@ -91,8 +91,13 @@ export class UpdatesService {
     assetId: string,
     receipt: string,
     deviceId: string,
+    deviceIdIsHash?: boolean,
   ): Promise<DownloadTokenResponseDto> {
-    const validation = await this.licensingService.verify({ receipt, device_id: deviceId });
+    const validation = await this.licensingService.verify({
+      receipt,
+      device_id: deviceId,
+      device_id_is_hash: deviceIdIsHash,
+    });
     if (!validation.valid) {
       throw new HttpException('license_invalid', HttpStatus.FORBIDDEN);
     }
@@ -1,5 +1,5 @@
-import { ApiProperty } from '@nestjs/swagger';
-import { IsNotEmpty, IsString } from 'class-validator';
+import { ApiProperty, ApiPropertyOptional } from '@nestjs/swagger';
+import { IsBoolean, IsNotEmpty, IsOptional, IsString } from 'class-validator';
 
 export class DownloadTokenRequestDto {
   @ApiProperty({ example: 'rcpt_v1...' })
@@ -12,6 +12,13 @@ export class DownloadTokenRequestDto {
   @IsNotEmpty()
   device_id: string;
 
+  @ApiPropertyOptional({
+    description: 'device_id is already the lowercase hex SHA-256 of the device id',
+  })
+  @IsBoolean()
+  @IsOptional()
+  device_id_is_hash?: boolean;
+
   @ApiProperty({ example: 'asset-id-123' })
   @IsString()
   @IsNotEmpty()